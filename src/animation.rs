@@ -7,60 +7,244 @@ use std::collections::hash_map::Entry;
 use std::sync::Arc;
 
 use Asset;
+use Channel;
+use Skeleton;
 use Source;
+use SourceLayer;
+use TargetAddress;
 use TreePrinter;
 
 use source::read_sources;
+use skeleton::find_bone_skeleton;
 
+///`<animation>` element, it may contain several samplers and nested animations
+#[derive(Clone)]
 pub struct Animation{
+    ///nested animations may have no id
+    pub id:Option<String>,
+    pub name:Option<String>,
+    ///samplers with channels, that use them
+    pub samplers:Vec<Sampler>,
+    pub children:Vec<Animation>,
+}
+
+///Sampler with target of channel, that uses it
+#[derive(Clone)]
+pub struct Sampler{
     pub id:String,
+    ///target address of channel, like "Position/transform"
+    pub target:String,
+    pub address:TargetAddress,
+    ///id of node(bone), that is animated
     pub bone_id:String,
-    pub skeleton_id:String,
+    ///skeleton, that contains bone, is found when scenes have been read
+    pub skeleton_id:Option<String>,
     pub keyframes_count:usize,
     pub sources:HashMap<String,Arc<Source>>,
 }
 
 impl Animation {
     pub fn parse(animation_element:&Element, asset:&Asset) -> Result<Animation, Error> {
-        let animation_id=animation_element.get_attribute("id")?.clone();
+        let id=animation_element.attributes.get("id").cloned();
+        let name=animation_element.attributes.get("name").cloned();
 
         let all_sources=read_sources(animation_element, asset)?;
+        //tangents of float channels are read without conversion, when they are needed
+        let mut raw_sources=None;
 
-        let sampler_element=animation_element.get_element("sampler")?;
-        let sources=Self::select_sources(&sampler_element,&all_sources)?;
-        let keyframes_count=Self::get_keyframes_count(&sources)?;
+        let mut samplers=Vec::new();
+        let mut children=Vec::new();
 
-        let channel_element=animation_element.get_element("channel")?;
-        let channel_source=channel_element.get_attribute("source")?.trim_left_matches('#');
-        let channel_target=channel_element.get_attribute("target")?;
+        for element in animation_element.children.iter(){
+            match element.name.as_str() {
+                "channel" => {
+                    let sampler_id=element.get_attribute("source")?.trim_left_matches('#');
+                    let target=element.get_attribute("target")?;
 
-        let bone_id=match channel_target.find('/') {
-            Some( pos ) => {
-                let (a,b)=channel_target.split_at(pos);
+                    let sampler_element=match animation_element.children.iter().find(|e| e.name.as_str()=="sampler" && e.attributes.get("id").map(|id| id.as_str())==Some(sampler_id)) {
+                        Some( sampler_element ) => sampler_element,
+                        None => return Err(Error::Other( format!("Sampler with id \"{}\" does not exists", sampler_id) )),
+                    };
 
-                String::from( a )
-            },
-            None => channel_target.clone(),
+                    samplers.push( Sampler::parse(sampler_element, target, animation_element, &all_sources, &mut raw_sources, asset)? );
+                },
+                "animation" =>
+                    children.push( Animation::parse(element, asset)? ),
+                _ => {},
+            }
+        }
+
+        let animation=Animation{
+            id:id,
+            name:name,
+            samplers:samplers,
+            children:children,
         };
 
-        let skeleton_id=match channel_source.find( &format!("_{}",&bone_id) ) {
-            Some( pos ) => {
-                let (a,b)=channel_source.split_at(pos);
+        Ok( animation )
+    }
+
+    ///Samplers of this animation and of all nested animations
+    pub fn all_samplers(&self) -> Vec<&Sampler> {
+        let mut samplers:Vec<&Sampler>=self.samplers.iter().collect();
+
+        for child in self.children.iter() {
+            samplers.extend( child.all_samplers() );
+        }
+
+        samplers
+    }
+
+    ///Decodes times, values and interpolations of channels of this animation and of all nested animations
+    pub fn channels(&self) -> Result<Vec<Channel>,Error> {
+        let mut channels=Vec::new();
+
+        for sampler in self.all_samplers() {
+            channels.push( sampler.decode()? );
+        }
+
+        Ok( channels )
+    }
 
-                String::from( a )
+    fn set_skeleton_ids(&mut self, skeletons:&HashMap<String,Arc<Skeleton>>) -> Result<(),Error> {
+        for sampler in self.samplers.iter_mut() {
+            sampler.skeleton_id=match find_bone_skeleton(skeletons, &sampler.bone_id)? {
+                Some( skeleton ) => Some( skeleton.id.clone() ),
+                None => None,
+            };
+        }
+
+        for child in self.children.iter_mut() {
+            child.set_skeleton_ids(skeletons)?;
+        }
+
+        Ok(())
+    }
+
+    ///Replaces skeleton ids of samplers by ids, that they are mapped to, collects bones of samplers without skeleton
+    fn rebind_skeleton_ids(&mut self, skeleton_ids:&HashMap<String,String>, unresolved:&mut Vec<String>) {
+        for sampler in self.samplers.iter_mut() {
+            sampler.skeleton_id=match sampler.skeleton_id.as_ref().and_then(|id| skeleton_ids.get(id)) {
+                Some( skeleton_id ) => Some( skeleton_id.clone() ),
+                None => {
+                    unresolved.push(sampler.bone_id.clone());
+                    None
+                },
+            };
+        }
+
+        for child in self.children.iter_mut() {
+            child.rebind_skeleton_ids(skeleton_ids, unresolved);
+        }
+    }
+
+    pub fn print(&self, printer:TreePrinter) {
+        match self.id {
+            Some( ref id ) => println!("Animation id:\"{}\"", id),
+            None => println!("Animation"),
+        }
+
+        let count=self.samplers.len()+self.children.len();
+
+        for (last,sampler) in self.samplers.iter().enumerate().map(|i| (i.0==count-1,i.1) ){
+            sampler.print( printer.new_branch(last) );
+        }
+
+        for (last,child) in self.children.iter().enumerate().map(|i| (i.0+self.samplers.len()==count-1,i.1) ){
+            child.print( printer.new_branch(last) );
+        }
+    }
+}
+
+impl Sampler {
+    fn parse(
+        sampler_element:&Element,
+        target:&String,
+        animation_element:&Element,
+        all_sources:&HashMap<String,Arc<Source>>,
+        raw_sources:&mut Option<HashMap<String,Arc<Source>>>,
+        asset:&Asset
+    ) -> Result<Sampler, Error> {
+        let id=sampler_element.get_attribute("id")?.clone();
+
+        let mut sources=Self::select_sources(sampler_element, all_sources)?;
+
+        //tangents of float channels are (time,value) pairs, they must not be converted like coordinates
+        let is_float_output=match sources.get("OUTPUT") {
+            Some( source ) => source.layers.len()==1 && match source.layers.values().next() {
+                Some( &SourceLayer::F32(_) ) => true,
+                _ => false,
             },
-            None => channel_source.to_string(),
+            None => false,
         };
 
-        let animation=Animation{
-            id:animation_id,
-            bone_id:bone_id,
-            skeleton_id:skeleton_id,
+        if is_float_output && (sources.contains_key("IN_TANGENT") || sources.contains_key("OUT_TANGENT")) {
+            if raw_sources.is_none() {
+                *raw_sources=Some( read_sources(animation_element, &asset.without_conversion())? );
+            }
+
+            for semantic in ["IN_TANGENT","OUT_TANGENT"].iter() {
+                let raw_source=match (sources.get(*semantic), raw_sources.as_ref()) {
+                    (Some( source ), Some( raw_sources )) => raw_sources.get(&source.id).cloned(),
+                    _ => None,
+                };
+
+                match raw_source {
+                    Some( raw_source ) => {sources.insert(String::from(*semantic), raw_source);},
+                    None => {},
+                }
+            }
+        }
+
+        let keyframes_count=Self::get_keyframes_count(&sources)?;
+
+        let address=TargetAddress::parse(target)?;
+
+        let is_translation=match address.sids.last().map(|sid| sid.as_str()) {
+            Some("translate") | Some("location") => true,
+            _ => false,
+        };
+
+        //translations are converted to meters like positions, matrices have been converted while sources were read
+        if is_translation && asset.unit_scale()!=1.0 {
+            for semantic in ["OUTPUT","IN_TANGENT","OUT_TANGENT"].iter() {
+                let scaled_source=match sources.get(*semantic) {
+                    Some( source ) => {
+                        let mut scaled_source=(**source).clone();
+
+                        if source.layers.len()==1 {
+                            let layer_names:Vec<String>=source.layers.keys().cloned().collect();
+                            scaled_source.scale_layers(&[layer_names[0].as_str()], asset.unit_scale());
+                        }else if is_float_output {
+                            //(time,value) pairs of tangents, value goes last
+                            match source.short_vertex_format.split(',').last() {
+                                Some( value_layer ) => scaled_source.scale_layers(&[value_layer], asset.unit_scale()),
+                                None => {},
+                            }
+                        }else{
+                            scaled_source.scale_positions(asset.unit_scale());
+                        }
+
+                        scaled_source
+                    },
+                    None => continue,
+                };
+
+                sources.insert(String::from(*semantic), Arc::new(scaled_source));
+            }
+        }
+
+        let sampler=Sampler{
+            id:id,
+            target:target.clone(),
+            bone_id:address.id.clone(),
+            address:address,
+            skeleton_id:None,
             keyframes_count:keyframes_count,
             sources:sources,
         };
 
-        Ok( animation )
+        Ok( sampler )
     }
 
     fn select_sources(element:&Element, sources:&HashMap<String,Arc<Source>>) -> Result<HashMap<String,Arc<Source>>,Error>{
@@ -86,7 +270,7 @@ impl Animation {
             }
         }
 
-        if sources.len()==0 {
+        if sources_list.len()==0 {
             return Err( Error::Other( String::from("No sources for animation") ));
         }
 
@@ -115,9 +299,21 @@ impl Animation {
         Ok( keyframes_count.unwrap() )
     }
 
+    ///Decodes times, values and interpolations of keys
+    pub fn decode(&self) -> Result<Channel,Error> {
+        Channel::decode(&self.target, &self.sources)
+    }
 
     pub fn print(&self, printer:TreePrinter) {
-        println!("Animation id:\"{}\" for bone with id \"{}\" of skeleton with id \"{}\"",self.id, self.bone_id, self.skeleton_id);
+        match self.skeleton_id {
+            Some( ref skeleton_id ) =>
+                println!("Sampler id:\"{}\" for bone with id \"{}\" of skeleton with id \"{}\"",self.id, self.bone_id, skeleton_id),
+            None =>
+                println!("Sampler id:\"{}\" for node with id \"{}\"",self.id, self.bone_id),
+        }
+
+        printer.new_branch(false);
+        println!("target: \"{}\"", self.target);
 
         printer.new_branch(false);
         println!("keyframes count: {}", self.keyframes_count);
@@ -147,9 +343,14 @@ pub fn parse_animations(root:&Element, asset:&Asset) -> Result< HashMap<String,A
         if animation_element.name.as_str()=="animation" {
             let animation=Animation::parse(animation_element, asset)?;
 
-            match animations.entry(animation.id.clone()){
+            let id=match animation.id {
+                Some( ref id ) => id.clone(),
+                None => return Err( Error::Other(String::from("Animation of library_animations has no id")) ),
+            };
+
+            match animations.entry(id.clone()){
                 Entry::Occupied(_) =>
-                    return Err( Error::Other(format!("Duplicate animation with id \"{}\"",animation.id)) ),
+                    return Err( Error::Other(format!("Duplicate animation with id \"{}\"",id)) ),
                 Entry::Vacant(entry) => {
                     entry.insert(Arc::new(animation));
                 },
@@ -159,3 +360,26 @@ pub fn parse_animations(root:&Element, asset:&Asset) -> Result< HashMap<String,A
 
     Ok(animations)
 }
+
+///Sets skeleton ids of samplers, animations, that are shared with clips, are copied
+pub fn bind_animations(animations:&mut HashMap<String,Arc<Animation>>, skeletons:&HashMap<String,Arc<Skeleton>>) -> Result<(),Error> {
+    for (_,animation) in animations.iter_mut(){
+        Arc::make_mut(animation).set_skeleton_ids(skeletons)?;
+    }
+
+    Ok(())
+}
+
+///Moves samplers from skeletons to skeletons with mapped ids, returns sorted ids of bones, that have no skeleton
+pub fn rebind_animations(animations:&mut HashMap<String,Arc<Animation>>, skeleton_ids:&HashMap<String,String>) -> Vec<String> {
+    let mut unresolved=Vec::new();
+
+    for (_,animation) in animations.iter_mut(){
+        Arc::make_mut(animation).rebind_skeleton_ids(skeleton_ids, &mut unresolved);
+    }
+
+    unresolved.sort();
+    unresolved.dedup();
+
+    unresolved
+}
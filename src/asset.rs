@@ -2,6 +2,8 @@ use Error;
 use XMLElement;
 use xmltree::Element;
 
+use std::time::{SystemTime,UNIX_EPOCH};
+
 pub struct Unit{
     pub name:String,
     pub ratio:f32,
@@ -20,35 +22,132 @@ pub struct Asset{
     pub unit:Unit,
     pub up_axis:Axis,
     pub editor:Editor,
+    pub fixups:Fixups,
 }
 
-#[derive(Copy,Clone,Eq,PartialEq)]
+#[derive(Copy,Clone,Eq,PartialEq,Debug)]
 pub enum Editor{
     Blender,
+    Max,
+    Maya,
+    FbxCollada,
+    SketchUp,
+    Cinema4D,
+    Assimp,
     Unknown,
 }
 
+impl Editor{
+    pub fn detect(authoring_tool:&str) -> Editor{
+        let tool=authoring_tool.to_lowercase();
+
+        if tool.starts_with("blender") {
+            Editor::Blender
+        }else if tool.contains("opencollada") && tool.contains("maya") {
+            Editor::Maya
+        }else if tool.contains("opencollada") && tool.contains("max") {
+            Editor::Max
+        }else if tool.contains("fbx collada") || tool.contains("fbx_collada") {
+            Editor::FbxCollada
+        }else if tool.contains("sketchup") {
+            Editor::SketchUp
+        }else if tool.contains("cinema4d") || tool.contains("cinema 4d") {
+            Editor::Cinema4D
+        }else if tool.contains("assimp") || tool.contains("asset importer") {
+            Editor::Assimp
+        }else{
+            Editor::Unknown
+        }
+    }
+
+    pub fn print_editor(&self) -> &'static str{
+        match *self{
+            Editor::Blender => "Blender",
+            Editor::Max => "OpenCOLLADA for 3ds Max",
+            Editor::Maya => "OpenCOLLADA for Maya",
+            Editor::FbxCollada => "FBX COLLADA exporter",
+            Editor::SketchUp => "SketchUp",
+            Editor::Cinema4D => "Cinema 4D",
+            Editor::Assimp => "Assimp",
+            Editor::Unknown => "unknown",
+        }
+    }
+}
+
+///Corrections of known exporter quirks, applied while the document is read
+#[derive(Copy,Clone,Eq,PartialEq,Debug)]
+pub struct Fixups{
+    ///mirror X axis after Z-up data has been converted to Y-up, so handedness is kept
+    pub flip_x:bool,
+    ///`<transparency>` of effects is stored as 1-opacity
+    pub invert_transparency:bool,
+}
+
+impl Fixups{
+    pub fn none() -> Fixups{
+        Fixups{
+            flip_x:false,
+            invert_transparency:false,
+        }
+    }
+
+    pub fn for_editor(editor:Editor) -> Fixups{
+        match editor{
+            Editor::Blender => Fixups{
+                flip_x:true,
+                ..Fixups::none()
+            },
+            Editor::Max => Fixups{
+                flip_x:true,
+                invert_transparency:true,
+                ..Fixups::none()
+            },
+            Editor::SketchUp => Fixups{
+                flip_x:true,
+                ..Fixups::none()
+            },
+            Editor::Maya | Editor::FbxCollada | Editor::Cinema4D | Editor::Assimp | Editor::Unknown => Fixups::none(),
+        }
+    }
+}
+
+///Options of `Document::parse_with_options`
+#[derive(Copy,Clone,Default)]
+pub struct LoadOptions{
+    ///use this editor instead of one detected from `<authoring_tool>`
+    pub editor:Option<Editor>,
+    ///use these fixups instead of known fixups of the editor
+    pub fixups:Option<Fixups>,
+}
+
 impl Asset{
-    pub fn parse(root:&Element) -> Result<Asset,Error>{
+    pub fn parse(root:&Element, options:&LoadOptions) -> Result<Asset,Error>{
         let asset=root.get_element("asset")?;
 
         let created=asset.get_element("created")?.get_text()?.clone();
         let modified=asset.get_element("modified")?.get_text()?.clone();
 
-        let unit_name=asset.get_element("unit")?.get_attribute("name")?.clone();
+        let (unit_name,unit_ratio)=match asset.get_element("unit") {
+            Ok( unit_element ) => {
+                let unit_name=match unit_element.attributes.get("name") {
+                    Some( name ) => name.clone(),
+                    None => String::from("meter"),
+                };
 
-        let unit_ratio=match unit_name.as_str(){
-            "meter" => {
-                let ratio_str=asset.get_element("unit")?.get_attribute("meter")?;
+                let unit_ratio=match unit_element.attributes.get("meter") {
+                    Some( _ ) => unit_element.parse_attribute_as_f32("meter")?,
+                    None => 1.0,
+                };
 
-                match ratio_str.parse::<f32>(){
-                    Ok ( r ) => r,
-                    Err( _ ) => return Err(Error::ParseFloatError( String::from("meter ratio"), ratio_str.clone() )),
-                }
+                (unit_name,unit_ratio)
             },
-            _ => return Err(Error::Other( format!("Asset/Unit: Expected meter unit, found {}", unit_name.as_str()) )),
+            Err( _ ) => (String::from("meter"), 1.0),
         };
 
+        if unit_ratio<=0.0 {
+            return Err(Error::Other( format!("Asset/Unit: meter ratio of unit {} must be positive", unit_name.as_str()) ));
+        }
+
         let up_axis={
             let up_axis_str=asset.get_element("up_axis")?.get_text()?;
 
@@ -60,13 +159,14 @@ impl Asset{
             }
         };
 
-        let contributor=asset.get_element("contributor")?;
-        let editor_str=contributor.get_element("authoring_tool")?.get_text()?;
+        let editor=match options.editor {
+            Some( editor ) => editor,
+            None => Self::detect_editor(asset),
+        };
 
-        let editor=if editor_str.starts_with("Blender") {
-            Editor::Blender
-        }else{
-            Editor::Unknown
+        let fixups=match options.fixups {
+            Some( fixups ) => fixups,
+            None => Fixups::for_editor(editor),
         };
 
         let asset=Asset{
@@ -78,8 +178,92 @@ impl Asset{
             },
             up_axis:up_axis,
             editor:editor,
+            fixups:fixups,
         };
 
         Ok( asset )
     }
+
+    fn detect_editor(asset:&Element) -> Editor {
+        for contributor in asset.children.iter(){
+            if contributor.name.as_str()=="contributor" {
+                match contributor.get_element("authoring_tool") {
+                    Ok( authoring_tool ) => {
+                        match authoring_tool.text {
+                            Some( ref tool ) => {
+                                let editor=Editor::detect(tool);
+
+                                if editor!=Editor::Unknown {
+                                    return editor;
+                                }
+                            },
+                            None => {},
+                        }
+                    },
+                    Err( _ ) => {},
+                }
+            }
+        }
+
+        Editor::Unknown
+    }
+
+    ///Asset of new document: Y_UP, meters, created and modified now
+    pub fn new() -> Asset {
+        let now=match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok( duration ) => duration.as_secs(),
+            Err( _ ) => 0,
+        };
+
+        Asset{
+            created:print_date(now),
+            modified:print_date(now),
+            unit:Unit{
+                name:String::from("meter"),
+                ratio:1.0,
+            },
+            up_axis:Axis::Y,
+            editor:Editor::Unknown,
+            fixups:Fixups::none(),
+        }
+    }
+
+    ///Copy of asset, that reads data as is: Y_UP, meters and no fixups
+    pub fn without_conversion(&self) -> Asset {
+        Asset{
+            created:self.created.clone(),
+            modified:self.modified.clone(),
+            unit:Unit{
+                name:String::from("meter"),
+                ratio:1.0,
+            },
+            up_axis:Axis::Y,
+            editor:self.editor,
+            fixups:Fixups::none(),
+        }
+    }
+
+    ///Meters in unit of document, positions are multiplied by it for every editor
+    pub fn unit_scale(&self) -> f32 {
+        self.unit.ratio
+    }
+}
+
+///UTC date like 2017-03-21T18:45:02Z from seconds since 1970
+fn print_date(seconds:u64) -> String {
+    let days=(seconds/86400) as i64;
+    let time=seconds%86400;
+
+    //civil date from days, era is 400 years
+    let z=days+719468;
+    let era=z.div_euclid(146097);
+    let day_of_era=z-era*146097;
+    let year_of_era=(day_of_era - day_of_era/1460 + day_of_era/36524 - day_of_era/146096)/365;
+    let day_of_year=day_of_era-(365*year_of_era + year_of_era/4 - year_of_era/100);
+    let mp=(5*day_of_year+2)/153;
+    let day=day_of_year-(153*mp+2)/5+1;
+    let month=if mp<10 { mp+3 } else { mp-9 };
+    let year=year_of_era+era*400+if month<=2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, time/3600, time%3600/60, time%60)
 }
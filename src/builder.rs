@@ -0,0 +1,517 @@
+use Error;
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+
+use Asset;
+use Bone;
+use Camera;
+use Controller;
+use Document;
+use Geometry;
+use Light;
+use Location;
+use TransformElement;
+use Mesh;
+use Node;
+use Polygon;
+use Scene;
+use Skeleton;
+use Source;
+use SourceLayer;
+use VertexIndices;
+
+///Polygons of one material. Every index of polygon refers position, normal and texture coordinates of vertex.
+pub struct MeshBuilder{
+    material:Option<String>,
+    positions:Vec<[f32;3]>,
+    normals:Option<Vec<[f32;3]>>,
+    tex_coords:Option<Vec<[f32;2]>>,
+    polygons:Vec<Vec<usize>>,
+}
+
+impl MeshBuilder{
+    pub fn new() -> MeshBuilder {
+        MeshBuilder{
+            material:None,
+            positions:Vec::new(),
+            normals:None,
+            tex_coords:None,
+            polygons:Vec::new(),
+        }
+    }
+
+    pub fn material(mut self, material:&str) -> MeshBuilder {
+        self.material=Some(String::from(material));
+        self
+    }
+
+    pub fn positions(mut self, positions:&[[f32;3]]) -> MeshBuilder {
+        self.positions=positions.to_vec();
+        self
+    }
+
+    pub fn normals(mut self, normals:&[[f32;3]]) -> MeshBuilder {
+        self.normals=Some(normals.to_vec());
+        self
+    }
+
+    pub fn tex_coords(mut self, tex_coords:&[[f32;2]]) -> MeshBuilder {
+        self.tex_coords=Some(tex_coords.to_vec());
+        self
+    }
+
+    pub fn polygon(mut self, indices:&[usize]) -> MeshBuilder {
+        self.polygons.push(indices.to_vec());
+        self
+    }
+
+    ///Every 3 indices make triangle
+    pub fn triangles(mut self, indices:&[usize]) -> MeshBuilder {
+        for triangle in indices.chunks(3) {
+            self.polygons.push(triangle.to_vec());
+        }
+
+        self
+    }
+
+    fn check(&self) -> Result<(),Error> {
+        let vertices_count=self.positions.len();
+
+        match self.normals {
+            Some( ref normals ) if normals.len()!=vertices_count =>
+                return Err(Error::Other( format!("Mesh has {} positions, but {} normals", vertices_count, normals.len()) )),
+            _ => {},
+        }
+
+        match self.tex_coords {
+            Some( ref tex_coords ) if tex_coords.len()!=vertices_count =>
+                return Err(Error::Other( format!("Mesh has {} positions, but {} texture coordinates", vertices_count, tex_coords.len()) )),
+            _ => {},
+        }
+
+        for polygon in self.polygons.iter() {
+            if polygon.len()<3 {
+                return Err(Error::Other( format!("Polygon has {} vertices, but at least 3 are expected", polygon.len()) ));
+            }
+
+            match polygon.iter().find(|index| **index>=vertices_count) {
+                Some( index ) => return Err(Error::Other( format!("Vertex index {} is out of {} positions", index, vertices_count) )),
+                None => {},
+            }
+        }
+
+        Ok(())
+    }
+}
+
+///Geometry of several meshes, positions(normals, texture coordinates) of meshes are joined into one source,
+///like `<vertices>` of COLLADA geometry
+pub struct GeometryBuilder{
+    id:String,
+    name:String,
+    meshes:Vec<MeshBuilder>,
+}
+
+impl GeometryBuilder{
+    pub fn new(id:&str, name:&str) -> GeometryBuilder {
+        GeometryBuilder{
+            id:String::from(id),
+            name:String::from(name),
+            meshes:Vec::new(),
+        }
+    }
+
+    pub fn mesh(mut self, mesh:MeshBuilder) -> GeometryBuilder {
+        self.meshes.push(mesh);
+        self
+    }
+
+    ///Ids of meshes are taken from `mesh_id`, like they are while document is read
+    pub fn build(&self, mesh_id:&mut usize) -> Result<Geometry,Error> {
+        if self.meshes.len()==0 {
+            return Err(Error::Other( format!("Geometry \"{}\" has no meshes", self.id) ));
+        }
+
+        for mesh in self.meshes.iter() {
+            mesh.check()?;
+        }
+
+        let has_normals=self.meshes[0].normals.is_some();
+        let has_tex_coords=self.meshes[0].tex_coords.is_some();
+
+        if self.meshes.iter().any(|mesh| mesh.normals.is_some()!=has_normals || mesh.tex_coords.is_some()!=has_tex_coords) {
+            return Err(Error::Other( format!("Meshes of geometry \"{}\" have different sources", self.id) ));
+        }
+
+        let positions=self.meshes.iter().flat_map(|mesh| mesh.positions.iter().cloned()).collect::<Vec<[f32;3]>>();
+        let mut sources=vec![ (String::from("VERTEX"), Arc::new( vec3_source(&format!("{}-positions", self.id), &positions) )) ];
+
+        if has_normals {
+            let normals=self.meshes.iter().flat_map(|mesh| mesh.normals.as_ref().unwrap().iter().cloned()).collect::<Vec<[f32;3]>>();
+            sources.push( (String::from("NORMAL"), Arc::new( vec3_source(&format!("{}-normals", self.id), &normals) )) );
+        }
+
+        if has_tex_coords {
+            let tex_coords=self.meshes.iter().flat_map(|mesh| mesh.tex_coords.as_ref().unwrap().iter().cloned()).collect::<Vec<[f32;2]>>();
+            sources.push( (String::from("TEXCOORD"), Arc::new( tex_coords_source(&format!("{}-tex_coords", self.id), &tex_coords) )) );
+        }
+
+        let (short_vertex_format,vertex_format)=mesh_vertex_format(&sources);
+
+        let mut meshes=Vec::with_capacity(self.meshes.len());
+        let mut first_position=0;
+
+        for (mesh_index,mesh) in self.meshes.iter().enumerate() {
+            let mut polygons=Vec::with_capacity(mesh.polygons.len());
+            let mut indices=Vec::new();
+
+            for polygon in mesh.polygons.iter() {
+                polygons.push( Polygon{
+                    first_vertex_index:indices.len(),
+                    vertices_count:polygon.len(),
+                });
+
+                indices.extend( polygon.iter().map(|index| first_position+index) );
+            }
+
+            let vertex_indices=sources.iter().map(|&(ref semantic,ref source)|
+                (semantic.clone(), Arc::new( VertexIndices{ source:source.clone(), indices:indices.clone() } ))
+            ).collect();
+
+            meshes.push( Arc::new( Mesh{
+                id:*mesh_id,
+                name:format!("{}#{}", self.name, mesh_index),
+                material:mesh.material.clone(),
+                short_vertex_format:short_vertex_format.clone(),
+                vertex_format:vertex_format.clone(),
+                sources:sources.clone(),
+                polygons:polygons,
+                vertex_indices:vertex_indices,
+            }));
+
+            *mesh_id+=1;
+            first_position+=mesh.positions.len();
+        }
+
+        Ok( Geometry{
+            id:self.id.clone(),
+            name:self.name.clone(),
+            meshes:meshes,
+        })
+    }
+}
+
+fn float_layers_source(id:&str, layer_names:&[&str], layers:Vec<Vec<f32>>) -> Source {
+    let vertex_format=layer_names.iter().map(|name| format!("{}:f32", name)).collect::<Vec<String>>().join(",");
+
+    Source{
+        id:String::from(id),
+        short_vertex_format:layer_names.join(","),
+        vertex_format:vertex_format,
+        layers:layer_names.iter().zip(layers.into_iter()).map(|(name,layer)| (String::from(*name), SourceLayer::F32(layer))).collect(),
+    }
+}
+
+fn vec3_source(id:&str, values:&[[f32;3]]) -> Source {
+    let layers=(0..3).map(|i| values.iter().map(|value| value[i]).collect()).collect();
+    float_layers_source(id, &["X","Y","Z"], layers)
+}
+
+fn tex_coords_source(id:&str, values:&[[f32;2]]) -> Source {
+    let layers=(0..2).map(|i| values.iter().map(|value| value[i]).collect()).collect();
+    float_layers_source(id, &["U","V"], layers)
+}
+
+///Same formats, that `Mesh::generate_vertex_format` gives
+fn mesh_vertex_format(sources:&[(String,Arc<Source>)]) -> (String,String) {
+    let short_vertex_format=sources.iter().map(|&(_,ref source)| format!("&({})", source.short_vertex_format)).collect::<Vec<String>>().join(" ");
+    let vertex_format=sources.iter().map(|&(ref semantic,ref source)| format!("{}:&({})", semantic, source.vertex_format)).collect::<Vec<String>>().join(" ");
+
+    (short_vertex_format, vertex_format)
+}
+
+struct BoneDescription{
+    id:String,
+    name:String,
+    parent:Option<String>,
+    location:Location,
+}
+
+///Bones may be added in any order, parent is referred by id
+pub struct SkeletonBuilder{
+    id:String,
+    location:Location,
+    bones:Vec<BoneDescription>,
+}
+
+impl SkeletonBuilder{
+    pub fn new(id:&str) -> SkeletonBuilder {
+        SkeletonBuilder{
+            id:String::from(id),
+            location:Location::identity(),
+            bones:Vec::new(),
+        }
+    }
+
+    ///Location of skeleton node
+    pub fn location(mut self, location:Location) -> SkeletonBuilder {
+        self.location=location;
+        self
+    }
+
+    ///Location is relative to parent bone, sid of bone is its id
+    pub fn bone(mut self, id:&str, name:&str, parent:Option<&str>, location:Location) -> SkeletonBuilder {
+        self.bones.push( BoneDescription{
+            id:String::from(id),
+            name:String::from(name),
+            parent:parent.map(|parent| String::from(parent)),
+            location:location,
+        });
+
+        self
+    }
+
+    ///Bones are sorted depth-first, children keep order of their addition
+    pub fn build(&self) -> Result<Skeleton,Error> {
+        let mut children:HashMap<Option<&str>,Vec<usize>>=HashMap::new();
+
+        for (index,bone) in self.bones.iter().enumerate() {
+            if self.bones[..index].iter().any(|other| other.id==bone.id) {
+                return Err(Error::Other( format!("Duplicate bone with id \"{}\"", bone.id) ));
+            }
+
+            match bone.parent {
+                Some( ref parent ) if !self.bones.iter().any(|other| &other.id==parent) =>
+                    return Err(Error::Other( format!("Parent \"{}\" of bone \"{}\" does not exists", parent, bone.id) )),
+                _ => {},
+            }
+
+            children.entry(bone.parent.as_ref().map(|parent| parent.as_str())).or_insert_with(Vec::new).push(index);
+        }
+
+        //order of bones in skeleton
+        let mut order=Vec::with_capacity(self.bones.len());
+        let mut stack:Vec<usize>=children.get(&None).map_or(Vec::new(), |roots| roots.iter().rev().cloned().collect());
+
+        while let Some( index )=stack.pop() {
+            order.push(index);
+
+            match children.get(&Some(self.bones[index].id.as_str())) {
+                Some( bone_children ) => stack.extend( bone_children.iter().rev() ),
+                None => {},
+            }
+        }
+
+        if order.len()!=self.bones.len() {
+            let lost=self.bones.iter().enumerate().filter(|&(index,_)| !order.contains(&index)).map(|(_,bone)| bone.id.as_str()).collect::<Vec<&str>>();
+            return Err(Error::Other( format!("Bones [{}] of skeleton \"{}\" are not attached to root", lost.join(", "), self.id) ));
+        }
+
+        let mut new_indices=vec![0;self.bones.len()];
+
+        for (new_index,index) in order.iter().enumerate() {
+            new_indices[*index]=new_index;
+        }
+
+        let mut bones_array=Vec::with_capacity(order.len());
+        let mut bones=HashMap::new();
+
+        for index in order.iter() {
+            let description=&self.bones[*index];
+
+            let bone=Arc::new( Bone{
+                id:description.id.clone(),
+                sid:description.id.clone(),
+                name:description.name.clone(),
+                skeleton_id:self.id.clone(),
+                index:new_indices[*index],
+                parent:description.parent.as_ref().map(|parent| new_indices[ self.bones.iter().position(|other| &other.id==parent).unwrap() ]),
+                children:children.get(&Some(description.id.as_str())).map_or(Vec::new(), |list| list.iter().map(|child| new_indices[*child]).collect()),
+
+                location:description.location.clone(),
+                matrix:description.location.to_matrix(),
+                transforms:vec![TransformElement::transform(&description.location.to_matrix())],
+            });
+
+            bones.insert(bone.id.clone(), bone.clone());
+            bones_array.push(bone);
+        }
+
+        Ok( Skeleton{
+            id:self.id.clone(),
+            location:self.location.clone(),
+            bones_array:bones_array,
+            bones:bones,
+        })
+    }
+}
+
+///Nodes of scene are stored by names, like they are while document is read
+pub struct SceneBuilder{
+    id:String,
+    name:String,
+    geometries:Vec<Node<Geometry>>,
+    cameras:Vec<Node<Camera>>,
+    lights:Vec<Node<Light>>,
+    skeletons:Vec<Node<Skeleton>>,
+}
+
+fn model_node<T>(id:&str, name:&str, location:Location, joined:&Arc<T>) -> Node<T> {
+    Node{
+        id:String::from(id),
+        name:String::from(name),
+        matrix:location.to_matrix(),
+        transforms:vec![TransformElement::transform(&location.to_matrix())],
+        location:location,
+        joined:joined.clone(),
+        controller:Controller::Model,
+    }
+}
+
+fn insert_nodes<T>(nodes:Vec<Node<T>>, kind:&str) -> Result<HashMap<String,Node<T>>,Error> {
+    let mut map=HashMap::new();
+
+    for node in nodes {
+        match map.entry(node.name.clone()) {
+            Entry::Occupied(_) => return Err(Error::Other( format!("Duplicate {} node with name \"{}\"", kind, node.name) )),
+            Entry::Vacant(entry) => { entry.insert(node); },
+        }
+    }
+
+    Ok( map )
+}
+
+impl SceneBuilder{
+    pub fn new(id:&str, name:&str) -> SceneBuilder {
+        SceneBuilder{
+            id:String::from(id),
+            name:String::from(name),
+            geometries:Vec::new(),
+            cameras:Vec::new(),
+            lights:Vec::new(),
+            skeletons:Vec::new(),
+        }
+    }
+
+    pub fn geometry(mut self, id:&str, name:&str, location:Location, geometry:&Arc<Geometry>) -> SceneBuilder {
+        self.geometries.push( model_node(id, name, location, geometry) );
+        self
+    }
+
+    pub fn camera(mut self, id:&str, name:&str, location:Location, camera:&Arc<Camera>) -> SceneBuilder {
+        self.cameras.push( model_node(id, name, location, camera) );
+        self
+    }
+
+    pub fn light(mut self, id:&str, name:&str, location:Location, light:&Arc<Light>) -> SceneBuilder {
+        self.lights.push( model_node(id, name, location, light) );
+        self
+    }
+
+    ///Id of node is id of skeleton, location of node is location of skeleton
+    pub fn skeleton(mut self, name:&str, skeleton:&Arc<Skeleton>) -> SceneBuilder {
+        self.skeletons.push( model_node(&skeleton.id, name, skeleton.location.clone(), skeleton) );
+        self
+    }
+
+    pub fn build(self) -> Result<Scene,Error> {
+        Ok( Scene{
+            id:self.id,
+            name:self.name,
+            geometries:insert_nodes(self.geometries, "geometry")?,
+            cameras:insert_nodes(self.cameras, "camera")?,
+            lights:insert_nodes(self.lights, "light")?,
+            skeletons:insert_nodes(self.skeletons, "skeleton")?,
+        })
+    }
+}
+
+fn insert_unique<T>(map:&mut HashMap<String,Arc<T>>, id:&str, value:T, kind:&str) -> Result<Arc<T>,Error> {
+    match map.entry(String::from(id)) {
+        Entry::Occupied(_) => Err(Error::Other( format!("Duplicate {} with id \"{}\"", kind, id) )),
+        Entry::Vacant(entry) => Ok( entry.insert(Arc::new(value)).clone() ),
+    }
+}
+
+impl Document{
+    ///Empty document, that is filled by builders
+    pub fn new() -> Document {
+        Document{
+            asset:Asset::new(),
+            cameras:HashMap::new(),
+            lights:HashMap::new(),
+            materials:HashMap::new(),
+            effects:HashMap::new(),
+            geometries:HashMap::new(),
+            skins:HashMap::new(),
+            skins_by_geometry:HashMap::new(),
+            morphs:HashMap::new(),
+            animations:HashMap::new(),
+            clips:HashMap::new(),
+            skeletons:HashMap::new(),
+            scenes:HashMap::new(),
+            warnings:Vec::new(),
+        }
+    }
+
+    ///Meshes get ids after ids of meshes of document
+    pub fn add_geometry(&mut self, builder:GeometryBuilder) -> Result<Arc<Geometry>,Error> {
+        let mut mesh_id=self.geometries.values().flat_map(|geometry| geometry.meshes.iter()).map(|mesh| mesh.id+1).max().unwrap_or(0);
+        let geometry=builder.build(&mut mesh_id)?;
+
+        insert_unique(&mut self.geometries, &builder.id, geometry, "geometry")
+    }
+
+    pub fn add_skeleton(&mut self, builder:SkeletonBuilder) -> Result<Arc<Skeleton>,Error> {
+        let skeleton=builder.build()?;
+
+        insert_unique(&mut self.skeletons, &builder.id, skeleton, "skeleton")
+    }
+
+    pub fn add_camera(&mut self, camera:Camera) -> Result<Arc<Camera>,Error> {
+        let id=camera.id.clone();
+
+        insert_unique(&mut self.cameras, &id, camera, "camera")
+    }
+
+    pub fn add_light(&mut self, light:Light) -> Result<Arc<Light>,Error> {
+        let id=light.id.clone();
+
+        insert_unique(&mut self.lights, &id, light, "light")
+    }
+
+    ///Geometries, cameras, lights and skeletons of scene must be added to document before
+    pub fn add_scene(&mut self, builder:SceneBuilder) -> Result<Arc<Scene>,Error> {
+        let scene=builder.build()?;
+
+        for node in scene.geometries.values() {
+            if !self.geometries.contains_key(&node.joined.id) {
+                return Err(Error::Other( format!("Geometry \"{}\" of node \"{}\" has not been added to document", node.joined.id, node.name) ));
+            }
+        }
+
+        for node in scene.cameras.values() {
+            if !self.cameras.contains_key(&node.joined.id) {
+                return Err(Error::Other( format!("Camera \"{}\" of node \"{}\" has not been added to document", node.joined.id, node.name) ));
+            }
+        }
+
+        for node in scene.lights.values() {
+            if !self.lights.contains_key(&node.joined.id) {
+                return Err(Error::Other( format!("Light \"{}\" of node \"{}\" has not been added to document", node.joined.id, node.name) ));
+            }
+        }
+
+        for node in scene.skeletons.values() {
+            if !self.skeletons.contains_key(&node.joined.id) {
+                return Err(Error::Other( format!("Skeleton \"{}\" of node \"{}\" has not been added to document", node.joined.id, node.name) ));
+            }
+        }
+
+        let id=scene.id.clone();
+
+        insert_unique(&mut self.scenes, &id, scene, "scene")
+    }
+}
@@ -6,10 +6,40 @@ use std::collections::HashMap;
 use std::collections::hash_map::Entry;
 use std::sync::Arc;
 
+use Asset;
+
+///Document has `xfov`, `yfov` or both of them, `aspect_ratio` is given with one of them
 pub struct Perspective{
     pub z_near:f32,
     pub z_far:f32,
-    pub x_fov:f32,
+    ///horizontal field of view in degrees
+    pub x_fov:Option<f32>,
+    ///vertical field of view in degrees
+    pub y_fov:Option<f32>,
+    ///width divided by height
+    pub aspect_ratio:Option<f32>,
+}
+
+impl Perspective{
+    ///Vertical field of view in degrees, it is computed from `x_fov` and `aspect_ratio`, if document has no `yfov`
+    pub fn vertical_fov(&self) -> Option<f32> {
+        match (self.y_fov, self.x_fov, self.aspect_ratio) {
+            (Some( y_fov ), _, _) => Some( y_fov ),
+            (None, Some( x_fov ), Some( aspect_ratio )) =>
+                Some( ((x_fov.to_radians()/2.0).tan()/aspect_ratio).atan().to_degrees()*2.0 ),
+            _ => None,
+        }
+    }
+
+    ///Aspect ratio, it is computed from `x_fov` and `y_fov`, if document has no `aspect_ratio`
+    pub fn aspect(&self) -> Option<f32> {
+        match (self.aspect_ratio, self.x_fov, self.y_fov) {
+            (Some( aspect_ratio ), _, _) => Some( aspect_ratio ),
+            (None, Some( x_fov ), Some( y_fov )) =>
+                Some( (x_fov.to_radians()/2.0).tan()/(y_fov.to_radians()/2.0).tan() ),
+            _ => None,
+        }
+    }
 }
 
 //TODO <extra><technique profile="blender">
@@ -21,15 +51,27 @@ pub struct Camera{
 }
 
 impl Camera{
-    pub fn parse(camera:&Element) -> Result<Camera,Error>{
+    pub fn parse(camera:&Element, asset:&Asset) -> Result<Camera,Error>{
         let id=camera.get_attribute("id")?.clone();
         let name=camera.get_attribute("name")?.clone();
 
         let perspective=camera.get_element("optics")?.get_element("technique_common")?.get_element("perspective")?;
 
-        let z_near=perspective.parse_text_as_f32("znear")?;
-        let z_far=perspective.parse_text_as_f32("zfar")?;
-        let x_fov=perspective.parse_text_as_f32("xfov")?;
+        //clip planes are distances, so they are converted to meters like positions
+        let z_near=perspective.parse_text_as_f32("znear")?*asset.unit_scale();
+        let z_far=perspective.parse_text_as_f32("zfar")?*asset.unit_scale();
+        let optional=|name:&str| match perspective.get_element(name) {
+            Ok( _ ) => perspective.parse_text_as_f32(name).map(Some),
+            Err( _ ) => Ok(None),
+        };
+
+        let x_fov=optional("xfov")?;
+        let y_fov=optional("yfov")?;
+        let aspect_ratio=optional("aspect_ratio")?;
+
+        if x_fov.is_none() && y_fov.is_none() {
+            return Err(Error::Other( format!("Camera \"{}\" has no xfov and yfov", id) ));
+        }
 
         Ok(
             Camera{
@@ -39,18 +81,20 @@ impl Camera{
                     z_near:z_near,
                     z_far:z_far,
                     x_fov:x_fov,
+                    y_fov:y_fov,
+                    aspect_ratio:aspect_ratio,
                 }
             }
         )
     }
 }
 
-pub fn parse_cameras(root:&Element) -> Result< HashMap<String,Arc<Camera>>, Error>{
+pub fn parse_cameras(root:&Element, asset:&Asset) -> Result< HashMap<String,Arc<Camera>>, Error>{
     let cameras_element=root.get_element("library_cameras")?;
     let mut cameras=HashMap::new();
 
     for camera_element in cameras_element.children.iter(){
-        let camera=Camera::parse(&camera_element)?;
+        let camera=Camera::parse(&camera_element, asset)?;
 
         match cameras.entry(camera.id.clone()){
             Entry::Occupied(_) => return Err(Error::Other( format!("Dublicate camera with id \"{}\"", &camera.id) )),
@@ -0,0 +1,470 @@
+use Error;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use Source;
+use SourceLayer;
+use Location;
+
+#[derive(Copy,Clone,Eq,PartialEq,Debug)]
+pub enum Interpolation{
+    Linear,
+    Step,
+    Bezier,
+    Hermite,
+    Cardinal,
+    BSpline,
+}
+
+impl Interpolation{
+    pub fn parse(name:&str) -> Result<Interpolation,Error>{
+        match name {
+            "LINEAR" => Ok( Interpolation::Linear ),
+            "STEP" => Ok( Interpolation::Step ),
+            "BEZIER" => Ok( Interpolation::Bezier ),
+            "HERMITE" => Ok( Interpolation::Hermite ),
+            "CARDINAL" => Ok( Interpolation::Cardinal ),
+            "BSPLINE" => Ok( Interpolation::BSpline ),
+            _ => Err(Error::Other( format!("Expected LINEAR, STEP, BEZIER, HERMITE, CARDINAL or BSPLINE interpolation, but {} has been found", name) )),
+        }
+    }
+
+    pub fn print_interpolation(&self) -> &'static str{
+        match *self{
+            Interpolation::Linear => "LINEAR",
+            Interpolation::Step => "STEP",
+            Interpolation::Bezier => "BEZIER",
+            Interpolation::Hermite => "HERMITE",
+            Interpolation::Cardinal => "CARDINAL",
+            Interpolation::BSpline => "BSPLINE",
+        }
+    }
+}
+
+///What to do with time outside of keys
+#[derive(Copy,Clone,Eq,PartialEq,Debug)]
+pub enum SampleMode{
+    ///use first or last key
+    Clamp,
+    ///repeat keys from first to last one
+    Loop,
+}
+
+///Value of channel at some time
+#[derive(Clone)]
+pub enum ChannelValue{
+    Float(f32),
+    Vec3([f32;3]),
+    Location(Location),
+}
+
+///Control point of BEZIER curve or tangent of HERMITE curve(IN_TANGENT/OUT_TANGENT)
+#[derive(Clone)]
+pub struct Tangent{
+    ///tangents of float channels are (time,value) pairs
+    pub time:Option<f32>,
+    pub value:ChannelValue,
+}
+
+impl Tangent{
+    fn rebased(&self, start:f32) -> Tangent {
+        Tangent{
+            time:self.time.map(|time| time-start),
+            value:self.value.clone(),
+        }
+    }
+}
+
+///Values of keys of channel
+#[derive(Clone)]
+pub enum ChannelValues{
+    Float(Vec<f32>),
+    Vec3(Vec<[f32;3]>),
+    ///decomposed `float4x4` outputs, like `transform` of Blender bones
+    Location(Vec<Location>),
+}
+
+impl ChannelValues{
+    pub fn get_length(&self) -> usize {
+        match *self{
+            ChannelValues::Float( ref list ) => list.len(),
+            ChannelValues::Vec3( ref list ) => list.len(),
+            ChannelValues::Location( ref list ) => list.len(),
+        }
+    }
+
+    pub fn get(&self, index:usize) -> ChannelValue {
+        match *self{
+            ChannelValues::Float( ref list ) => ChannelValue::Float( list[index] ),
+            ChannelValues::Vec3( ref list ) => ChannelValue::Vec3( list[index] ),
+            ChannelValues::Location( ref list ) => ChannelValue::Location( list[index].clone() ),
+        }
+    }
+
+    ///Empty list of values of same type
+    pub fn empty_like(&self) -> ChannelValues {
+        match *self{
+            ChannelValues::Float(_) => ChannelValues::Float( Vec::new() ),
+            ChannelValues::Vec3(_) => ChannelValues::Vec3( Vec::new() ),
+            ChannelValues::Location(_) => ChannelValues::Location( Vec::new() ),
+        }
+    }
+
+    pub fn push(&mut self, value:ChannelValue) -> Result<(),Error> {
+        match (self, value) {
+            (&mut ChannelValues::Float( ref mut list ), ChannelValue::Float( value )) => list.push(value),
+            (&mut ChannelValues::Vec3( ref mut list ), ChannelValue::Vec3( value )) => list.push(value),
+            (&mut ChannelValues::Location( ref mut list ), ChannelValue::Location( value )) => list.push(value),
+            (values, _) => return Err(Error::Other( format!("Value can not be added to {} values", values.print_data_type()) )),
+        }
+
+        Ok(())
+    }
+
+    pub fn print_data_type(&self) -> &'static str{
+        match *self{
+            ChannelValues::Float(_) => "float",
+            ChannelValues::Vec3(_) => "vec3",
+            ChannelValues::Location(_) => "location",
+        }
+    }
+
+    fn from_source(source:&Source) -> Result<ChannelValues,Error> {
+        match source.layers.get("location") {
+            Some( &SourceLayer::Matrix(ref matrices) ) => return Ok( ChannelValues::Location(matrices.iter().map(|matrix| matrix.decompose()).collect()) ),
+            _ => {},
+        }
+
+        for &(a,b,c) in [("X","Y","Z"),("R","G","B")].iter() {
+            match (source.layers.get(a), source.layers.get(b), source.layers.get(c)) {
+                (Some( &SourceLayer::F32(ref x) ), Some( &SourceLayer::F32(ref y) ), Some( &SourceLayer::F32(ref z) )) =>
+                    return Ok( ChannelValues::Vec3( x.iter().zip(y.iter()).zip(z.iter()).map(|((x,y),z)| [*x,*y,*z]).collect() ) ),
+                _ => {},
+            }
+        }
+
+        if source.layers.len()==1 {
+            match source.layers.values().next() {
+                Some( &SourceLayer::F32(ref values) ) => return Ok( ChannelValues::Float(values.clone()) ),
+                _ => {},
+            }
+        }
+
+        Err(Error::Other( format!("Source \"{}\" with layers \"{}\" can not be used as output of animation", source.id, source.vertex_format) ))
+    }
+}
+
+///Decoded sampler of animation, that is applied to target
+#[derive(Clone)]
+pub struct Channel{
+    ///target address, like "Position/transform"
+    pub target:String,
+    pub times:Vec<f32>,
+    pub values:ChannelValues,
+    ///interpolation from each key to next one
+    pub interpolation:Vec<Interpolation>,
+    ///empty, if sampler has no IN_TANGENT
+    pub in_tangents:Vec<Tangent>,
+    ///empty, if sampler has no OUT_TANGENT
+    pub out_tangents:Vec<Tangent>,
+}
+
+impl Channel{
+    ///Decodes sources of sampler by their semantic(INPUT, OUTPUT, INTERPOLATION)
+    pub fn decode(target:&str, sources:&HashMap<String,Arc<Source>>) -> Result<Channel,Error> {
+        let times=match sources.get("INPUT") {
+            Some( source ) => {
+                match source.layers.values().next() {
+                    Some( &SourceLayer::F32(ref times) ) if source.layers.len()==1 => times.clone(),
+                    _ => return Err(Error::Other( format!("INPUT source \"{}\" of animation must have one float layer", source.id) )),
+                }
+            },
+            None => return Err(Error::Other( format!("Sampler of channel \"{}\" has no INPUT source", target) )),
+        };
+
+        let values=match sources.get("OUTPUT") {
+            Some( source ) => ChannelValues::from_source(source)?,
+            None => return Err(Error::Other( format!("Sampler of channel \"{}\" has no OUTPUT source", target) )),
+        };
+
+        let interpolation=match sources.get("INTERPOLATION") {
+            Some( source ) => {
+                match source.layers.values().next() {
+                    Some( &SourceLayer::Name(ref names) ) if source.layers.len()==1 => {
+                        let mut interpolation=Vec::with_capacity(names.len());
+
+                        for name in names.iter() {
+                            interpolation.push( Interpolation::parse(name)? );
+                        }
+
+                        interpolation
+                    },
+                    _ => return Err(Error::Other( format!("INTERPOLATION source \"{}\" of animation must have one name layer", source.id) )),
+                }
+            },
+            None => vec![Interpolation::Linear;times.len()],
+        };
+
+        if times.len()==0 {
+            return Err(Error::Other( format!("Channel \"{}\" has no keys", target) ));
+        }
+
+        if values.get_length()!=times.len() || interpolation.len()!=times.len() {
+            return Err(Error::Other( format!("Channel \"{}\" has {} times, {} values and {} interpolations", target, times.len(), values.get_length(), interpolation.len()) ));
+        }
+
+        let in_tangents=Self::decode_tangents(sources.get("IN_TANGENT"), &values)?;
+        let out_tangents=Self::decode_tangents(sources.get("OUT_TANGENT"), &values)?;
+
+        if (in_tangents.len()>0 && in_tangents.len()!=times.len()) || (out_tangents.len()>0 && out_tangents.len()!=times.len()) {
+            return Err(Error::Other( format!("Channel \"{}\" has {} times, {} in tangents and {} out tangents", target, times.len(), in_tangents.len(), out_tangents.len()) ));
+        }
+
+        let channel=Channel{
+            target:String::from(target),
+            times:times,
+            values:values,
+            interpolation:interpolation,
+            in_tangents:in_tangents,
+            out_tangents:out_tangents,
+        };
+
+        Ok( channel )
+    }
+
+    ///Tangents of matrices are not supported, such channels get no tangents
+    fn decode_tangents(source:Option<&Arc<Source>>, values:&ChannelValues) -> Result<Vec<Tangent>,Error> {
+        let source=match source {
+            Some( source ) => source,
+            None => return Ok( Vec::new() ),
+        };
+
+        match *values {
+            ChannelValues::Float(_) => {
+                let mut layers=Vec::with_capacity(2);
+
+                for layer_name in source.short_vertex_format.split(',') {
+                    match source.layers.get(layer_name) {
+                        Some( &SourceLayer::F32(ref list) ) => layers.push(list),
+                        _ => return Err(Error::Other( format!("Tangent source \"{}\" of float channel must have float layers", source.id) )),
+                    }
+                }
+
+                match layers.len() {
+                    1 => Ok( layers[0].iter().map(|value| Tangent{time:None, value:ChannelValue::Float(*value)}).collect() ),
+                    2 => Ok( layers[0].iter().zip(layers[1].iter()).map(|(time,value)| Tangent{time:Some(*time), value:ChannelValue::Float(*value)}).collect() ),
+                    _ => Err(Error::Other( format!("Tangent source \"{}\" of float channel must have 1 or 2 layers", source.id) )),
+                }
+            },
+            ChannelValues::Vec3(_) => {
+                match ChannelValues::from_source(source)? {
+                    ChannelValues::Vec3( list ) => Ok( list.into_iter().map(|value| Tangent{time:None, value:ChannelValue::Vec3(value)}).collect() ),
+                    _ => Err(Error::Other( format!("Tangent source \"{}\" of vec3 channel must have X, Y and Z layers", source.id) )),
+                }
+            },
+            ChannelValues::Location(_) => Ok( Vec::new() ),
+        }
+    }
+
+    pub fn start_time(&self) -> f32 {
+        self.times[0]
+    }
+
+    pub fn end_time(&self) -> f32 {
+        self.times[self.times.len()-1]
+    }
+
+    ///Value at time, CARDINAL and BSPLINE keys are interpolated linearly
+    pub fn sample(&self, time:f32, mode:SampleMode) -> ChannelValue {
+        let (start,end)=(self.start_time(), self.end_time());
+
+        let time=match mode {
+            SampleMode::Clamp => time.max(start).min(end),
+            SampleMode::Loop => {
+                if end>start {
+                    start+(time-start).rem_euclid(end-start)
+                }else{
+                    start
+                }
+            },
+        };
+
+        //last key, that is not after time
+        let key=match self.times.iter().rposition(|key_time| *key_time<=time) {
+            Some( key ) => key,
+            None => 0,
+        };
+
+        if key==self.times.len()-1 {
+            return self.values.get(key);
+        }
+
+        let (t0,t1)=(self.times[key], self.times[key+1]);
+        let s=if t1>t0 { (time-t0)/(t1-t0) } else { 0.0 };
+
+        let has_tangents=self.in_tangents.len()>0 && self.out_tangents.len()>0;
+
+        match self.interpolation[key] {
+            Interpolation::Step => self.values.get(key),
+            Interpolation::Bezier if has_tangents => {
+                let (c0,c1)=(&self.out_tangents[key], &self.in_tangents[key+1]);
+
+                let s=match (c0.time, c1.time) {
+                    (Some( c0_time ), Some( c1_time )) => solve_bezier(t0, c0_time, c1_time, t1, time),
+                    _ => s,
+                };
+
+                self.combine(key, c0, c1, |p0,c0,c1,p1| bezier(p0,c0,c1,p1,s)).unwrap_or_else(|| self.lerp(key, s))
+            },
+            Interpolation::Hermite if has_tangents => {
+                let (m0,m1)=(&self.out_tangents[key], &self.in_tangents[key+1]);
+
+                self.combine(key, m0, m1, |p0,m0,m1,p1| {
+                    let (s2,s3)=(s*s, s*s*s);
+
+                    (2.0*s3-3.0*s2+1.0)*p0 + (s3-2.0*s2+s)*m0 + (-2.0*s3+3.0*s2)*p1 + (s3-s2)*m1
+                }).unwrap_or_else(|| self.lerp(key, s))
+            },
+            _ => self.lerp(key, s),
+        }
+    }
+
+    ///Keys from start to end with sampled boundary keys, times begin from zero.
+    ///Curves of cut segments are replaced by lines.
+    pub fn cut(&self, start:f32, end:f32) -> Result<Channel,Error> {
+        let mut channel=Channel{
+            target:self.target.clone(),
+            times:Vec::new(),
+            values:self.values.empty_like(),
+            interpolation:Vec::new(),
+            in_tangents:Vec::new(),
+            out_tangents:Vec::new(),
+        };
+
+        let has_tangents=self.in_tangents.len()>0 && self.out_tangents.len()>0;
+
+        let inner_keys:Vec<usize>=(0..self.times.len()).filter(|key| self.times[*key]>=start && self.times[*key]<=end).collect();
+
+        let first_time=inner_keys.first().map(|key| self.times[*key]);
+        if first_time!=Some(start) {
+            //interpolation of segment, that contains start
+            let interpolation=match self.times.iter().rposition(|time| *time<start) {
+                Some( key ) => self.interpolation[key],
+                None => Interpolation::Linear,
+            };
+
+            channel.push_sampled(self, start, start, interpolation, has_tangents)?;
+        }
+
+        for key in inner_keys.iter() {
+            channel.times.push( self.times[*key]-start );
+            channel.values.push( self.values.get(*key) )?;
+            channel.interpolation.push( self.interpolation[*key] );
+
+            if has_tangents {
+                channel.in_tangents.push( self.in_tangents[*key].rebased(start) );
+                channel.out_tangents.push( self.out_tangents[*key].rebased(start) );
+            }
+        }
+
+        let last_time=inner_keys.last().map(|key| self.times[*key]);
+        if last_time!=Some(end) && end>start {
+            //interpolation of last key is not used
+            channel.push_sampled(self, end, start, Interpolation::Linear, has_tangents)?;
+        }
+
+        Ok( channel )
+    }
+
+    ///Adds key, that starts or ends cut segment
+    fn push_sampled(&mut self, source:&Channel, time:f32, start:f32, interpolation:Interpolation, has_tangents:bool) -> Result<(),Error> {
+        //previous segment is cut too
+        match self.interpolation.last_mut() {
+            Some( previous ) => *previous=Self::cut_interpolation(*previous),
+            None => {},
+        }
+
+        let value=source.sample(time, SampleMode::Clamp);
+
+        if has_tangents {
+            //tangents of boundary keys are not used by lines
+            let tangent=Tangent{
+                time:match source.in_tangents[0].time {
+                    Some( _ ) => Some( time-start ),
+                    None => None,
+                },
+                value:value.clone(),
+            };
+
+            self.in_tangents.push( tangent.clone() );
+            self.out_tangents.push( tangent );
+        }
+
+        self.times.push( time-start );
+        self.values.push( value )?;
+        self.interpolation.push( Self::cut_interpolation(interpolation) );
+
+        Ok(())
+    }
+
+    fn cut_interpolation(interpolation:Interpolation) -> Interpolation {
+        match interpolation {
+            Interpolation::Step => Interpolation::Step,
+            _ => Interpolation::Linear,
+        }
+    }
+
+    fn lerp(&self, key:usize, s:f32) -> ChannelValue {
+        match self.values {
+            ChannelValues::Float( ref list ) => ChannelValue::Float( list[key]+(list[key+1]-list[key])*s ),
+            ChannelValues::Vec3( ref list ) => {
+                let (a,b)=(list[key], list[key+1]);
+
+                ChannelValue::Vec3( [a[0]+(b[0]-a[0])*s, a[1]+(b[1]-a[1])*s, a[2]+(b[2]-a[2])*s] )
+            },
+            ChannelValues::Location( ref list ) => ChannelValue::Location( list[key].interpolate(&list[key+1], s) ),
+        }
+    }
+
+    ///Applies curve to each component of keys and their tangents
+    fn combine<F:Fn(f32,f32,f32,f32) -> f32>(&self, key:usize, t0:&Tangent, t1:&Tangent, curve:F) -> Option<ChannelValue> {
+        match (&self.values, &t0.value, &t1.value) {
+            (&ChannelValues::Float( ref list ), &ChannelValue::Float( c0 ), &ChannelValue::Float( c1 )) =>
+                Some( ChannelValue::Float( curve(list[key], c0, c1, list[key+1]) ) ),
+            (&ChannelValues::Vec3( ref list ), &ChannelValue::Vec3( c0 ), &ChannelValue::Vec3( c1 )) => {
+                let (p0,p1)=(list[key], list[key+1]);
+
+                Some( ChannelValue::Vec3( [
+                    curve(p0[0], c0[0], c1[0], p1[0]),
+                    curve(p0[1], c0[1], c1[1], p1[1]),
+                    curve(p0[2], c0[2], c1[2], p1[2]),
+                ] ) )
+            },
+            _ => None,
+        }
+    }
+}
+
+fn bezier(p0:f32, c0:f32, c1:f32, p1:f32, s:f32) -> f32 {
+    let r=1.0-s;
+
+    r*r*r*p0 + 3.0*r*r*s*c0 + 3.0*r*s*s*c1 + s*s*s*p1
+}
+
+///Finds parameter of curve, that has such time, time of curve should grow
+fn solve_bezier(t0:f32, c0:f32, c1:f32, t1:f32, time:f32) -> f32 {
+    let (mut low,mut high)=(0.0,1.0);
+
+    for _ in 0..24 {
+        let middle=(low+high)*0.5;
+
+        if bezier(t0,c0,c1,t1,middle)<time {
+            low=middle;
+        }else{
+            high=middle;
+        }
+    }
+
+    (low+high)*0.5
+}
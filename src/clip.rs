@@ -0,0 +1,243 @@
+use Error;
+use XMLElement;
+use StringExt;
+use xmltree::Element;
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+
+use std::path::Path;
+use std::io::prelude::*;
+use std::fs::File;
+
+use Animation;
+use Channel;
+use Pose;
+use SampleMode;
+use Skeleton;
+use TargetAddress;
+use TreePrinter;
+
+use manifest::parse_manifest;
+
+///Part of timeline, that should become separate clip
+#[derive(Clone,PartialEq,Debug)]
+pub struct ClipRange{
+    pub name:String,
+    pub start:f32,
+    pub end:f32,
+}
+
+///Format of clip manifest
+#[derive(Copy,Clone,Eq,PartialEq,Debug)]
+pub enum ManifestFormat{
+    Json,
+    Toml,
+}
+
+///Decoded channels of part of timeline, times begin from zero
+#[derive(Clone)]
+pub struct Clip{
+    pub name:String,
+    pub duration:f32,
+    pub channels:Vec<Channel>,
+}
+
+///Part of timeline, like "walk" or "idle", that uses several animations
+pub struct AnimationClip{
+    pub id:String,
+    pub name:String,
+    pub start:f32,
+    ///time of last key of animations, if `end` attribute is absent
+    pub end:f32,
+    pub animations:Vec<Arc<Animation>>,
+}
+
+impl AnimationClip{
+    pub fn parse(clip_element:&Element, animations:&HashMap<String,Arc<Animation>>) -> Result<AnimationClip,Error>{
+        let id=clip_element.get_attribute("id")?.clone();
+        let name=match clip_element.attributes.get("name") {
+            Some( name ) => name.clone(),
+            None => id.clone(),
+        };
+
+        let start=match clip_element.attributes.get("start") {
+            Some( start ) => start.parse_as_f32("start of animation clip")?,
+            None => 0.0,
+        };
+
+        let mut clip_animations=Vec::new();
+
+        for instance_element in clip_element.children.iter(){
+            if instance_element.name.as_str()=="instance_animation" {
+                let animation_id=instance_element.get_attribute("url")?.trim_left_matches('#');
+
+                match animations.get(animation_id) {
+                    Some( animation ) => clip_animations.push( animation.clone() ),
+                    None => return Err(Error::Other( format!("Animation with id \"{}\" of clip \"{}\" does not exists", animation_id, id) )),
+                }
+            }
+        }
+
+        let end=match clip_element.attributes.get("end") {
+            Some( end ) => end.parse_as_f32("end of animation clip")?,
+            None => {
+                let mut end=start;
+
+                for animation in clip_animations.iter() {
+                    for channel in animation.channels()? {
+                        end=end.max( channel.end_time() );
+                    }
+                }
+
+                end
+            },
+        };
+
+        if end<start {
+            return Err(Error::Other( format!("Animation clip \"{}\" ends({}) before it starts({})", id, end, start) ));
+        }
+
+        let clip=AnimationClip{
+            id:id,
+            name:name,
+            start:start,
+            end:end,
+            animations:clip_animations,
+        };
+
+        Ok( clip )
+    }
+
+    ///Channels of animations from start to end of clip
+    pub fn decode(&self) -> Result<Clip,Error> {
+        let range=ClipRange{
+            name:self.name.clone(),
+            start:self.start,
+            end:self.end,
+        };
+
+        Clip::cut(&range, self.animations.iter())
+    }
+
+    pub fn print(&self, printer:TreePrinter) {
+        println!("Animation clip id:\"{}\" name:\"{}\" from {} to {}", self.id, self.name, self.start, self.end);
+
+        for (last,animation) in self.animations.iter().enumerate().map(|i| (i.0==self.animations.len()-1,i.1) ){
+            printer.new_branch(last);
+
+            match animation.id {
+                Some( ref id ) => println!("Animation id:\"{}\"", id),
+                None => println!("Animation"),
+            }
+        }
+    }
+}
+
+impl ClipRange{
+    ///Reads ranges from manifest, format is chosen by extension(`.json` or `.toml`), see `ClipRange::parse_manifest`
+    pub fn read_manifest(file_name:&Path) -> Result<Vec<ClipRange>,Error> {
+        let file_name_str=match file_name.to_str(){
+            Some( fns ) => String::from(fns),
+            None => return Err( Error::NotUnicodeFileName ),
+        };
+
+        let format=match file_name.extension().and_then(|extension| extension.to_str()) {
+            Some( "json" ) => ManifestFormat::Json,
+            Some( "toml" ) => ManifestFormat::Toml,
+            _ => return Err(Error::Other( format!("Manifest \"{}\" must have extension .json or .toml", file_name_str) )),
+        };
+
+        let mut text=String::new();
+
+        match File::open(file_name).and_then(|mut file| file.read_to_string(&mut text)) {
+            Ok( _ ) => {},
+            Err( e ) => return Err(Error::FileError(file_name_str,e)),
+        }
+
+        Self::parse_manifest(&text, format)
+    }
+
+    ///Manifest is JSON(`{"fps":24, "clips":[{"name":"walk", "start":0, "end":30}]}` or array of clips)
+    ///or TOML(`fps=24` and `[[clips]]` tables with name, start and end, or `[walk]` tables with start and end).
+    ///If fps is set, start and end are frames.
+    pub fn parse_manifest(text:&str, format:ManifestFormat) -> Result<Vec<ClipRange>,Error> {
+        let (fps,ranges)=parse_manifest(text, format)?;
+
+        let mut clip_ranges=Vec::with_capacity(ranges.len());
+
+        for range in ranges.into_iter() {
+            let clip_range=match fps {
+                Some( fps ) => ClipRange{ name:range.name, start:range.start/fps, end:range.end/fps },
+                None => range,
+            };
+
+            if clip_range.end<clip_range.start {
+                return Err(Error::Other( format!("Clip \"{}\" ends before it starts", clip_range.name) ));
+            }
+
+            clip_ranges.push(clip_range);
+        }
+
+        Ok( clip_ranges )
+    }
+}
+
+impl Clip{
+    ///Cuts channels of animations by range
+    pub fn cut<'a, I:Iterator<Item=&'a Arc<Animation>>>(range:&ClipRange, animations:I) -> Result<Clip,Error> {
+        if range.end<range.start {
+            return Err(Error::Other( format!("Clip \"{}\" ends({}) before it starts({})", range.name, range.end, range.start) ));
+        }
+
+        let mut channels=Vec::new();
+
+        for animation in animations {
+            for channel in animation.channels()? {
+                channels.push( channel.cut(range.start, range.end)? );
+            }
+        }
+
+        let clip=Clip{
+            name:range.name.clone(),
+            duration:range.end-range.start,
+            channels:channels,
+        };
+
+        Ok( clip )
+    }
+
+    ///Rest pose with channels of clip, that target bones of skeleton(see `Pose::apply_channel`)
+    pub fn pose_at(&self, skeleton:&Skeleton, time:f32, mode:SampleMode) -> Result<Pose,Error> {
+        let mut pose=Pose::rest(skeleton);
+
+        for channel in self.channels.iter() {
+            pose.apply_sampled(skeleton, &TargetAddress::parse(&channel.target)?, channel, time, mode)?;
+        }
+
+        Ok( pose )
+    }
+}
+
+pub fn parse_clips(root:&Element, animations:&HashMap<String,Arc<Animation>>) -> Result< HashMap<String,Arc<AnimationClip>>, Error>{
+    let clips_element=match root.get_element("library_animation_clips") {
+        Ok( clips_element ) => clips_element,
+        Err( _ ) => return Ok( HashMap::new() ),
+    };
+
+    let mut clips=HashMap::new();
+
+    for clip_element in clips_element.children.iter(){
+        if clip_element.name.as_str()=="animation_clip" {
+            let clip=AnimationClip::parse(&clip_element, animations)?;
+
+            match clips.entry(clip.id.clone()){
+                Entry::Occupied(_) => return Err(Error::Other( format!("Duplicate animation clip with id \"{}\"", &clip.id) )),
+                Entry::Vacant(entry) => { entry.insert(Arc::new(clip)); },
+            }
+        }
+    }
+
+    Ok(clips)
+}
@@ -12,13 +12,17 @@ use std::fmt;
 use Asset;
 
 use Bone;
+use Morph;
+use Skeleton;
 use Skin;
 
 #[derive(Clone)]
 pub enum Controller{
     Model,
     Bone(Arc<Bone>),
-    Skin(Arc<Skin>),
+    ///skin and skeleton, that has been referenced by `<skeleton>` of `<instance_controller>`
+    Skin(Arc<Skin>,Arc<Skeleton>),
+    Morph(Arc<Morph>),
 }
 
 impl Display for Controller{
@@ -26,27 +30,45 @@ impl Display for Controller{
         match *self{
             Controller::Model => write!(f, "Controller: model positions"),
             Controller::Bone( ref bone ) => write!(f, "Controller: {}", bone),
-            Controller::Skin( ref skin ) => write!(f, "Controller: {}", skin),
+            Controller::Skin( ref skin, ref skeleton ) => write!(f, "Controller: {} bound to skeleton with id \"{}\"", skin, skeleton.id),
+            Controller::Morph( ref morph ) => write!(f, "Controller: {}", morph),
         }
     }
 }
 
-pub fn parse_controllers(root:&Element, asset:&Asset) -> Result< (HashMap<String,Arc<Skin>>,HashMap<String,Arc<Skin>>), Error>{
-    let controllers_element=root.get_element("library_controllers")?;
+///Returns skins by id of controller, lists of skins by id of geometry and morphs by id of controller
+pub fn parse_controllers(root:&Element, asset:&Asset) -> Result< (HashMap<String,Arc<Skin>>,HashMap<String,Vec<Arc<Skin>>>,HashMap<String,Arc<Morph>>), Error>{
+    let controllers_element=match root.get_element("library_controllers") {
+        Ok( controllers_element ) => controllers_element,
+        Err( _ ) => return Ok( (HashMap::new(), HashMap::new(), HashMap::new()) ),
+    };
+
+    let morphs=parse_morphs(controllers_element, asset)?;
 
     let mut skins=HashMap::new();
-    let mut skins_by_id=HashMap::new();
+    let mut skins_by_geometry:HashMap<String,Vec<Arc<Skin>>>=HashMap::new();
 
     for controller_element in controllers_element.children.iter(){
         if controller_element.name.as_str()=="controller" {
             let controller_id=controller_element.get_attribute("id")?.clone();
-            let controller_name=controller_element.get_attribute("name")?.clone();
+            let controller_name=get_controller_name(controller_element, &controller_id);
 
             for skin_element in controller_element.children.iter() {
                 if skin_element.name.as_str()=="skin" {
-                    let skin=Arc::new( Skin::parse(skin_element, controller_id, controller_name, asset)? );
+                    let mut skin=Skin::parse(skin_element, controller_id, controller_name, asset)?;
+
+                    //skin deforms result of morph
+                    match morphs.get(&skin.geometry_id) {
+                        Some( morph ) => {
+                            skin.geometry_id=morph.geometry_id.clone();
+                            skin.morph=Some(morph.clone());
+                        },
+                        None => {},
+                    }
+
+                    let skin=Arc::new( skin );
 
-                    match skins_by_id.entry(skin.id.clone()){
+                    match skins.entry(skin.id.clone()){
                         Entry::Occupied(_) =>
                             return Err(Error::Other( format!("Skin with id \"{}\" already exists",&skin.id) )),
                         Entry::Vacant(entry) => {
@@ -54,11 +76,34 @@ pub fn parse_controllers(root:&Element, asset:&Asset) -> Result< (HashMap<String
                         },
                     }
 
-                    match skins.entry(skin.geometry_id.clone()){
+                    skins_by_geometry.entry(skin.geometry_id.clone()).or_insert_with(Vec::new).push(skin);
+
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok( (skins, skins_by_geometry, morphs) )
+}
+
+fn parse_morphs(controllers_element:&Element, asset:&Asset) -> Result< HashMap<String,Arc<Morph>>, Error>{
+    let mut morphs=HashMap::new();
+
+    for controller_element in controllers_element.children.iter(){
+        if controller_element.name.as_str()=="controller" {
+            let controller_id=controller_element.get_attribute("id")?.clone();
+            let controller_name=get_controller_name(controller_element, &controller_id);
+
+            for morph_element in controller_element.children.iter() {
+                if morph_element.name.as_str()=="morph" {
+                    let morph=Arc::new( Morph::parse(morph_element, controller_id, controller_name, asset)? );
+
+                    match morphs.entry(morph.id.clone()){
                         Entry::Occupied(_) =>
-                            return Err(Error::Other( format!("Geometry with id \"{}\" already has skin",&skin.geometry_id) )),
+                            return Err(Error::Other( format!("Morph with id \"{}\" already exists",&morph.id) )),
                         Entry::Vacant(entry) => {
-                            entry.insert(skin);
+                            entry.insert(morph);
                         },
                     }
 
@@ -68,5 +113,12 @@ pub fn parse_controllers(root:&Element, asset:&Asset) -> Result< (HashMap<String
         }
     }
 
-    Ok( (skins, skins_by_id) )
+    Ok( morphs )
+}
+
+fn get_controller_name(controller_element:&Element, controller_id:&String) -> String {
+    match controller_element.attributes.get("name") {
+        Some( name ) => name.clone(),
+        None => controller_id.clone(),
+    }
 }
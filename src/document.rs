@@ -3,17 +3,24 @@ use XMLElement;
 use xmltree::Element;
 
 use Asset;
+use LoadOptions;
 use Camera;
+use Light;
+use Material;
+use Effect;
 use Geometry;
 use Animation;
+use AnimationClip;
+use Clip;
+use ClipRange;
 use Skin;
+use Morph;
 use Skeleton;
 use Scene;
 use TreePrinter;
 
 use std::path::Path;
 
-use std::io::prelude::*;
 use std::io::BufReader;
 use std::fs::File;
 
@@ -21,23 +28,47 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use camera::parse_cameras;
+use light::parse_lights;
+use material::parse_materials;
+use effect::parse_effects;
 use geometry::parse_geometries;
 use animation::parse_animations;
+use animation::bind_animations;
+use animation::rebind_animations;
+use clip::parse_clips;
 use controller::parse_controllers;
+use morph::check_morph_targets;
 use scene::parse_scenes;
+use skeleton::find_bone_skeleton;
 
 pub struct Document{
     pub asset:Asset,
     pub cameras:HashMap<String,Arc<Camera>>,
+    pub lights:HashMap<String,Arc<Light>>,
+    pub materials:HashMap<String,Arc<Material>>,
+    pub effects:HashMap<String,Arc<Effect>>,
     pub geometries:HashMap<String,Arc<Geometry>>,
+    ///skins by id of controller
     pub skins:HashMap<String,Arc<Skin>>,
+    ///skins by id of geometry, one geometry may be bound to several skeletons
+    pub skins_by_geometry:HashMap<String,Vec<Arc<Skin>>>,
+    ///morphs by id of controller
+    pub morphs:HashMap<String,Arc<Morph>>,
     pub animations:HashMap<String,Arc<Animation>>,
+    pub clips:HashMap<String,Arc<AnimationClip>>,
     pub skeletons:HashMap<String,Arc<Skeleton>>,
     pub scenes:HashMap<String,Arc<Scene>>,
+    ///problems, that have been skipped while reading, like lights of unknown type
+    pub warnings:Vec<String>,
 }
 
 impl Document{
     pub fn parse(file_name:&Path) -> Result<Document,Error>{
+        Self::parse_with_options(file_name, &LoadOptions::default())
+    }
+
+    ///Reads document, options allow to override detected editor and its fixups
+    pub fn parse_with_options(file_name:&Path, options:&LoadOptions) -> Result<Document,Error>{
         let file=match File::open(file_name){
             Ok(f) => f,
             Err(e) => {
@@ -62,28 +93,176 @@ impl Document{
         }
 
         let version=root.get_attribute("version")?;
-        let asset=Asset::parse(&root)?;
+        let asset=Asset::parse(&root, options)?;
 
-        let cameras=parse_cameras(&root)?;
+        let cameras=parse_cameras(&root, &asset)?;
+        let mut warnings=Vec::new();
+        let lights=parse_lights(&root, &mut warnings)?;
+        let materials=parse_materials(&root)?;
+        let effects=parse_effects(&root, &asset, &mut warnings)?;
         let geometries=parse_geometries(&root, &asset)?;
         let animations=parse_animations(&root, &asset)?;
-        let (skins, skins_by_id)=parse_controllers(&root, &asset)?;
+        let (skins, skins_by_geometry, morphs)=parse_controllers(&root, &asset)?;
+        check_morph_targets(&morphs, &geometries)?;
 
         let mut document=Document{
             asset:asset,
             cameras:cameras,
+            lights:lights,
+            materials:materials,
+            effects:effects,
             geometries:geometries,
             animations:animations,
+            clips:HashMap::new(),
             skins:skins,
+            skins_by_geometry:skins_by_geometry,
+            morphs:morphs,
             skeletons:HashMap::new(),
             scenes:HashMap::new(),
+            warnings:warnings,
         };
 
-        parse_scenes(&root, &mut document, skins_by_id)?;
+        parse_scenes(&root, &mut document)?;
+
+        bind_animations(&mut document.animations, &document.skeletons)?;
+
+        //clips share animations, so they are read when animations have been bound to skeletons
+        document.clips=parse_clips(&root, &document.animations)?;
 
         Ok(document)
     }
 
+    ///Cuts animations of one timeline into clips, animations go in order of their ids
+    pub fn split_animations(&self, ranges:&[ClipRange]) -> Result<Vec<Clip>,Error> {
+        let mut animation_ids:Vec<&String>=self.animations.keys().collect();
+        animation_ids.sort();
+
+        let mut clips=Vec::with_capacity(ranges.len());
+
+        for range in ranges.iter() {
+            clips.push( Clip::cut(range, animation_ids.iter().map(|id| &self.animations[*id]))? );
+        }
+
+        Ok( clips )
+    }
+
+    ///Adds animations and clips of other document, whose skeletons have same bones and hierarchy as skeletons of this one.
+    ///Animations are bound to skeletons, that have been compared, bones of other nodes are reported in `warnings`.
+    ///Clashing ids get suffix like `_2`. Returns ids of added animations.
+    pub fn merge_animations(&mut self, other:&Document) -> Result<Vec<String>,Error> {
+        let mut skeleton_ids=Vec::new();
+
+        for animation in other.animations.values() {
+            for sampler in animation.all_samplers() {
+                match sampler.skeleton_id {
+                    Some( ref skeleton_id ) => skeleton_ids.push(skeleton_id.clone()),
+                    None => {},
+                }
+            }
+        }
+
+        skeleton_ids.sort();
+        skeleton_ids.dedup();
+
+        //skeletons of other document are replaced by checked skeletons of this one
+        let mut primary_ids=HashMap::new();
+
+        for skeleton_id in skeleton_ids.iter() {
+            match other.skeletons.get(skeleton_id) {
+                Some( skeleton ) => { primary_ids.insert(skeleton_id.clone(), self.check_skeleton(skeleton)?.id.clone()); },
+                None => {},
+            }
+        }
+
+        let mut animation_ids:Vec<&String>=other.animations.keys().collect();
+        animation_ids.sort();
+
+        let mut renamed=HashMap::new();
+        let mut added=HashMap::new();
+
+        for id in animation_ids {
+            let new_id=get_unique_id(&self.animations, &added, id);
+
+            let mut animation=(*other.animations[id]).clone();
+            animation.id=Some(new_id.clone());
+
+            renamed.insert(id.clone(), new_id.clone());
+            added.insert(new_id, Arc::new(animation));
+        }
+
+        let unresolved=rebind_animations(&mut added, &primary_ids);
+
+        if unresolved.len()>0 {
+            self.warnings.push( format!("Merged animations target nodes, that are not bones of skeletons: {}", unresolved.join(", ")) );
+        }
+
+        let mut clip_ids:Vec<&String>=other.clips.keys().collect();
+        clip_ids.sort();
+
+        for id in clip_ids {
+            let clip=&other.clips[id];
+            let new_id=get_unique_id(&self.clips, &HashMap::<String,()>::new(), id);
+
+            let merged_clip=AnimationClip{
+                id:new_id.clone(),
+                name:clip.name.clone(),
+                start:clip.start,
+                end:clip.end,
+                animations:clip.animations.iter().filter_map(|animation|
+                    animation.id.as_ref().and_then(|id| renamed.get(id)).map(|new_id| added[new_id].clone())
+                ).collect(),
+            };
+
+            self.clips.insert(new_id, Arc::new(merged_clip));
+        }
+
+        let mut added_ids:Vec<String>=added.keys().cloned().collect();
+        added_ids.sort();
+
+        self.animations.extend(added.into_iter());
+
+        Ok( added_ids )
+    }
+
+    ///Finds skeleton with same id(or with its root bone) and compares their bones and hierarchy, returns found skeleton
+    fn check_skeleton(&self, skeleton:&Skeleton) -> Result<&Arc<Skeleton>,Error> {
+        let root_id=match skeleton.roots().first() {
+            Some( root_index ) => &skeleton.bones_array[*root_index].id,
+            None => return Err(Error::Other( format!("Skeleton \"{}\" has no bones", skeleton.id) )),
+        };
+
+        let primary=match self.skeletons.get(&skeleton.id) {
+            Some( primary ) => primary,
+            None => match find_bone_skeleton(&self.skeletons, root_id)? {
+                Some( primary ) => primary,
+                None => return Err(Error::Other( format!("Document has no skeleton \"{}\" for animations", skeleton.id) )),
+            },
+        };
+
+        let parent_id=|skeleton:&Skeleton, index:Option<usize>| index.map(|index| skeleton.bones_array[index].id.clone());
+
+        let mut missing=Vec::new();
+        let mut moved=Vec::new();
+
+        for bone in skeleton.bones_array.iter() {
+            match primary.bones.get(&bone.id) {
+                Some( primary_bone ) => {
+                    if parent_id(skeleton, bone.parent)!=parent_id(primary, primary_bone.parent) {
+                        moved.push(bone.id.as_str());
+                    }
+                },
+                None => missing.push(bone.id.as_str()),
+            }
+        }
+
+        if missing.len()>0 || moved.len()>0 {
+            return Err(Error::Other( format!("Skeleton \"{}\" does not match skeleton \"{}\": missing bones [{}], bones with other parent [{}]",
+                skeleton.id, primary.id, missing.join(", "), moved.join(", ")) ));
+        }
+
+        Ok( primary )
+    }
+
     pub fn print(&self){
         let mut printer=TreePrinter::new();
         println!("Document");
@@ -91,7 +270,9 @@ impl Document{
         self.print_geometries( printer.new_branch(false) );
         self.print_skeletons( printer.new_branch(false) );
         self.print_animations( printer.new_branch(false) );
+        self.print_clips( printer.new_branch(false) );
         self.print_skins( printer.new_branch(false) );
+        self.print_morphs( printer.new_branch(false) );
         self.print_scenes( printer.new_branch(true) );
     }
 
@@ -127,6 +308,22 @@ impl Document{
         }
     }
 
+    fn print_clips(&self, printer:TreePrinter) {
+        println!("Animation clips");
+
+        for (last,(_,clip)) in self.clips.iter().clone().enumerate().map(|i| (i.0==self.clips.len()-1,i.1) ){
+            clip.print( printer.new_branch(last) );
+        }
+    }
+
+    fn print_morphs(&self, printer:TreePrinter) {
+        println!("Morphs");
+
+        for (last,(_,morph)) in self.morphs.iter().clone().enumerate().map(|i| (i.0==self.morphs.len()-1,i.1) ){
+            morph.print( printer.new_branch(last) );
+        }
+    }
+
     fn print_scenes(&self, printer:TreePrinter) {
         println!("Scenes");
 
@@ -136,3 +333,16 @@ impl Document{
     }
 
 }
+
+///Id, that is absent in both maps: id itself or id with suffix `_2`, `_3`...
+fn get_unique_id<A,B>(existing:&HashMap<String,A>, added:&HashMap<String,B>, id:&str) -> String {
+    let mut unique_id=String::from(id);
+    let mut index=1;
+
+    while existing.contains_key(&unique_id) || added.contains_key(&unique_id) {
+        index+=1;
+        unique_id=format!("{}_{}", id, index);
+    }
+
+    unique_id
+}
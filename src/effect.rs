@@ -0,0 +1,222 @@
+use Error;
+use XMLElement;
+use xmltree::Element;
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+
+use Asset;
+
+#[derive(Copy,Clone,Eq,PartialEq,Debug)]
+pub enum Shading{
+    Constant,
+    Lambert,
+    Phong,
+    Blinn,
+}
+
+impl Shading{
+    pub fn print_shading(&self) -> &'static str{
+        match *self{
+            Shading::Constant => "constant",
+            Shading::Lambert => "lambert",
+            Shading::Phong => "phong",
+            Shading::Blinn => "blinn",
+        }
+    }
+}
+
+#[derive(Clone,PartialEq,Debug)]
+pub enum ColorOrTexture{
+    Color([f32;4]),
+    ///sampler of effect and name of texture coordinates set
+    Texture{ texture:String, texcoord:String },
+}
+
+///Common profile of effect
+pub struct Effect{
+    pub id:String,
+    pub name:String,
+    pub shading:Shading,
+    pub emission:Option<ColorOrTexture>,
+    pub ambient:Option<ColorOrTexture>,
+    pub diffuse:Option<ColorOrTexture>,
+    pub specular:Option<ColorOrTexture>,
+    pub shininess:Option<f32>,
+    ///1.0 is opaque, `<transparency>` is combined with `<transparent>` color by its `opaque` mode,
+    ///transparent texture is not sampled, so only `<transparency>` is used for it.
+    ///Fixup `invert_transparency` has been applied to `<transparency>`.
+    pub opacity:f32,
+}
+
+impl Effect{
+    ///Reads technique of `profile_COMMON`, error if effect has no such technique or no shading.
+    ///Values, that can not be read(`<param>` instead of `<float>`, colors with other count of floats), are reported
+    ///in warnings and get defaults.
+    pub fn parse(effect:&Element, asset:&Asset, warnings:&mut Vec<String>) -> Result<Effect,Error>{
+        let id=effect.get_attribute("id")?.clone();
+        let name=match effect.attributes.get("name") {
+            Some( name ) => name.clone(),
+            None => id.clone(),
+        };
+
+        let technique=match effect.get_element("profile_COMMON").and_then(|profile| profile.get_element("technique")) {
+            Ok( technique ) => technique,
+            Err( _ ) => return Err(Error::Other( format!("Effect \"{}\" has no technique of profile_COMMON", id) )),
+        };
+
+        let shading_element=match technique.children.iter().find(|element| Self::parse_shading(&element.name).is_some()) {
+            Some( shading_element ) => shading_element,
+            None => return Err(Error::Other( format!("Effect \"{}\" has no constant, lambert, phong or blinn shading", id) )),
+        };
+
+        let shading=Self::parse_shading(&shading_element.name).unwrap();
+
+        let shininess=Self::parse_float(shading_element, "shininess", &id, warnings)?;
+
+        let transparency=match Self::parse_float(shading_element, "transparency", &id, warnings)? {
+            Some( transparency ) if asset.fixups.invert_transparency => 1.0-transparency,
+            Some( transparency ) => transparency,
+            None => 1.0,
+        };
+
+        let opaque=match shading_element.get_element("transparent") {
+            Ok( transparent_element ) => match transparent_element.attributes.get("opaque") {
+                Some( opaque ) => opaque.as_str(),
+                None => "A_ONE",
+            },
+            Err( _ ) => "A_ONE",
+        };
+
+        let opacity=match (Self::parse_color_or_texture(shading_element, "transparent", &id, warnings)?, opaque) {
+            (Some( ColorOrTexture::Color( color ) ), "A_ONE") => color[3]*transparency,
+            (Some( ColorOrTexture::Color( color ) ), "A_ZERO") => 1.0-color[3]*transparency,
+            (Some( ColorOrTexture::Color( color ) ), "RGB_ZERO") => 1.0-luminance(&color)*transparency,
+            (Some( ColorOrTexture::Color( color ) ), "RGB_ONE") => luminance(&color)*transparency,
+            (Some( ColorOrTexture::Color(_) ), opaque) => {
+                warnings.push( format!("Effect \"{}\" has unknown opaque mode \"{}\", A_ONE is used", id, opaque) );
+                transparency
+            },
+            _ => transparency,
+        };
+
+        Ok(
+            Effect{
+                id:id.clone(),
+                name:name,
+                shading:shading,
+                emission:Self::parse_color_or_texture(shading_element, "emission", &id, warnings)?,
+                ambient:Self::parse_color_or_texture(shading_element, "ambient", &id, warnings)?,
+                diffuse:Self::parse_color_or_texture(shading_element, "diffuse", &id, warnings)?,
+                specular:Self::parse_color_or_texture(shading_element, "specular", &id, warnings)?,
+                shininess:shininess,
+                opacity:opacity,
+            }
+        )
+    }
+
+    fn parse_shading(name:&str) -> Option<Shading> {
+        match name {
+            "constant" => Some(Shading::Constant),
+            "lambert" => Some(Shading::Lambert),
+            "phong" => Some(Shading::Phong),
+            "blinn" => Some(Shading::Blinn),
+            _ => None,
+        }
+    }
+
+    ///`<float>` of element, `<param>` refers to value, that is not read, so it is reported and value is None
+    fn parse_float(shading_element:&Element, name:&str, id:&str, warnings:&mut Vec<String>) -> Result<Option<f32>,Error> {
+        let element=match shading_element.get_element(name) {
+            Ok( element ) => element,
+            Err( _ ) => return Ok(None),
+        };
+
+        match element.get_element("float") {
+            Ok( _ ) => Ok( Some(element.parse_text_as_f32("float")?) ),
+            Err( _ ) => {
+                warnings.push( format!("Effect \"{}\" has no <float> in <{}>, default value is used", id, name) );
+                Ok(None)
+            },
+        }
+    }
+
+    ///Color has RGBA, alpha of RGB color is 1.0, colors with other count of values are reported and skipped
+    fn parse_color_or_texture(shading_element:&Element, name:&str, id:&str, warnings:&mut Vec<String>) -> Result<Option<ColorOrTexture>,Error> {
+        let element=match shading_element.get_element(name) {
+            Ok( element ) => element,
+            Err( _ ) => return Ok(None),
+        };
+
+        match element.get_element("color") {
+            Ok( color_element ) => {
+                let values:Result<Vec<f32>,_>=color_element.get_text()?.split_whitespace().map(|value| value.parse::<f32>()).collect();
+
+                return match values {
+                    Ok( ref values ) if values.len()==4 => Ok( Some(ColorOrTexture::Color([values[0], values[1], values[2], values[3]])) ),
+                    Ok( ref values ) if values.len()==3 => Ok( Some(ColorOrTexture::Color([values[0], values[1], values[2], 1.0])) ),
+                    _ => {
+                        warnings.push( format!("Effect \"{}\" has <{}> color, that is not RGB or RGBA, it is skipped", id, name) );
+                        Ok(None)
+                    },
+                };
+            },
+            Err( _ ) => {},
+        }
+
+        match element.get_element("texture") {
+            Ok( texture_element ) => Ok( Some(ColorOrTexture::Texture{
+                texture:texture_element.get_attribute("texture")?.clone(),
+                texcoord:match texture_element.attributes.get("texcoord") {
+                    Some( texcoord ) => texcoord.clone(),
+                    None => String::new(),
+                },
+            }) ),
+            Err( _ ) => Ok(None),
+        }
+    }
+
+    ///Diffuse color with opacity as alpha, white if diffuse is texture
+    pub fn base_color(&self) -> [f32;4] {
+        let color=match self.diffuse {
+            Some( ColorOrTexture::Color( color ) ) => color,
+            _ => [1.0;4],
+        };
+
+        [color[0], color[1], color[2], color[3]*self.opacity]
+    }
+}
+
+pub fn parse_effects(root:&Element, asset:&Asset, warnings:&mut Vec<String>) -> Result< HashMap<String,Arc<Effect>>, Error>{
+    let effects_element=match root.get_element("library_effects") {
+        Ok( effects_element ) => effects_element,
+        Err( _ ) => return Ok( HashMap::new() ),
+    };
+
+    let mut effects=HashMap::new();
+
+    for effect_element in effects_element.children.iter(){
+        if effect_element.name.as_str()=="effect" {
+            let effect=match Effect::parse(&effect_element, asset, warnings) {
+                Ok( effect ) => effect,
+                Err( error ) => {
+                    warnings.push( format!("Effect is skipped: {}", error) );
+                    continue;
+                },
+            };
+
+            match effects.entry(effect.id.clone()){
+                Entry::Occupied(_) => return Err(Error::Other( format!("Duplicate effect with id \"{}\"", &effect.id) )),
+                Entry::Vacant(entry) => { entry.insert(Arc::new(effect)); },
+            }
+        }
+    }
+
+    Ok(effects)
+}
+
+///Relative luminance of color, that RGB opaque modes use
+fn luminance(color:&[f32;4]) -> f32 {
+    0.212671*color[0] + 0.715160*color[1] + 0.072169*color[2]
+}
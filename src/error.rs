@@ -7,6 +7,7 @@ use std::fmt;
 pub enum Error{
     NotUnicodeFileName,
     FileError(String,std::io::Error),
+    WriteError(std::io::Error),
     ParseError(xmltree::ParseError),
     NoAttribute{element_name:String, attrib_name:String},
     NoElement{element_name:String, child_element_name:String},
@@ -23,6 +24,7 @@ impl Display for Error{
         match *self{
             Error::NotUnicodeFileName => write!(f, "Charset of name of file is not unicode"),
             Error::FileError(ref file_name, ref e) => write!(f, "File \"{}\" error:{}", file_name, e),
+            Error::WriteError(ref e) => write!(f, "Write error:{}", e),
             Error::ParseError(ref e) => write!(f, "Parse error:{}", e),
             Error::NoAttribute{ref element_name, ref attrib_name} => write!(f, "Element \"{}\" has not attrib \"{}\"", element_name, attrib_name),
             Error::NoElement{ref element_name, ref child_element_name} => write!(f, "Element \"{}\" does not contains element \"{}\"", element_name, child_element_name),
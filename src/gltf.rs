@@ -0,0 +1,972 @@
+use Error;
+
+use std::io::Write;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json;
+use serde_json::{Number,Value};
+
+use Bone;
+use ColorOrTexture;
+use Controller;
+use Document;
+use Effect;
+use Geometry;
+use Interpolation;
+use Location;
+use Material;
+use Matrix;
+use Mesh;
+use Shading;
+use Skeleton;
+use Skin;
+use Source;
+use SourceLayer;
+use TrackValues;
+use TrsTrack;
+
+use writer::sorted;
+
+const FLOAT:usize=5126;
+const UNSIGNED_SHORT:usize=5123;
+const UNSIGNED_INT:usize=5125;
+
+const ARRAY_BUFFER:usize=34962;
+const ELEMENT_ARRAY_BUFFER:usize=34963;
+
+///Json number of float, `name` describes value in error, json has no NaN and infinity
+fn float(value:f32, name:&str) -> Result<Value,Error> {
+    //shortest text of f32 is read as f64, so 0.1 is not written as 0.10000000149011612
+    match value.to_string().parse::<f64>().ok().and_then(Number::from_f64) {
+        Some( number ) => Ok( Value::Number(number) ),
+        None => Err(Error::Other( format!("{} is {}, glTF json can not store it", name, value) )),
+    }
+}
+
+fn floats(values:&[f32], name:&str) -> Result<Value,Error> {
+    let mut array=Vec::with_capacity(values.len());
+
+    for value in values.iter() {
+        array.push( float(*value, name)? );
+    }
+
+    Ok( Value::Array(array) )
+}
+
+///Node of glTF, children are added while scene is walked
+struct NodeDescription{
+    name:String,
+    location:Location,
+    children:Vec<usize>,
+    mesh:Option<usize>,
+    skin:Option<usize>,
+    camera:Option<usize>,
+}
+
+///Collects json arrays and binary buffer of glTF document
+struct Exporter<'a>{
+    document:&'a Document,
+    buffer:Vec<u8>,
+    buffer_views:Vec<Value>,
+    accessors:Vec<Value>,
+    nodes:Vec<NodeDescription>,
+    meshes:Vec<Value>,
+    skins:Vec<Value>,
+    cameras:Vec<Value>,
+    materials:Vec<Value>,
+    animations:Vec<Value>,
+    scenes:Vec<Value>,
+    ///indices of nodes by ids of scene nodes and bones, animations target them
+    node_indices:HashMap<String,usize>,
+    mesh_indices:HashMap<(String,Option<String>),usize>,
+    camera_indices:HashMap<String,usize>,
+    ///indices of skins by ids of skins and root nodes of skeletons, that joints are taken from
+    skin_indices:HashMap<(String,usize),usize>,
+    material_indices:HashMap<String,usize>,
+    warnings:Vec<String>,
+}
+
+///Vertex data of one primitive, vertices are unique combinations of indices of sources
+struct Primitive{
+    positions:Vec<[f32;3]>,
+    normals:Vec<[f32;3]>,
+    tex_coords:Vec<[f32;2]>,
+    joints:Vec<[u16;4]>,
+    weights:Vec<[f32;4]>,
+    indices:Vec<u32>,
+}
+
+fn get_float_layers<'a>(source:&'a Source, names:&[&str]) -> Result<Vec<&'a Vec<f32>>,Error> {
+    let mut layers=Vec::with_capacity(names.len());
+
+    for name in names.iter() {
+        match source.layers.get(*name) {
+            Some( &SourceLayer::F32( ref list ) ) => layers.push(list),
+            _ => return Err(Error::Other( format!("Source \"{}\" has no float layer \"{}\"", source.id, name) )),
+        }
+    }
+
+    Ok( layers )
+}
+
+fn get_value<'a>(list:&'a Vec<f32>, index:usize, source:&Source) -> Result<f32,Error> {
+    match list.get(index) {
+        Some( value ) => Ok(*value),
+        None => Err(Error::Other( format!("Index {} is out of source \"{}\"", index, source.id) )),
+    }
+}
+
+fn normalize(v:[f32;3]) -> [f32;3] {
+    let length=(v[0]*v[0] + v[1]*v[1] + v[2]*v[2]).sqrt();
+
+    if length>0.0 {
+        [v[0]/length, v[1]/length, v[2]/length]
+    }else{
+        v
+    }
+}
+
+///Inverse-transpose of 3x3 part of matrix up to positive factor, it keeps normals perpendicular to
+///faces, that are scaled non-uniformly, but normals must be normalized after it
+fn normal_matrix(matrix:&Matrix, skin_id:&str) -> Result<[f32;9],Error> {
+    let m=&matrix.mat;
+    let cofactor=[
+        m[5]*m[10] - m[6]*m[9],  m[6]*m[8] - m[4]*m[10], m[4]*m[9] - m[5]*m[8],
+        m[2]*m[9] - m[1]*m[10],  m[0]*m[10] - m[2]*m[8], m[1]*m[8] - m[0]*m[9],
+        m[1]*m[6] - m[2]*m[5],   m[2]*m[4] - m[0]*m[6],  m[0]*m[5] - m[1]*m[4],
+    ];
+
+    let determinant=m[0]*cofactor[0] + m[1]*cofactor[1] + m[2]*cofactor[2];
+
+    if determinant==0.0 {
+        return Err(Error::Other( format!("Bind shape matrix of skin \"{}\" can not be inverted", skin_id) ));
+    }
+
+    let sign=determinant.signum();
+    let mut normal_matrix=[0.0;9];
+
+    for i in 0..9 {
+        normal_matrix[i]=cofactor[i]*sign;
+    }
+
+    Ok( normal_matrix )
+}
+
+fn transform_normal(normal_matrix:&[f32;9], n:[f32;3]) -> [f32;3] {
+    let m=normal_matrix;
+
+    [
+        m[0]*n[0] + m[1]*n[1] + m[2]*n[2],
+        m[3]*n[0] + m[4]*n[1] + m[5]*n[2],
+        m[6]*n[0] + m[7]*n[1] + m[8]*n[2],
+    ]
+}
+
+///Column-major matrix of glTF
+fn column_major(matrix:&Matrix) -> [f32;16] {
+    let m=matrix.mat;
+    let mut mat=[0.0;16];
+
+    for row in 0..4 {
+        for col in 0..4 {
+            mat[col*4+row]=m[row*4+col];
+        }
+    }
+
+    mat
+}
+
+impl<'a> Exporter<'a>{
+    fn new(document:&'a Document) -> Exporter<'a> {
+        Exporter{
+            document:document,
+            buffer:Vec::new(),
+            buffer_views:Vec::new(),
+            accessors:Vec::new(),
+            nodes:Vec::new(),
+            meshes:Vec::new(),
+            skins:Vec::new(),
+            cameras:Vec::new(),
+            materials:Vec::new(),
+            animations:Vec::new(),
+            scenes:Vec::new(),
+            node_indices:HashMap::new(),
+            mesh_indices:HashMap::new(),
+            camera_indices:HashMap::new(),
+            skin_indices:HashMap::new(),
+            material_indices:HashMap::new(),
+            warnings:Vec::new(),
+        }
+    }
+
+    ///Appends bytes to buffer as new buffer view, views are aligned by 4 bytes
+    fn push_buffer_view(&mut self, bytes:&[u8], target:Option<usize>) -> usize {
+        while self.buffer.len()%4!=0 {
+            self.buffer.push(0);
+        }
+
+        let mut view=json!({
+            "buffer":0,
+            "byteOffset":self.buffer.len(),
+            "byteLength":bytes.len(),
+        });
+
+        match target {
+            Some( target ) => view["target"]=json!(target),
+            None => {},
+        }
+
+        self.buffer.extend_from_slice(bytes);
+        self.buffer_views.push(view);
+
+        self.buffer_views.len()-1
+    }
+
+    fn push_accessor(&mut self, accessor:Value) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len()-1
+    }
+
+    ///Accessor of floats, `components` is count of floats per element, like 3 for VEC3
+    fn push_floats(&mut self, values:&[f32], components:usize, accessor_type:&str, target:Option<usize>, with_bounds:bool) -> Result<usize,Error> {
+        let bytes:Vec<u8>=values.iter().flat_map(|value| value.to_bits().to_le_bytes().to_vec()).collect();
+        let view=self.push_buffer_view(&bytes, target);
+        let count=values.len()/components;
+
+        let mut accessor=json!({
+            "bufferView":view,
+            "componentType":FLOAT,
+            "count":count,
+            "type":accessor_type,
+        });
+
+        //POSITION and input of animation sampler require bounds
+        if with_bounds && count>0 {
+            let mut min=vec![::std::f32::MAX;components];
+            let mut max=vec![::std::f32::MIN;components];
+
+            for element in values.chunks(components) {
+                for i in 0..components {
+                    min[i]=min[i].min(element[i]);
+                    max[i]=max[i].max(element[i]);
+                }
+            }
+
+            accessor["min"]=floats(&min, "Minimum of accessor")?;
+            accessor["max"]=floats(&max, "Maximum of accessor")?;
+        }
+
+        Ok( self.push_accessor(accessor) )
+    }
+
+    fn push_indices(&mut self, indices:&[u32]) -> usize {
+        let bytes:Vec<u8>=indices.iter().flat_map(|index| index.to_le_bytes().to_vec()).collect();
+        let view=self.push_buffer_view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+
+        self.push_accessor( json!({
+            "bufferView":view,
+            "componentType":UNSIGNED_INT,
+            "count":indices.len(),
+            "type":"SCALAR",
+        }) )
+    }
+
+    fn push_joints(&mut self, joints:&[[u16;4]]) -> usize {
+        let bytes:Vec<u8>=joints.iter().flat_map(|joint| joint.iter().flat_map(|index| index.to_le_bytes().to_vec()).collect::<Vec<u8>>()).collect();
+        let view=self.push_buffer_view(&bytes, Some(ARRAY_BUFFER));
+
+        self.push_accessor( json!({
+            "bufferView":view,
+            "componentType":UNSIGNED_SHORT,
+            "count":joints.len(),
+            "type":"VEC4",
+        }) )
+    }
+
+    fn push_node(&mut self, name:&str, location:&Location) -> usize {
+        self.nodes.push( NodeDescription{
+            name:String::from(name),
+            location:location.clone(),
+            children:Vec::new(),
+            mesh:None,
+            skin:None,
+            camera:None,
+        });
+
+        self.nodes.len()-1
+    }
+
+    fn export_materials(&mut self) -> Result<(),Error> {
+        let document=self.document;
+
+        for material in sorted(&document.materials) {
+            let json=material_json(material, document.effects.get(&material.effect_id))?;
+
+            self.materials.push(json);
+            self.material_indices.insert(material.id.clone(), self.materials.len()-1);
+        }
+
+        Ok(())
+    }
+
+    ///Material of mesh is symbol, exporters write id or name of material
+    fn get_material_index(&self, symbol:&str) -> Option<usize> {
+        match self.material_indices.get(symbol) {
+            Some( index ) => Some(*index),
+            None => self.document.materials.values().find(|material| material.name==symbol).and_then(|material| self.material_indices.get(&material.id).cloned()),
+        }
+    }
+
+    fn export_scenes(&mut self) -> Result<(),Error> {
+        let document=self.document;
+
+        for scene in sorted(&document.scenes) {
+            let mut roots=Vec::new();
+            //nodes of bones of skeletons of this scene
+            let mut bone_nodes:HashMap<String,Vec<usize>>=HashMap::new();
+
+            for node in sorted(&scene.skeletons) {
+                let node_index=self.push_node(&node.name, &node.location);
+                self.node_indices.entry(node.id.clone()).or_insert(node_index);
+                roots.push(node_index);
+
+                let indices=self.export_bones(&node.joined, node_index);
+                bone_nodes.insert(node.joined.id.clone(), indices);
+            }
+
+            let bone_node=|bone:&Arc<Bone>, bone_nodes:&HashMap<String,Vec<usize>>| bone_nodes.get(&bone.skeleton_id).map(|indices| indices[bone.index]);
+
+            let mut attached=Vec::new();
+
+            for node in sorted(&scene.geometries) {
+                let node_index=self.push_node(&node.name, &node.location);
+                self.node_indices.entry(node.id.clone()).or_insert(node_index);
+
+                let (mesh_index,skin_index)=match node.controller {
+                    Controller::Skin( ref skin, ref skeleton ) => {
+                        let joint_nodes=match bone_nodes.get(&skeleton.id) {
+                            Some( indices ) => indices.clone(),
+                            None => return Err(Error::Other( format!("Skeleton \"{}\" of node \"{}\" is not in scene \"{}\"", skeleton.id, node.name, scene.id) )),
+                        };
+
+                        let mesh_index=self.export_mesh(&node.joined, Some(skin))?;
+                        let skin_index=self.export_skin(skin, skeleton, &joint_nodes)?;
+
+                        (mesh_index, Some(skin_index))
+                    },
+                    _ => (self.export_mesh(&node.joined, None)?, None),
+                };
+
+                self.nodes[node_index].mesh=Some(mesh_index);
+                self.nodes[node_index].skin=skin_index;
+
+                attached.push( (node_index, match node.controller { Controller::Bone( ref bone ) => bone_node(bone, &bone_nodes), _ => None }) );
+            }
+
+            for node in sorted(&scene.cameras) {
+                let node_index=self.push_node(&node.name, &node.location);
+                self.node_indices.entry(node.id.clone()).or_insert(node_index);
+                self.nodes[node_index].camera=Some( self.export_camera(&node.joined.id)? );
+
+                attached.push( (node_index, match node.controller { Controller::Bone( ref bone ) => bone_node(bone, &bone_nodes), _ => None }) );
+            }
+
+            for (node_index,parent) in attached {
+                match parent {
+                    Some( parent ) => self.nodes[parent].children.push(node_index),
+                    None => roots.push(node_index),
+                }
+            }
+
+            self.scenes.push( json!({"name":scene.name, "nodes":roots}) );
+        }
+
+        Ok(())
+    }
+
+    ///Node of every bone, returns indices of nodes in order of bones
+    fn export_bones(&mut self, skeleton:&Skeleton, skeleton_node:usize) -> Vec<usize> {
+        let mut indices=Vec::with_capacity(skeleton.bones_array.len());
+
+        for bone in skeleton.bones_array.iter() {
+            let node_index=self.push_node(&bone.name, &bone.location);
+            self.node_indices.entry(bone.id.clone()).or_insert(node_index);
+            indices.push(node_index);
+
+            //parent has smaller index, so its node exists
+            let parent=match bone.parent {
+                Some( parent_index ) => indices[parent_index],
+                None => skeleton_node,
+            };
+
+            self.nodes[parent].children.push(node_index);
+        }
+
+        indices
+    }
+
+    fn export_camera(&mut self, camera_id:&str) -> Result<usize,Error> {
+        match self.camera_indices.get(camera_id) {
+            Some( index ) => return Ok(*index),
+            None => {},
+        }
+
+        let camera=&self.document.cameras[camera_id];
+
+        //without aspect ratio horizontal field of view is used as vertical one
+        let y_fov=match (camera.perspective.vertical_fov(), camera.perspective.x_fov) {
+            (Some( y_fov ), _) | (None, Some( y_fov )) => y_fov,
+            (None, None) => return Err(Error::Other( format!("Camera \"{}\" has no field of view", camera.id) )),
+        };
+
+        let mut perspective=json!({ "yfov":float(y_fov.to_radians(), "Field of view of camera")? });
+
+        match camera.perspective.aspect() {
+            Some( aspect ) => perspective["aspectRatio"]=float(aspect, "Aspect ratio of camera")?,
+            None => {},
+        }
+
+        perspective["znear"]=float(camera.perspective.z_near, "Near plane of camera")?;
+        perspective["zfar"]=float(camera.perspective.z_far, "Far plane of camera")?;
+
+        self.cameras.push( json!({
+            "name":camera.name,
+            "type":"perspective",
+            "perspective":perspective,
+        }) );
+
+        self.camera_indices.insert(String::from(camera_id), self.cameras.len()-1);
+        Ok( self.cameras.len()-1 )
+    }
+
+    ///Nodes with same skin and skeleton share one skin
+    fn export_skin(&mut self, skin:&Skin, skeleton:&Skeleton, bone_nodes:&[usize]) -> Result<usize,Error> {
+        let root_node=match skeleton.roots().first() {
+            Some( root ) => bone_nodes[*root],
+            None => return Err( Error::Other(format!("Skeleton \"{}\" of skin \"{}\" has no bones", skeleton.id, skin.id)) ),
+        };
+
+        let key=(skin.id.clone(), root_node);
+
+        match self.skin_indices.get(&key) {
+            Some( index ) => return Ok(*index),
+            None => {},
+        }
+
+        let joints=skin.bind(skeleton)?;
+
+        let mut matrices=Vec::with_capacity(joints.len()*16);
+
+        for joint in joints.iter() {
+            matrices.extend_from_slice( &column_major(&joint.inverse_bind_matrix) );
+        }
+
+        let accessor=self.push_floats(&matrices, 16, "MAT4", None, false)?;
+
+        let mut joint_nodes=Vec::with_capacity(joints.len());
+
+        for joint in joints.iter() {
+            match joint.bone_index {
+                Some( bone_index ) => joint_nodes.push(bone_nodes[bone_index]),
+                None => return Err( Error::Other(format!("Skin \"{}\" has joints, that are absent in skeleton \"{}\"", skin.id, skeleton.id)) ),
+            }
+        }
+
+        self.skins.push( json!({
+            "name":skin.name,
+            "inverseBindMatrices":accessor,
+            "skeleton":root_node,
+            "joints":joint_nodes,
+        }) );
+
+        self.skin_indices.insert(key, self.skins.len()-1);
+        Ok( self.skins.len()-1 )
+    }
+
+    ///Mesh of geometry, skinned geometry has own mesh with joints and weights
+    fn export_mesh(&mut self, geometry:&Geometry, skin:Option<&Arc<Skin>>) -> Result<usize,Error> {
+        let key=(geometry.id.clone(), skin.map(|skin| skin.id.clone()));
+
+        match self.mesh_indices.get(&key) {
+            Some( index ) => return Ok(*index),
+            None => {},
+        }
+
+        let mut primitives=Vec::with_capacity(geometry.meshes.len());
+
+        for mesh in geometry.meshes.iter() {
+            let primitive=build_primitive(mesh, skin.map(|skin| &**skin))?;
+
+            if primitive.indices.len()==0 {
+                continue;
+            }
+
+            let positions:Vec<f32>=primitive.positions.iter().flat_map(|p| p.to_vec()).collect();
+            let mut attributes=json!({ "POSITION":self.push_floats(&positions, 3, "VEC3", Some(ARRAY_BUFFER), true)? });
+
+            if primitive.normals.len()>0 {
+                let normals:Vec<f32>=primitive.normals.iter().flat_map(|n| n.to_vec()).collect();
+                attributes["NORMAL"]=json!( self.push_floats(&normals, 3, "VEC3", Some(ARRAY_BUFFER), false)? );
+            }
+
+            if primitive.tex_coords.len()>0 {
+                let tex_coords:Vec<f32>=primitive.tex_coords.iter().flat_map(|t| t.to_vec()).collect();
+                attributes["TEXCOORD_0"]=json!( self.push_floats(&tex_coords, 2, "VEC2", Some(ARRAY_BUFFER), false)? );
+            }
+
+            if primitive.joints.len()>0 {
+                let weights:Vec<f32>=primitive.weights.iter().flat_map(|w| w.to_vec()).collect();
+                attributes["JOINTS_0"]=json!( self.push_joints(&primitive.joints) );
+                attributes["WEIGHTS_0"]=json!( self.push_floats(&weights, 4, "VEC4", Some(ARRAY_BUFFER), false)? );
+            }
+
+            let mut json=json!({
+                "attributes":attributes,
+                "indices":self.push_indices(&primitive.indices),
+            });
+
+            match mesh.material.as_ref().and_then(|symbol| self.get_material_index(symbol)) {
+                Some( material ) => json["material"]=json!(material),
+                None => {},
+            }
+
+            primitives.push(json);
+        }
+
+        if primitives.len()==0 {
+            return Err(Error::Other( format!("Geometry \"{}\" has no polygons", geometry.id) ));
+        }
+
+        self.meshes.push( json!({"name":geometry.name, "primitives":primitives}) );
+        self.mesh_indices.insert(key, self.meshes.len()-1);
+
+        Ok( self.meshes.len()-1 )
+    }
+
+    ///Animation of every clip, or of every animation if document has no clips
+    fn export_animations(&mut self) -> Result<(),Error> {
+        let document=self.document;
+
+        if document.clips.len()>0 {
+            for clip in sorted(&document.clips) {
+                let tracks=clip.decode()?.to_trs_tracks()?;
+                self.export_animation(&clip.name, &tracks)?;
+            }
+        }else{
+            for animation in sorted(&document.animations) {
+                let tracks=animation.to_trs_tracks()?;
+                let name=match animation.name {
+                    Some( ref name ) => name.clone(),
+                    None => animation.id.clone().unwrap_or_default(),
+                };
+
+                self.export_animation(&name, &tracks)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    ///Tracks of nodes, that are not exported(like lights), are reported in warnings
+    fn export_animation(&mut self, name:&str, tracks:&[TrsTrack]) -> Result<(),Error> {
+        let mut samplers=Vec::with_capacity(tracks.len());
+        let mut channels=Vec::with_capacity(tracks.len());
+        let mut skipped:Vec<&str>=Vec::new();
+
+        for track in tracks.iter() {
+            let node_index=match self.node_indices.get(&track.node_id) {
+                Some( index ) => *index,
+                None => {
+                    if !skipped.contains(&track.node_id.as_str()) {
+                        skipped.push(&track.node_id);
+                    }
+
+                    continue;
+                },
+            };
+
+            let (path,values,components,kind)=match track.values {
+                TrackValues::Translation( ref values ) => ("translation", values.iter().flat_map(|v| v.to_vec()).collect::<Vec<f32>>(), 3, "VEC3"),
+                TrackValues::Scale( ref values ) => ("scale", values.iter().flat_map(|v| v.to_vec()).collect::<Vec<f32>>(), 3, "VEC3"),
+                TrackValues::Rotation( ref values ) =>
+                    ("rotation", values.iter().flat_map(|q| { let q=q.normalize(); vec![q.x, q.y, q.z, q.w] }).collect::<Vec<f32>>(), 4, "VEC4"),
+            };
+
+            let is_step=track.interpolation.iter().all(|interpolation| *interpolation==Interpolation::Step);
+
+            let (times,values)=if is_step {
+                (track.times.clone(), values)
+            }else{
+                close_step_segments(track, &values, components)
+            };
+
+            let input=self.push_floats(&times, 1, "SCALAR", None, true)?;
+            let output=self.push_floats(&values, components, kind, None, false)?;
+
+            samplers.push( json!({
+                "input":input,
+                "output":output,
+                "interpolation":if is_step { "STEP" } else { "LINEAR" },
+            }) );
+
+            channels.push( json!({
+                "sampler":samplers.len()-1,
+                "target":{"node":node_index, "path":path},
+            }) );
+        }
+
+        if skipped.len()>0 {
+            self.warnings.push( format!("Animation \"{}\" has tracks of nodes, that are not exported to glTF: {}", name, skipped.join(", ")) );
+        }
+
+        //glTF animation must have channels
+        if channels.len()>0 {
+            self.animations.push( json!({
+                "name":name,
+                "channels":channels,
+                "samplers":samplers,
+            }) );
+        }
+
+        Ok(())
+    }
+
+    ///Json of document and warnings, buffer is described by `buffer`
+    fn to_json(self, buffer:Value) -> Result<(String,Vec<u8>,Vec<String>),Error> {
+        let mut nodes=Vec::with_capacity(self.nodes.len());
+
+        for node in self.nodes.iter() {
+            let position=&node.location.position;
+            let rotation=node.location.rotation.normalize();
+            let scale=&node.location.scale;
+            let name=format!("Location of node \"{}\"", node.name);
+
+            let mut json=json!({
+                "name":node.name,
+                "translation":floats(&[position.x, position.y, position.z], &name)?,
+                "rotation":floats(&[rotation.x, rotation.y, rotation.z, rotation.w], &name)?,
+                "scale":floats(&[scale.x, scale.y, scale.z], &name)?,
+            });
+
+            if node.children.len()>0 {
+                json["children"]=json!(node.children);
+            }
+
+            match node.mesh {
+                Some( mesh ) => json["mesh"]=json!(mesh),
+                None => {},
+            }
+
+            match node.skin {
+                Some( skin ) => json["skin"]=json!(skin),
+                None => {},
+            }
+
+            match node.camera {
+                Some( camera ) => json["camera"]=json!(camera),
+                None => {},
+            }
+
+            nodes.push(json);
+        }
+
+        let mut root=json!({
+            "asset":{"version":"2.0", "generator":"collada-rs"},
+        });
+
+        if self.scenes.len()>0 {
+            root["scene"]=json!(0);
+        }
+
+        //glTF forbids empty arrays
+        let arrays=vec![
+            ("scenes", self.scenes),
+            ("nodes", nodes),
+            ("meshes", self.meshes),
+            ("skins", self.skins),
+            ("cameras", self.cameras),
+            ("materials", self.materials),
+            ("animations", self.animations),
+            ("accessors", self.accessors),
+            ("bufferViews", self.buffer_views),
+        ];
+
+        for (name,array) in arrays {
+            if array.len()>0 {
+                root[name]=Value::Array(array);
+            }
+        }
+
+        let mut buffer_bytes=self.buffer;
+
+        if buffer_bytes.len()>0 {
+            root["buffers"]=json!([buffer]);
+        }
+
+        while buffer_bytes.len()%4!=0 {
+            buffer_bytes.push(0);
+        }
+
+        let text=match serde_json::to_string(&root) {
+            Ok( text ) => text,
+            Err( e ) => return Err(Error::Other( format!("Can not write glTF json: {}", e) )),
+        };
+
+        Ok( (text, buffer_bytes, self.warnings) )
+    }
+}
+
+///glTF sampler has one interpolation, so STEP segments of LINEAR track end with extra key,
+///that keeps value of segment until 1ms(or half of segment) before next key
+fn close_step_segments(track:&TrsTrack, values:&[f32], components:usize) -> (Vec<f32>,Vec<f32>) {
+    let mut times=Vec::with_capacity(track.times.len());
+    let mut closed=Vec::with_capacity(values.len());
+
+    for key in 0..track.times.len() {
+        let value=&values[key*components..(key+1)*components];
+
+        times.push(track.times[key]);
+        closed.extend_from_slice(value);
+
+        if track.interpolation[key]==Interpolation::Step && key+1<track.times.len() {
+            let duration=track.times[key+1]-track.times[key];
+
+            times.push(track.times[key+1]-(duration/2.0).min(0.001));
+            closed.extend_from_slice(value);
+        }
+    }
+
+    (times, closed)
+}
+
+///Approximation of phong(blinn, lambert) by metallic-roughness model:
+///diffuse becomes base color, shininess gives roughness, materials are not metallic
+fn material_json(material:&Material, effect:Option<&Arc<Effect>>) -> Result<Value,Error> {
+    let mut json=json!({"name":material.name});
+
+    let effect=match effect {
+        Some( effect ) => effect,
+        None => return Ok( json ),
+    };
+
+    let base_color=effect.base_color();
+
+    let roughness=match (effect.shading, effect.shininess) {
+        (Shading::Phong, Some( shininess )) | (Shading::Blinn, Some( shininess )) => (2.0/(shininess.max(0.0)+2.0)).sqrt(),
+        _ => 1.0,
+    };
+
+    let name=format!("Color of material \"{}\"", material.id);
+
+    json["pbrMetallicRoughness"]=json!({
+        "baseColorFactor":floats(&base_color, &name)?,
+        "metallicFactor":0.0,
+        "roughnessFactor":float(roughness, &name)?,
+    });
+
+    match effect.emission {
+        Some( ColorOrTexture::Color( color ) ) if color[0]>0.0 || color[1]>0.0 || color[2]>0.0 =>
+            json["emissiveFactor"]=floats(&[color[0].min(1.0), color[1].min(1.0), color[2].min(1.0)], &name)?,
+        _ => {},
+    }
+
+    if base_color[3]<1.0 {
+        json["alphaMode"]=json!("BLEND");
+    }
+
+    Ok( json )
+}
+
+///Triangulates polygons of mesh by fans and gives own vertex to every combination of indices of sources
+fn build_primitive(mesh:&Mesh, skin:Option<&Skin>) -> Result<Primitive,Error> {
+    let mut positions_source=None;
+    let mut normals_source=None;
+    let mut tex_coords_source=None;
+
+    for &(ref semantic, ref source) in mesh.sources.iter() {
+        match semantic.as_str() {
+            "VERTEX" => positions_source=Some( (semantic, get_float_layers(source, &["X","Y","Z"])?, source) ),
+            "NORMAL" => normals_source=Some( (semantic, get_float_layers(source, &["X","Y","Z"])?, source) ),
+            "TEXCOORD" if tex_coords_source.is_none() => tex_coords_source=Some( (semantic, get_float_layers(source, &["U","V"])?, source) ),
+            _ => {},
+        }
+    }
+
+    let positions_source=match positions_source {
+        Some( source ) => source,
+        None => return Err(Error::Other( format!("Mesh \"{}\" has no VERTEX input", mesh.name) )),
+    };
+
+    let (bind_matrix,influences)=match skin {
+        Some( skin ) => (Some( (skin.bind_shape_matrix.clone(), normal_matrix(&skin.bind_shape_matrix, &skin.id)?) ), Some( skin.influences::<4>()? )),
+        None => (None, None),
+    };
+
+    let used_sources:Vec<&String>=[Some(&positions_source), normals_source.as_ref(), tex_coords_source.as_ref()].iter()
+        .filter_map(|source| source.map(|source| source.0)).collect();
+    let mut indices_of_sources=Vec::with_capacity(used_sources.len());
+
+    for semantic in used_sources.iter() {
+        match mesh.vertex_indices.get(*semantic) {
+            Some( vertex_indices ) => indices_of_sources.push( &vertex_indices.indices ),
+            None => return Err(Error::Other( format!("Mesh \"{}\" has no indices of source \"{}\"", mesh.name, semantic) )),
+        }
+    }
+
+    let mut primitive=Primitive{
+        positions:Vec::new(),
+        normals:Vec::new(),
+        tex_coords:Vec::new(),
+        joints:Vec::new(),
+        weights:Vec::new(),
+        indices:Vec::new(),
+    };
+
+    let mut vertices:HashMap<Vec<usize>,u32>=HashMap::new();
+
+    for polygon in mesh.polygons.iter() {
+        let mut corners=Vec::with_capacity(polygon.vertices_count);
+
+        for corner in polygon.first_vertex_index..polygon.first_vertex_index+polygon.vertices_count {
+            let key:Vec<usize>=indices_of_sources.iter().map(|indices| indices[corner]).collect();
+
+            let vertex=match vertices.get(&key) {
+                Some( vertex ) => *vertex,
+                None => {
+                    let (_, ref layers, source)=positions_source;
+                    let index=key[0];
+                    let mut position=[get_value(layers[0], index, source)?, get_value(layers[1], index, source)?, get_value(layers[2], index, source)?];
+
+                    match bind_matrix {
+                        Some( (ref matrix, _) ) => position=matrix.transform_point(position),
+                        None => {},
+                    }
+
+                    primitive.positions.push(position);
+
+                    let mut next=1;
+
+                    match normals_source {
+                        Some( (_, ref layers, source) ) => {
+                            let index=key[next];
+                            let mut normal=[get_value(layers[0], index, source)?, get_value(layers[1], index, source)?, get_value(layers[2], index, source)?];
+
+                            match bind_matrix {
+                                Some( (_, ref normal_matrix) ) => normal=transform_normal(normal_matrix, normal),
+                                None => {},
+                            }
+
+                            primitive.normals.push( normalize(normal) );
+                            next+=1;
+                        },
+                        None => {},
+                    }
+
+                    match tex_coords_source {
+                        //glTF places origin of texture at top left corner
+                        Some( (_, ref layers, source) ) => {
+                            let index=key[next];
+                            primitive.tex_coords.push( [get_value(layers[0], index, source)?, 1.0-get_value(layers[1], index, source)?] );
+                        },
+                        None => {},
+                    }
+
+                    match influences {
+                        Some( ref influences ) => match (influences.joints.get(key[0]), influences.weights.get(key[0])) {
+                            (Some( joints ), Some( weights )) => {
+                                primitive.joints.push(*joints);
+                                primitive.weights.push(*weights);
+                            },
+                            _ => return Err(Error::Other( format!("Vertex {} of mesh \"{}\" has no weights in skin", key[0], mesh.name) )),
+                        },
+                        None => {},
+                    }
+
+                    let vertex=(primitive.positions.len()-1) as u32;
+                    vertices.insert(key, vertex);
+
+                    vertex
+                },
+            };
+
+            corners.push(vertex);
+        }
+
+        for i in 1..corners.len().saturating_sub(1) {
+            primitive.indices.extend_from_slice( &[corners[0], corners[i], corners[i+1]] );
+        }
+    }
+
+    Ok( primitive )
+}
+
+impl Document{
+    fn export_gltf<'a>(&'a self) -> Result<Exporter<'a>,Error> {
+        let mut exporter=Exporter::new(self);
+
+        exporter.export_materials()?;
+        exporter.export_scenes()?;
+        exporter.export_animations()?;
+
+        Ok( exporter )
+    }
+
+    ///Writes document as glTF 2.0 json and binary buffer, `bin_uri` is path of buffer relative to json file.
+    ///Returns warnings about parts of document, that glTF can not keep.
+    pub fn write_gltf<J:Write,B:Write>(&self, json:&mut J, bin:&mut B, bin_uri:&str) -> Result<Vec<String>,Error> {
+        let exporter=self.export_gltf()?;
+        let buffer_length=exporter.buffer.len();
+
+        let buffer=json!({"uri":bin_uri, "byteLength":buffer_length});
+
+        let (text,bytes,warnings)=exporter.to_json(buffer)?;
+
+        let result=json.write_all(text.as_bytes())
+            .and_then(|_| bin.write_all(&bytes[..buffer_length]));
+
+        match result {
+            Ok( _ ) => Ok( warnings ),
+            Err( e ) => Err(Error::WriteError(e)),
+        }
+    }
+
+    ///Writes document as binary glTF 2.0, json and buffer are chunks of one file(see `write_gltf`)
+    pub fn write_glb<W:Write>(&self, writer:&mut W) -> Result<Vec<String>,Error> {
+        let exporter=self.export_gltf()?;
+        let buffer=json!({"byteLength":exporter.buffer.len()});
+
+        let (text,bin,warnings)=exporter.to_json(buffer)?;
+
+        //json chunk is padded by spaces
+        let mut json=text.into_bytes();
+        while json.len()%4!=0 {
+            json.push(b' ');
+        }
+
+        let mut length=12+8+json.len();
+        if bin.len()>0 {
+            length+=8+bin.len();
+        }
+
+        let mut glb=Vec::with_capacity(length);
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"JSON");
+        glb.extend_from_slice(&json);
+
+        if bin.len()>0 {
+            glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+            glb.extend_from_slice(b"BIN\0");
+            glb.extend_from_slice(&bin);
+        }
+
+        match writer.write_all(&glb) {
+            Ok( _ ) => Ok( warnings ),
+            Err( e ) => Err(Error::WriteError(e)),
+        }
+    }
+}
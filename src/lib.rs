@@ -1,4 +1,7 @@
 extern crate xmltree;
+#[macro_use]
+extern crate serde_json;
+extern crate toml;
 
 mod string_ext;
 pub use string_ext::StringExt;
@@ -16,16 +19,25 @@ mod document;
 pub use document::Document;
 
 mod asset;
-pub use asset::{Asset,Axis,Editor};
+pub use asset::{Asset,Axis,Editor,Fixups,LoadOptions};
 
 mod camera;
 pub use camera::Camera;
 
+mod light;
+pub use light::{Light,LightKind};
+
+mod material;
+pub use material::Material;
+
+mod effect;
+pub use effect::{Effect,Shading,ColorOrTexture};
+
 mod source;
 pub use source::{Source,SourceLayer};
 
 mod mesh;
-pub use mesh::{Mesh,VertexIndices};
+pub use mesh::{Mesh,Polygon,VertexIndices};
 
 mod geometry;
 pub use geometry::Geometry;
@@ -33,23 +45,63 @@ pub use geometry::Geometry;
 mod controller;
 pub use controller::Controller;
 
+mod morph;
+pub use morph::{Morph,MorphMethod};
+
 mod skin;
-pub use skin::{Skin,BoneIndices};
+pub use skin::{Skin,BoneIndices,Influences,Joint,SkinningMethod,DeformedMesh,SkinPartition};
 
 mod location;
-pub use location::{Location,Position,Scale,Quaternion,Matrix};
+pub use location::{Location,Position,Scale,Quaternion,Matrix,TransformElement};
+
+mod pose;
+pub use pose::Pose;
 
 mod node;
 pub use node::Node;
 
 mod skeleton;
-pub use skeleton::{Bone,Skeleton};
+pub use skeleton::{Bone,Skeleton,DepthFirstIter,BreadthFirstIter};
+
+mod channel;
+pub use channel::{Channel,ChannelValues,ChannelValue,Interpolation,SampleMode,Tangent};
+
+mod target;
+pub use target::{Selector,Target,TargetAddress};
 
 mod animation;
-pub use animation::Animation;
+pub use animation::{Animation,Sampler};
+
+mod clip;
+pub use clip::{AnimationClip,Clip,ClipRange,ManifestFormat};
+
+mod manifest;
+
+mod trs;
+pub use trs::{TrsTrack,TrackValues,CURVE_FPS};
+
+mod root_motion;
+pub use root_motion::{RootMotion,RootMotionAxes};
+
+mod mirror;
+pub use mirror::{MirrorMap,MirrorPattern};
+
+mod retarget;
+pub use retarget::retarget;
+
+mod reduction;
+pub use reduction::{KeyError,Reduction};
 
 mod scene;
 pub use scene::Scene;
 
+mod builder;
+pub use builder::{GeometryBuilder,MeshBuilder,SceneBuilder,SkeletonBuilder};
+
+mod writer;
+pub use writer::WriteOptions;
+
+mod gltf;
+
 mod tree_printer;
 pub use tree_printer::TreePrinter;
@@ -1 +1,96 @@
- 
+use Error;
+use XMLElement;
+use xmltree::Element;
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+
+use ArrayIter;
+
+#[derive(Copy,Clone,Eq,PartialEq,Debug)]
+pub enum LightKind{
+    Ambient,
+    Directional,
+    Point,
+    Spot,
+}
+
+pub struct Light{
+    pub id:String,
+    pub name:String,
+    pub kind:LightKind,
+    pub color:[f32;3],
+}
+
+impl Light{
+    pub fn parse(light:&Element) -> Result<Light,Error>{
+        let id=light.get_attribute("id")?.clone();
+        let name=match light.attributes.get("name") {
+            Some( name ) => name.clone(),
+            None => id.clone(),
+        };
+
+        let technique=light.get_element("technique_common")?;
+
+        let (kind,kind_element)=match technique.children.iter().next() {
+            Some( kind_element ) => {
+                let kind=match kind_element.name.as_str() {
+                    "ambient" => LightKind::Ambient,
+                    "directional" => LightKind::Directional,
+                    "point" => LightKind::Point,
+                    "spot" => LightKind::Spot,
+                    _ => return Err(Error::Other( format!("Light \"{}\" has type {}, expected ambient, directional, point or spot", id, kind_element.name) )),
+                };
+
+                (kind,kind_element)
+            },
+            None => return Err(Error::Other( format!("Light \"{}\" has no type", id) )),
+        };
+
+        let color_text=match kind_element.get_element("color") {
+            Ok( color_element ) => color_element.get_text()?,
+            Err( _ ) => return Err(Error::Other( format!("Light \"{}\" has no color", id) )),
+        };
+        let mut color_iter=ArrayIter::new(color_text, 3, "color");
+        let color=[color_iter.read_f32()?, color_iter.read_f32()?, color_iter.read_f32()?];
+
+        Ok(
+            Light{
+                id:id,
+                name:name,
+                kind:kind,
+                color:color,
+            }
+        )
+    }
+}
+
+///Reads lights, lights of unknown type or without color are skipped with warning
+pub fn parse_lights(root:&Element, warnings:&mut Vec<String>) -> Result< HashMap<String,Arc<Light>>, Error>{
+    let lights_element=match root.get_element("library_lights") {
+        Ok( lights_element ) => lights_element,
+        Err( _ ) => return Ok( HashMap::new() ),
+    };
+
+    let mut lights=HashMap::new();
+
+    for light_element in lights_element.children.iter(){
+        if light_element.name.as_str()=="light" {
+            let light=match Light::parse(&light_element) {
+                Ok( light ) => light,
+                Err( error ) => {
+                    warnings.push( format!("Light is skipped: {}", error) );
+                    continue;
+                },
+            };
+
+            match lights.entry(light.id.clone()){
+                Entry::Occupied(_) => return Err(Error::Other( format!("Duplicate light with id \"{}\"", &light.id) )),
+                Entry::Vacant(entry) => { entry.insert(Arc::new(light)); },
+            }
+        }
+    }
+
+    Ok(lights)
+}
@@ -1,11 +1,11 @@
 use std;
 use Error;
 use XMLElement;
+use StringExt;
 use xmltree::Element;
 
 use Asset;
 use Axis;
-use Editor;
 
 #[derive(Clone)]
 pub struct Position{
@@ -30,10 +30,16 @@ impl Position{
             Axis::Z => Position::new(x,z,y),//blender
         };
 
-        if asset.editor==Editor::Blender {
+        if asset.fixups.flip_x {
             position.x=-position.x;
         }
 
+        let unit_scale=asset.unit_scale();
+
+        position.x*=unit_scale;
+        position.y*=unit_scale;
+        position.z*=unit_scale;
+
         position
     }
 
@@ -165,6 +171,60 @@ impl Quaternion {
         Ok(rotation)
     }
 
+    pub fn identity() -> Self{
+        Quaternion::new(0.0,0.0,0.0,1.0)
+    }
+
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn dot(&self, other:&Quaternion) -> f32 {
+        self.x*other.x + self.y*other.y + self.z*other.z + self.w*other.w
+    }
+
+    pub fn rotate(&self, v:[f32;3]) -> [f32;3] {
+        //v+2w(q×v)+2q×(q×v)
+        let t=[
+            2.0*(self.y*v[2] - self.z*v[1]),
+            2.0*(self.z*v[0] - self.x*v[2]),
+            2.0*(self.x*v[1] - self.y*v[0]),
+        ];
+
+        [
+            v[0] + self.w*t[0] + self.y*t[2] - self.z*t[1],
+            v[1] + self.w*t[1] + self.z*t[0] - self.x*t[2],
+            v[2] + self.w*t[2] + self.x*t[1] - self.y*t[0],
+        ]
+    }
+
+    ///Spherical interpolation by shortest arc
+    pub fn slerp(&self, other:&Quaternion, s:f32) -> Quaternion {
+        let mut cos=self.dot(other);
+        let mut other=other.clone();
+
+        if cos<0.0 {
+            cos=-cos;
+            other=Quaternion::new(-other.x, -other.y, -other.z, -other.w);
+        }
+
+        let (a,b)=if cos>0.9995 {
+            (1.0-s, s)
+        }else{
+            let angle=cos.acos();
+            let sin=angle.sin();
+
+            (((1.0-s)*angle).sin()/sin, (s*angle).sin()/sin)
+        };
+
+        Quaternion::new(
+            a*self.x + b*other.x,
+            a*self.y + b*other.y,
+            a*self.z + b*other.z,
+            a*self.w + b*other.w
+        ).normalize()
+    }
+
     pub fn magnitude(&self) -> f32 {
         (self.w.powi(2)+self.x.powi(2)+self.y.powi(2)+self.z.powi(2)).sqrt()
     }
@@ -187,7 +247,7 @@ impl Quaternion {
             Axis::Z => Quaternion::new(x,z,y,w),//blender
         };
 
-        if asset.editor==Editor::Blender {
+        if asset.fixups.flip_x {
             quat.x=-quat.x;
         }
 
@@ -195,7 +255,7 @@ impl Quaternion {
     }
 
     pub fn with_axis_angle_and_asset(x:f32,y:f32,z:f32,a:f32,asset:&Asset) -> Self {
-        let angle=a/(180.0/3.14);
+        let angle=a.to_radians();
 
         let sin_a = (angle / 2.0).sin();
         let cos_a = (angle / 2.0).cos();
@@ -204,6 +264,34 @@ impl Quaternion {
 
         quat.normalize()
     }
+
+    ///Rotation by angles in degrees about X, Y and Z, composed like `<rotate>` elements Z, Y, X of node
+    pub fn with_euler_angles(angles:[f32;3]) -> Self {
+        let half=|i:usize| angles[i].to_radians()/2.0;
+
+        let qx=Quaternion::new(half(0).sin(), 0.0, 0.0, half(0).cos());
+        let qy=Quaternion::new(0.0, half(1).sin(), 0.0, half(1).cos());
+        let qz=Quaternion::new(0.0, 0.0, half(2).sin(), half(2).cos());
+
+        qz*qy*qx
+    }
+
+    ///Angles in degrees about X, Y and Z, inverse of `with_euler_angles`
+    pub fn euler_angles(&self) -> [f32;3] {
+        let (x,y,z,w)=(self.x, self.y, self.z, self.w);
+
+        let sin_y=(-2.0*(x*z - w*y)).max(-1.0).min(1.0);
+        let angle_y=sin_y.asin();
+
+        //gimbal lock, rotation about X is merged to rotation about Z
+        let (angle_x,angle_z)=if sin_y.abs()>0.9999 {
+            (0.0, (-2.0*(x*y - w*z)).atan2(1.0 - 2.0*(x*x + z*z)))
+        }else{
+            ((2.0*(y*z + w*x)).atan2(1.0 - 2.0*(x*x + y*y)), (2.0*(x*y + w*z)).atan2(1.0 - 2.0*(y*y + z*z)))
+        };
+
+        [angle_x.to_degrees(), angle_y.to_degrees(), angle_z.to_degrees()]
+    }
 }
 
 impl std::ops::Mul for Quaternion {
@@ -231,6 +319,17 @@ impl Matrix{
         }
     }
 
+    pub fn identity() -> Matrix{
+        Matrix {
+            mat:[
+                1.0, 0.0, 0.0, 0.0,
+                0.0, 1.0, 0.0, 0.0,
+                0.0, 0.0, 1.0, 0.0,
+                0.0, 0.0, 0.0, 1.0,
+            ],
+        }
+    }
+
     pub fn parse(text:&String) -> Result<Matrix,Error>{
         let mut values=[0.0;16];
 
@@ -258,6 +357,12 @@ impl Matrix{
     }
 
     pub fn to_quat(&self, asset:&Asset) -> Quaternion {
+        let quat=self.to_raw_quat();
+
+        Quaternion::with_asset(quat.x, quat.y, quat.z, quat.w, asset).normalize()
+    }
+
+    fn to_raw_quat(&self) -> Quaternion {
         let mat=&self.mat;
 
         let t=mat[0] + mat[5] + mat[10] + 1.0;
@@ -269,34 +374,131 @@ impl Matrix{
             let y = ( mat[2] - mat[8] ) * s;
             let z = ( mat[4] - mat[1] ) * s;
 
-            Quaternion::with_asset(x, y, z, w, asset).normalize()
-        }else if mat[0]>mat[5] && mat[0]>mat[10] {
-            let s = (( 1.0 + mat[0] - mat[5] - mat[10] ) * 2.0).sqrt();
-            let x = 0.5 / s;
+            Quaternion::new(x, y, z, w).normalize()
+        }else if mat[0]>=mat[5] && mat[0]>=mat[10] {
+            let s = ( 1.0 + mat[0] - mat[5] - mat[10] ).sqrt() * 2.0;
+            let x = 0.25 * s;
             let y = (mat[1] + mat[4] ) / s;
             let z = (mat[2] + mat[8] ) / s;
-            let w = (mat[6] + mat[9] ) / s;
+            let w = (mat[9] - mat[6] ) / s;
 
-            Quaternion::with_asset(x, y, z, w, asset).normalize()
-        }else if mat[5]>mat[0] && mat[5]>mat[10] {
-            let s = (( 1.0 + mat[5] - mat[0] - mat[10] ) * 2.0).sqrt();
+            Quaternion::new(x, y, z, w).normalize()
+        }else if mat[5]>=mat[10] {
+            let s = ( 1.0 + mat[5] - mat[0] - mat[10] ).sqrt() * 2.0;
             let x = (mat[1] + mat[4] ) / s;
-            let y = 0.5 / s;
+            let y = 0.25 * s;
             let z = (mat[6] + mat[9] ) / s;
-            let w = (mat[2] + mat[8] ) / s;
+            let w = (mat[2] - mat[8] ) / s;
 
-            Quaternion::with_asset(x, y, z, w, asset).normalize()
+            Quaternion::new(x, y, z, w).normalize()
         }else{
-            let s = (( 1.0 + mat[10] - mat[0] - mat[5] ) * 2.0).sqrt();
+            let s = ( 1.0 + mat[10] - mat[0] - mat[5] ).sqrt() * 2.0;
             let x = (mat[2] + mat[8] ) / s;
             let y = (mat[6] + mat[9] ) / s;
-            let z = 0.5 / s;
-            let w = (mat[1] + mat[4] ) / s;
+            let z = 0.25 * s;
+            let w = (mat[4] - mat[1] ) / s;
 
-            Quaternion::with_asset(x, y, z, w, asset).normalize()
+            Quaternion::new(x, y, z, w).normalize()
         }
     }
 
+    pub fn multiply(&self, other:&Matrix) -> Matrix {
+        let mut mat=[0.0;16];
+
+        for row in 0..4 {
+            for col in 0..4 {
+                let mut v=0.0;
+
+                for k in 0..4 {
+                    v+=self.mat[row*4+k]*other.mat[k*4+col];
+                }
+
+                mat[row*4+col]=v;
+            }
+        }
+
+        Matrix::from(mat)
+    }
+
+    pub fn transform_point(&self, p:[f32;3]) -> [f32;3] {
+        let m=&self.mat;
+
+        [
+            m[0]*p[0] + m[1]*p[1] + m[2]*p[2] + m[3],
+            m[4]*p[0] + m[5]*p[1] + m[6]*p[2] + m[7],
+            m[8]*p[0] + m[9]*p[1] + m[10]*p[2] + m[11],
+        ]
+    }
+
+    pub fn transform_vector(&self, v:[f32;3]) -> [f32;3] {
+        let m=&self.mat;
+
+        [
+            m[0]*v[0] + m[1]*v[1] + m[2]*v[2],
+            m[4]*v[0] + m[5]*v[1] + m[6]*v[2],
+            m[8]*v[0] + m[9]*v[1] + m[10]*v[2],
+        ]
+    }
+
+    ///Splits matrix to position, scale and rotation without conversion of axes(matrix should be already converted)
+    pub fn decompose(&self) -> Location {
+        let m=&self.mat;
+
+        let position=Position::new(m[3], m[7], m[11]);
+
+        let mut scale=[
+            (m[0].powi(2) + m[4].powi(2) + m[8].powi(2)).sqrt(),
+            (m[1].powi(2) + m[5].powi(2) + m[9].powi(2)).sqrt(),
+            (m[2].powi(2) + m[6].powi(2) + m[10].powi(2)).sqrt(),
+        ];
+
+        let determinant=
+            m[0]*(m[5]*m[10] - m[6]*m[9]) -
+            m[1]*(m[4]*m[10] - m[6]*m[8]) +
+            m[2]*(m[4]*m[9] - m[5]*m[8]);
+
+        if determinant<0.0 {
+            scale[0]=-scale[0];
+        }
+
+        let mut rotation_matrix=Matrix::identity();
+
+        for row in 0..3 {
+            for col in 0..3 {
+                rotation_matrix.mat[row*4+col]=if scale[col]!=0.0 { m[row*4+col]/scale[col] } else { 0.0 };
+            }
+        }
+
+        let rotation=rotation_matrix.to_raw_quat();
+
+        Location::new(position, Scale::new(scale[0], scale[1], scale[2]), rotation)
+    }
+
+    ///Converts matrix of document to Y-up meters, like positions are converted.
+    ///Unlike `to_location` it keeps exact scale and shear.
+    pub fn with_asset(&self, asset:&Asset) -> Matrix {
+        //axis of document for each converted axis, matrix becomes C*M*C⁻¹
+        let axes=match asset.up_axis {
+            Axis::X => [1,0,2],
+            Axis::Y => [0,1,2],
+            Axis::Z => [0,2,1],
+        };
+
+        let sign=|axis:usize| if axis==0 && asset.fixups.flip_x { -1.0 } else { 1.0 };
+
+        let mut matrix=Matrix::identity();
+
+        for row in 0..3 {
+            for col in 0..3 {
+                matrix.mat[row*4+col]=sign(row)*sign(col)*self.mat[axes[row]*4+axes[col]];
+            }
+
+            matrix.mat[row*4+3]=sign(row)*self.mat[axes[row]*4+3]*asset.unit_scale();
+        }
+
+        matrix
+    }
+
     pub fn to_location(&self, asset:&Asset) -> Location {
         let position = Position::with_asset(self.mat[3], self.mat[7], self.mat[11], asset);
 
@@ -328,4 +530,122 @@ impl Location {
             rotation:rotation,
         }
     }
+
+    pub fn identity() -> Self {
+        Location::new(Position::new(0.0, 0.0, 0.0), Scale::new(1.0, 1.0, 1.0), Quaternion::identity())
+    }
+
+    ///Lerps position and scale, slerps rotation
+    pub fn interpolate(&self, other:&Location, s:f32) -> Location {
+        let lerp=|a:f32,b:f32| a+(b-a)*s;
+
+        Location::new(
+            Position::new(lerp(self.position.x, other.position.x), lerp(self.position.y, other.position.y), lerp(self.position.z, other.position.z)),
+            Scale::new(lerp(self.scale.x, other.scale.x), lerp(self.scale.y, other.scale.y), lerp(self.scale.z, other.scale.z)),
+            self.rotation.slerp(&other.rotation, s)
+        )
+    }
+
+    ///Matrix of translation*rotation*scale
+    pub fn to_matrix(&self) -> Matrix {
+        let q=&self.rotation;
+        let s=[self.scale.x, self.scale.y, self.scale.z];
+
+        let rotation=[
+            1.0-2.0*(q.y*q.y + q.z*q.z), 2.0*(q.x*q.y - q.z*q.w),     2.0*(q.x*q.z + q.y*q.w),
+            2.0*(q.x*q.y + q.z*q.w),     1.0-2.0*(q.x*q.x + q.z*q.z), 2.0*(q.y*q.z - q.x*q.w),
+            2.0*(q.x*q.z - q.y*q.w),     2.0*(q.y*q.z + q.x*q.w),     1.0-2.0*(q.x*q.x + q.y*q.y),
+        ];
+
+        let mut matrix=Matrix::identity();
+
+        for row in 0..3 {
+            for col in 0..3 {
+                matrix.mat[row*4+col]=rotation[row*3+col]*s[col];
+            }
+        }
+
+        matrix.mat[3]=self.position.x;
+        matrix.mat[7]=self.position.y;
+        matrix.mat[11]=self.position.z;
+
+        matrix
+    }
+}
+
+///Transform element of node: `<matrix>`, `<translate>`, `<rotate>`, `<scale>`, `<lookat>` or `<skew>`.
+///Values of first four are converted to Y-up meters(axis of `<rotate>` is converted, angle is not),
+///values of `<lookat>` and `<skew>` are not converted.
+#[derive(Clone,Debug)]
+pub struct TransformElement{
+    pub name:String,
+    pub sid:Option<String>,
+    pub values:Vec<f32>,
+}
+
+impl TransformElement{
+    ///Transform elements of node in order of document
+    pub fn parse_all(node:&Element, asset:&Asset) -> Result<Vec<TransformElement>,Error> {
+        let mut transforms=Vec::new();
+
+        for element in node.children.iter(){
+            let count=match element.name.as_str() {
+                "matrix" => 16,
+                "translate" | "scale" => 3,
+                "rotate" => 4,
+                "lookat" => 9,
+                "skew" => 7,
+                _ => continue,
+            };
+
+            let mut values=Vec::with_capacity(count);
+
+            for v in element.get_text()?.split_whitespace() {
+                values.push( v.parse_as_f32(&element.name)? );
+            }
+
+            if values.len()!=count {
+                return Err(Error::Other( format!("<{}> has {} values, expected {}", element.name, values.len(), count) ));
+            }
+
+            let values=match element.name.as_str() {
+                "matrix" => {
+                    let mut mat=[0.0;16];
+                    mat.copy_from_slice(&values);
+
+                    Matrix::from(mat).with_asset(asset).mat.to_vec()
+                },
+                "translate" => {
+                    let position=Position::with_asset(values[0], values[1], values[2], asset);
+                    vec![position.x, position.y, position.z]
+                },
+                "scale" => {
+                    let scale=Scale::with_asset(values[0], values[1], values[2], asset);
+                    vec![scale.x, scale.y, scale.z]
+                },
+                "rotate" => {
+                    let axis=Quaternion::with_asset(values[0], values[1], values[2], 0.0, asset);
+                    vec![axis.x, axis.y, axis.z, values[3]]
+                },
+                _ => values,
+            };
+
+            transforms.push( TransformElement{
+                name:element.name.clone(),
+                sid:element.attributes.get("sid").cloned(),
+                values:values,
+            } );
+        }
+
+        Ok( transforms )
+    }
+
+    ///`<matrix sid="transform">`, like nodes are written
+    pub fn transform(matrix:&Matrix) -> TransformElement {
+        TransformElement{
+            name:String::from("matrix"),
+            sid:Some( String::from("transform") ),
+            values:matrix.mat.to_vec(),
+        }
+    }
 }
@@ -0,0 +1,101 @@
+use Error;
+
+use serde_json;
+use serde_json::Value;
+use toml;
+
+use ClipRange;
+use ManifestFormat;
+
+///Reads fps and clip ranges from JSON or TOML text
+pub fn parse_manifest(text:&str, format:ManifestFormat) -> Result<(Option<f32>,Vec<ClipRange>),Error> {
+    let value=match format {
+        ManifestFormat::Json => match serde_json::from_str::<Value>(text) {
+            Ok( value ) => value,
+            Err( e ) => return Err(Error::Other( format!("Manifest is not valid JSON: {}", e) )),
+        },
+        //TOML is converted to JSON value, tables keep order of keys
+        ManifestFormat::Toml => {
+            let table=match text.parse::<toml::Table>() {
+                Ok( table ) => table,
+                Err( e ) => return Err(Error::Other( format!("Manifest is not valid TOML: {}", e) )),
+            };
+
+            match serde_json::to_value(table) {
+                Ok( value ) => value,
+                Err( e ) => return Err(Error::Other( format!("Manifest has unsupported TOML value: {}", e) )),
+            }
+        },
+    };
+
+    read_manifest(value)
+}
+
+///Array of clips or object with optional fps, `clips` array and clips with name of key
+fn read_manifest(value:Value) -> Result<(Option<f32>,Vec<ClipRange>),Error> {
+    let members=match value {
+        Value::Array( clips ) => {
+            let mut ranges=Vec::with_capacity(clips.len());
+
+            for clip in clips.into_iter() {
+                ranges.push( read_range(None, clip)? );
+            }
+
+            return Ok( (None,ranges) );
+        },
+        Value::Object( members ) => members,
+        _ => return Err(Error::Other( String::from("Manifest must be object or array of clips") )),
+    };
+
+    let mut fps=None;
+    let mut ranges=Vec::new();
+
+    for (key,value) in members.into_iter() {
+        match (key.as_str(), value) {
+            ("fps", Value::Number( number )) => {
+                match number.as_f64() {
+                    Some( number ) if number>0.0 => fps=Some(number as f32),
+                    _ => return Err(Error::Other( format!("Manifest has bad fps {}", number) )),
+                }
+            },
+            ("fps", _) => return Err(Error::Other( String::from("Fps of manifest must be number") )),
+            ("clips", Value::Array( clips )) => {
+                for clip in clips.into_iter() {
+                    ranges.push( read_range(None, clip)? );
+                }
+            },
+            ("clips", _) => return Err(Error::Other( String::from("Clips of manifest must be array") )),
+            (_, clip @ Value::Object( _ )) => ranges.push( read_range(Some(key.clone()), clip)? ),
+            _ => return Err(Error::Other( format!("Manifest has unknown value \"{}\"", key) )),
+        }
+    }
+
+    Ok( (fps,ranges) )
+}
+
+///Clip object has start, end and name, if name is not key of object
+fn read_range(key:Option<String>, clip:Value) -> Result<ClipRange,Error> {
+    let members=match clip {
+        Value::Object( members ) => members,
+        _ => return Err(Error::Other( String::from("Clip of manifest must be object") )),
+    };
+
+    let mut name=key;
+    let mut start=None;
+    let mut end=None;
+
+    for (key,value) in members.into_iter() {
+        match (key.as_str(), value) {
+            ("name", Value::String( string )) => name=Some(string),
+            ("start", Value::Number( ref number )) => start=number.as_f64(),
+            ("end", Value::Number( ref number )) => end=number.as_f64(),
+            ("name", _) | ("start", _) | ("end", _) => return Err(Error::Other( format!("Clip of manifest has bad \"{}\" value", key) )),
+            _ => {},
+        }
+    }
+
+    match (name, start, end) {
+        (Some( name ), Some( start ), Some( end )) => Ok( ClipRange{ name:name, start:start as f32, end:end as f32 } ),
+        _ => Err(Error::Other( String::from("Clip of manifest must have name, start and end") )),
+    }
+}
@@ -0,0 +1,55 @@
+use Error;
+use XMLElement;
+use xmltree::Element;
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+
+pub struct Material{
+    pub id:String,
+    pub name:String,
+    pub effect_id:String,
+}
+
+impl Material{
+    pub fn parse(material:&Element) -> Result<Material,Error>{
+        let id=material.get_attribute("id")?.clone();
+        let name=match material.attributes.get("name") {
+            Some( name ) => name.clone(),
+            None => id.clone(),
+        };
+
+        let effect_id=material.get_element("instance_effect")?.get_attribute("url")?.trim_left_matches('#').to_string();
+
+        Ok(
+            Material{
+                id:id,
+                name:name,
+                effect_id:effect_id,
+            }
+        )
+    }
+}
+
+pub fn parse_materials(root:&Element) -> Result< HashMap<String,Arc<Material>>, Error>{
+    let materials_element=match root.get_element("library_materials") {
+        Ok( materials_element ) => materials_element,
+        Err( _ ) => return Ok( HashMap::new() ),
+    };
+
+    let mut materials=HashMap::new();
+
+    for material_element in materials_element.children.iter(){
+        if material_element.name.as_str()=="material" {
+            let material=Material::parse(&material_element)?;
+
+            match materials.entry(material.id.clone()){
+                Entry::Occupied(_) => return Err(Error::Other( format!("Duplicate material with id \"{}\"", &material.id) )),
+                Entry::Vacant(entry) => { entry.insert(Arc::new(material)); },
+            }
+        }
+    }
+
+    Ok(materials)
+}
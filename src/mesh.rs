@@ -49,7 +49,7 @@ impl Mesh{
         let all_sources=read_sources(mesh, asset)?;
 
         for polylist in mesh.children.iter(){
-            if polylist.name.as_str()=="polylist"{
+            if polylist.name.as_str()=="polylist" || polylist.name.as_str()=="triangles" {
                 let material=match polylist.attributes.get("material"){
                     Some(m) => Some(m.clone()),
                     None => None,
@@ -102,6 +102,14 @@ impl Mesh{
 
     pub fn read_polygons(polylist:&Element) -> Result<(Vec<Polygon>,usize),Error>{//read polygons(<vcount> tag)
         let polygons_count=polylist.parse_attribute_as_usize("count")?;
+
+        //<triangles> has no <vcount>
+        if polylist.name.as_str()=="triangles" {
+            let polygons=(0..polygons_count).map(|i| Polygon{ first_vertex_index:i*3, vertices_count:3 }).collect();
+
+            return Ok((polygons,polygons_count*3));
+        }
+
         let polygons_vcount=polylist.get_element("vcount")?.get_text()?;
 
         let mut polygons=Vec::with_capacity(polygons_count);
@@ -164,6 +172,52 @@ impl Mesh{
         Ok(vertex_indices)
     }
 
+    ///Mesh with selected polygons only, sources are shared
+    pub fn subset(&self, polygon_indices:&[usize]) -> Mesh {
+        let mut polygons=Vec::with_capacity(polygon_indices.len());
+        let mut vertex_indices_indices:HashMap<&String,Vec<usize>>=self.vertex_indices.keys().map(|name| (name,Vec::new())).collect();
+        let mut vertex_indices_count=0;
+
+        for &polygon_index in polygon_indices.iter() {
+            let polygon=&self.polygons[polygon_index];
+
+            polygons.push(
+                Polygon{
+                    first_vertex_index:vertex_indices_count,
+                    vertices_count:polygon.vertices_count,
+                }
+            );
+
+            vertex_indices_count+=polygon.vertices_count;
+
+            for (name,vertex_indices) in self.vertex_indices.iter() {
+                let indices=vertex_indices_indices.get_mut(name).unwrap();
+                indices.extend_from_slice( &vertex_indices.indices[polygon.first_vertex_index..polygon.first_vertex_index+polygon.vertices_count] );
+            }
+        }
+
+        let vertex_indices=self.vertex_indices.iter().map(|(name,vertex_indices)|
+            (
+                name.clone(),
+                Arc::new( VertexIndices{
+                    source:vertex_indices.source.clone(),
+                    indices:vertex_indices_indices.remove(name).unwrap(),
+                } ),
+            )
+        ).collect();
+
+        Mesh{
+            id:self.id,
+            name:self.name.clone(),
+            material:self.material.clone(),
+            short_vertex_format:self.short_vertex_format.clone(),
+            vertex_format:self.vertex_format.clone(),
+            sources:self.sources.clone(),
+            polygons:polygons,
+            vertex_indices:vertex_indices,
+        }
+    }
+
     pub fn print(&self, printer:TreePrinter) {
         match self.material{
             Some(ref material) => {
@@ -0,0 +1,234 @@
+use Error;
+
+use std::collections::HashMap;
+
+use Axis;
+use Channel;
+use ChannelValue;
+use Clip;
+use Location;
+use Position;
+use Quaternion;
+use Selector;
+use Skeleton;
+use Tangent;
+use TargetAddress;
+
+///Rule of naming of left and right bones, like `Hand.l` and `Hand.r`, `LeftArm` and `RightArm` or `L_Hand` and `R_Hand`.
+///Token may be prefix, suffix or part of name, but it must be separate word: it is bounded by ends of name,
+///separators or camel case, so `Hand_r_2` matches `_r`, but `Hand_rest` does not.
+#[derive(Clone,Debug)]
+pub struct MirrorPattern{
+    ///left and right tokens
+    pub pairs:Vec<(String,String)>,
+}
+
+///Ids of paired bones in both directions
+#[derive(Clone,Debug)]
+pub struct MirrorMap{
+    pub pairs:HashMap<String,String>,
+}
+
+impl MirrorPattern{
+    pub fn new(left:&str, right:&str) -> MirrorPattern {
+        MirrorPattern{
+            pairs:vec![ (String::from(left), String::from(right)) ],
+        }
+    }
+
+    ///`.l`/`.r` and `_l`/`_r` in lower and upper case
+    pub fn blender() -> MirrorPattern {
+        let tokens=[(".l",".r"), ("_l","_r"), (".L",".R"), ("_L","_R")];
+
+        MirrorPattern{
+            pairs:tokens.iter().map(|&(left,right)| (String::from(left), String::from(right))).collect(),
+        }
+    }
+
+    ///Name of other side, None if name has no token
+    pub fn mirror_name(&self, name:&str) -> Option<String> {
+        for &(ref left, ref right) in self.pairs.iter() {
+            match find_token(name, left) {
+                Some( pos ) => return Some( format!("{}{}{}", &name[..pos], right, &name[pos+left.len()..]) ),
+                None => {},
+            }
+
+            match find_token(name, right) {
+                Some( pos ) => return Some( format!("{}{}{}", &name[..pos], left, &name[pos+right.len()..]) ),
+                None => {},
+            }
+        }
+
+        None
+    }
+}
+
+///Last position of token, that is separate word of name
+fn find_token(name:&str, token:&str) -> Option<usize> {
+    let (first,last)=match (token.chars().next(), token.chars().last()) {
+        (Some( first ), Some( last )) => (first,last),
+        _ => return None,
+    };
+
+    name.match_indices(token).map(|(pos,_)| pos).filter(|pos| {
+        let starts=match name[..*pos].chars().last() {
+            Some( before ) => is_word_boundary(before, first),
+            None => true,
+        };
+
+        let ends=match name[pos+token.len()..].chars().next() {
+            Some( after ) => is_word_boundary(last, after),
+            None => true,
+        };
+
+        starts && ends
+    }).last()
+}
+
+///Word ends between characters: one of them is separator or camel case begins new word, like `LeftArm` or `ArmLeft`
+fn is_word_boundary(left:char, right:char) -> bool {
+    !left.is_alphanumeric() || !right.is_alphanumeric() || ((left.is_lowercase() || left.is_numeric()) && right.is_uppercase())
+}
+
+impl MirrorMap{
+    pub fn get(&self, id:&str) -> Option<&String> {
+        self.pairs.get(id)
+    }
+}
+
+impl Skeleton{
+    ///Pairs bones, whose names(or ids) differ by tokens of pattern
+    pub fn mirror_map(&self, pattern:&MirrorPattern) -> MirrorMap {
+        let mut pairs=HashMap::new();
+
+        for bone in self.bones_array.iter() {
+            let by_name=pattern.mirror_name(&bone.name).and_then(|name| self.get_bone_by_name(&name));
+            let by_id=pattern.mirror_name(&bone.id).and_then(|id| self.get_bone_by_id(&id));
+
+            match by_name.or(by_id) {
+                Some( other ) if other.id!=bone.id => { pairs.insert(bone.id.clone(), other.id.clone()); },
+                _ => {},
+            }
+        }
+
+        MirrorMap{
+            pairs:pairs,
+        }
+    }
+}
+
+fn axis_index(axis:Axis) -> usize {
+    match axis {
+        Axis::X => 0,
+        Axis::Y => 1,
+        Axis::Z => 2,
+    }
+}
+
+///Axis of `rotateX`, `translate.Y` and similar targets
+fn get_target_axis(name:&str) -> Option<usize> {
+    match name.chars().last() {
+        Some( 'X' ) => Some(0),
+        Some( 'Y' ) => Some(1),
+        Some( 'Z' ) => Some(2),
+        _ => None,
+    }
+}
+
+///Reflection of value across plane, that is perpendicular to axis
+fn reflect(value:&ChannelValue, address:&TargetAddress, axis:usize) -> ChannelValue {
+    let sid=address.sids.last().map_or("", |sid| sid.as_str());
+
+    match *value {
+        ChannelValue::Location( ref location ) => {
+            let mut position=[location.position.x, location.position.y, location.position.z];
+            position[axis]=-position[axis];
+
+            //rotation around normal of plane stays, rotations around other axes turn back
+            let q=&location.rotation;
+            let mut rotation=[-q.x, -q.y, -q.z];
+            rotation[axis]=-rotation[axis];
+
+            ChannelValue::Location( Location::new(
+                Position::new(position[0], position[1], position[2]),
+                location.scale.clone(),
+                Quaternion::new(rotation[0], rotation[1], rotation[2], q.w)
+            ))
+        },
+        ChannelValue::Vec3( vector ) if sid=="translate" || sid=="location" => {
+            let mut vector=vector;
+            vector[axis]=-vector[axis];
+
+            ChannelValue::Vec3( vector )
+        },
+        ChannelValue::Float( float ) if sid.starts_with("rotate") || sid.starts_with("rotation") => {
+            match get_target_axis(sid) {
+                Some( target_axis ) if target_axis!=axis => ChannelValue::Float( -float ),
+                _ => ChannelValue::Float( float ),
+            }
+        },
+        ChannelValue::Float( float ) if sid=="translate" || sid=="location" => {
+            let component=match address.selector {
+                Selector::Member( ref member ) => get_target_axis(member),
+                Selector::Index( index ) => Some(index),
+                _ => None,
+            };
+
+            if component==Some(axis) {
+                ChannelValue::Float( -float )
+            }else{
+                ChannelValue::Float( float )
+            }
+        },
+        _ => value.clone(),
+    }
+}
+
+impl Channel{
+    ///Reflects values across plane, that is perpendicular to axis, and retargets channel to paired bone
+    pub fn mirror(&self, map:&MirrorMap, axis:Axis) -> Result<Channel,Error> {
+        let address=TargetAddress::parse(&self.target)?;
+        let axis=axis_index(axis);
+
+        let target=match map.get(&address.id) {
+            Some( other ) => format!("{}{}", other, &self.target[address.id.len()..]),
+            None => self.target.clone(),
+        };
+
+        let mut values=self.values.empty_like();
+
+        for key in 0..self.times.len() {
+            values.push( reflect(&self.values.get(key), &address, axis) )?;
+        }
+
+        let reflect_tangent=|tangent:&Tangent| Tangent{
+            time:tangent.time,
+            value:reflect(&tangent.value, &address, axis),
+        };
+
+        let channel=Channel{
+            target:target,
+            times:self.times.clone(),
+            values:values,
+            interpolation:self.interpolation.clone(),
+            in_tangents:self.in_tangents.iter().map(&reflect_tangent).collect(),
+            out_tangents:self.out_tangents.iter().map(&reflect_tangent).collect(),
+        };
+
+        Ok( channel )
+    }
+}
+
+impl Clip{
+    ///Swaps channels of paired bones and reflects them across plane, that is perpendicular to axis.
+    ///Bones of pairs must have mirrored rest orientations.
+    pub fn mirror(&self, map:&MirrorMap, axis:Axis) -> Result<Clip,Error> {
+        let mut channels=Vec::with_capacity(self.channels.len());
+
+        for channel in self.channels.iter() {
+            channels.push( channel.mirror(map, axis)? );
+        }
+
+        Ok( Clip{ name:self.name.clone(), duration:self.duration, channels:channels } )
+    }
+}
@@ -0,0 +1,151 @@
+use Error;
+use XMLElement;
+use xmltree::Element;
+
+use std::sync::Arc;
+use std::collections::HashMap;
+
+use std::fmt::Display;
+use std::fmt;
+
+use Asset;
+use Geometry;
+use Source;
+use SourceLayer;
+use TreePrinter;
+
+use source::read_sources;
+
+#[derive(Copy,Clone,Eq,PartialEq,Debug)]
+pub enum MorphMethod{
+    ///result is (1-sum(weights))*base+sum(weight*target)
+    Normalized,
+    ///result is base+sum(weight*target), targets store differences
+    Relative,
+}
+
+pub struct Morph{
+    pub id:String,
+    pub name:String,
+    pub geometry_id:String,
+    pub method:MorphMethod,
+    pub targets:Vec<String>,
+    pub weights:Vec<f32>,
+}
+
+impl Display for Morph{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Morph id:\"{}\" for geometry with id \"{}\"", self.id, self.geometry_id)
+    }
+}
+
+impl Morph {
+    pub fn parse(morph_element:&Element, id:String, name:String, asset:&Asset) -> Result<Self, Error> {
+        let geometry_id=morph_element.get_attribute("source")?.trim_left_matches('#').to_string();
+
+        let method=match morph_element.attributes.get("method") {
+            Some( method ) => {
+                match method.as_str() {
+                    "NORMALIZED" => MorphMethod::Normalized,
+                    "RELATIVE" => MorphMethod::Relative,
+                    _ => return Err(Error::Other( format!("Expected NORMALIZED or RELATIVE morph method, but {} has been found", method) )),
+                }
+            },
+            None => MorphMethod::Normalized,
+        };
+
+        let all_sources=read_sources(morph_element, asset)?;
+        let targets_element=morph_element.get_element("targets")?;
+
+        let mut targets=None;
+        let mut weights=None;
+
+        for input_element in targets_element.children.iter(){
+            if input_element.name.as_str()=="input" {
+                let source_semantic=input_element.get_attribute("semantic")?;
+                let source_id=input_element.get_attribute("source")?.trim_left_matches('#');
+
+                let source=match all_sources.get(source_id){
+                    Some(s) => s.clone(),
+                    None => return Err(Error::Other( format!("Source with id \"{}\" does not exists", source_id) )),
+                };
+
+                match source_semantic.as_str() {
+                    "MORPH_TARGET" => targets=Some( Self::read_targets(&source)? ),
+                    "MORPH_WEIGHT" => weights=Some( Self::read_weights(&source)? ),
+                    _ => {},
+                }
+            }
+        }
+
+        let targets=match targets {
+            Some( targets ) => targets,
+            None => return Err(Error::Other( format!("Morph \"{}\" has no MORPH_TARGET input", id) )),
+        };
+
+        let weights=match weights {
+            Some( weights ) => weights,
+            None => return Err(Error::Other( format!("Morph \"{}\" has no MORPH_WEIGHT input", id) )),
+        };
+
+        if targets.len()!=weights.len() {
+            return Err(Error::Other( format!("Morph \"{}\" has {} targets but {} weights", id, targets.len(), weights.len()) ));
+        }
+
+        let morph=Morph{
+            id:id,
+            name:name,
+            geometry_id:geometry_id,
+            method:method,
+            targets:targets,
+            weights:weights,
+        };
+
+        Ok( morph )
+    }
+
+    fn read_targets(source:&Source) -> Result<Vec<String>,Error> {
+        for (_,layer) in source.layers.iter() {
+            match *layer {
+                SourceLayer::Name( ref names ) =>
+                    return Ok( names.iter().map(|name| name.trim_left_matches('#').to_string()).collect() ),
+                _ => {},
+            }
+        }
+
+        Err(Error::Other( format!("MORPH_TARGET source \"{}\" must have IDREF layer", source.id) ))
+    }
+
+    fn read_weights(source:&Source) -> Result<Vec<f32>,Error> {
+        for (_,layer) in source.layers.iter() {
+            match *layer {
+                SourceLayer::F32( ref weights ) =>
+                    return Ok( weights.clone() ),
+                _ => {},
+            }
+        }
+
+        Err(Error::Other( format!("MORPH_WEIGHT source \"{}\" must have float layer", source.id) ))
+    }
+
+    pub fn print(&self, printer:TreePrinter) {
+        println!("Morph id:\"{}\" for geometry with id \"{}\"", self.id, self.geometry_id);
+
+        for (last,(target,weight)) in self.targets.iter().zip(self.weights.iter()).enumerate().map(|i| (i.0==self.targets.len()-1,i.1) ){
+            printer.new_branch(last);
+            println!("Target geometry id:\"{}\" weight:{}", target, weight);
+        }
+    }
+}
+
+pub fn check_morph_targets(morphs:&HashMap<String,Arc<Morph>>, geometries:&HashMap<String,Arc<Geometry>>) -> Result<(),Error> {
+    for (_,morph) in morphs.iter() {
+        for geometry_id in Some(&morph.geometry_id).into_iter().chain(morph.targets.iter()) {
+            if !geometries.contains_key(geometry_id) {
+                return Err(Error::Other( format!("Geometry with id \"{}\" of morph \"{}\" does not exists", geometry_id, morph.id) ));
+            }
+        }
+    }
+
+    Ok(())
+}
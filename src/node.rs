@@ -8,18 +8,19 @@ use std::sync::Arc;
 
 use Geometry;
 use Camera;
+use Light;
 use Document;
 use Asset;
-use Axis;
-use Editor;
 use Bone;
 use Skeleton;
+use skeleton::find_bone_skeleton;
 use Skin;
 use Controller;
 use TreePrinter;
 
 use Location;
 use Matrix;
+use TransformElement;
 use Position;
 use Quaternion;
 use Scale;
@@ -28,10 +29,21 @@ pub struct Node<T>{
     pub id:String,
     pub name:String,
     pub location:Location,
+    ///transformation of node converted to Y-up meters, unlike `location` it keeps shear and exact scale
+    pub matrix:Matrix,
+    ///transform elements of node, channels refer to them by sid
+    pub transforms:Vec<TransformElement>,
     pub joined:Arc<T>,
     pub controller:Controller,
 }
 
+///Skinned geometry node, that waits for its skeleton
+pub struct SkinBinding{
+    pub node_name:String,
+    pub skin:Arc<Skin>,
+    pub skeleton_roots:Vec<String>,
+}
+
 impl Node<Geometry>{
     pub fn print(&self, printer:TreePrinter) {
         println!("Node id:\"{}\" name:\"{}\" joided to \"{}\"",self.id,self.name,self.joined.id);
@@ -50,42 +62,64 @@ impl Node<Camera>{
     }
 }
 
-impl Node<Skeleton>{
+impl Node<Light>{
     pub fn print(&self, printer:TreePrinter) {
         println!("Node id:\"{}\" name:\"{}\" joided to \"{}\"",self.id,self.name,self.joined.id);
+
+        printer.new_branch(true);
+        println!("{}", self.controller);
     }
 }
 
-pub fn parse_node(
-    node:&Element,
-    document:&mut Document,
-    skins_by_id:&HashMap<String,Arc<Skin>>,
-    bone:Option<Arc<Bone>>,
-    geometries:&mut HashMap<String,Node<Geometry>>,
-    cameras:&mut HashMap<String,Node<Camera>>,
-    skeletons:&mut HashMap<String,Node<Skeleton>>
-) -> Result<(),Error>{
-    let id=node.get_attribute("id")?.clone();
-    let name=node.get_attribute("name")?.clone();
+impl Node<Skeleton>{
+    pub fn print(&self, printer:TreePrinter) {
+        println!("Node id:\"{}\" name:\"{}\" joided to \"{}\"",self.id,self.name,self.joined.id);
+    }
+}
 
-    let location = match node.get_element("matrix") {
-        Ok( matrix_element ) => Matrix::parse(matrix_element.get_text()?)?.to_location(&document.asset),
+///Location and matrix of node converted to Y-up meters: by `<matrix>` or by `<translate>`, `<rotate>` and `<scale>`
+pub fn parse_location(node:&Element, asset:&Asset) -> Result<(Location,Matrix),Error> {
+    match node.get_element("matrix") {
+        Ok( matrix_element ) => {
+            let matrix=Matrix::parse(matrix_element.get_text()?)?;
+            Ok( (matrix.to_location(asset), matrix.with_asset(asset)) )
+        },
         _ => {
             let position=match node.get_element("translate"){
-                Ok ( position ) => Position::parse(position.get_text()?, &document.asset)?,
+                Ok ( position ) => Position::parse(position.get_text()?, asset)?,
                 Err ( _ ) => Position::new(0.0, 0.0, 0.0),
             };
 
             let scale=match node.get_element("scale"){
-                Ok ( scale ) => Scale::parse(scale.get_text()?, &document.asset)?,
-                Err ( _ ) => Scale::new(0.0, 0.0, 0.0),
+                Ok ( scale ) => Scale::parse(scale.get_text()?, asset)?,
+                Err ( _ ) => Scale::new(1.0, 1.0, 1.0),
             };
 
-            let rotation=Quaternion::parse_angles(node, &document.asset)?;
+            let rotation=Quaternion::parse_angles(node, asset)?;
+
+            let location=Location::new(position, scale, rotation);
+            let matrix=location.to_matrix();
 
-            Location::new(position, scale, rotation)
+            Ok( (location,matrix) )
         },
-    };
+    }
+}
+
+pub fn parse_node(
+    node:&Element,
+    document:&mut Document,
+    bone:Option<Arc<Bone>>,
+    geometries:&mut HashMap<String,Node<Geometry>>,
+    cameras:&mut HashMap<String,Node<Camera>>,
+    lights:&mut HashMap<String,Node<Light>>,
+    skeletons:&mut HashMap<String,Node<Skeleton>>,
+    skin_bindings:&mut Vec<SkinBinding>,
+) -> Result<(),Error>{
+    let id=node.get_attribute("id")?.clone();
+    let name=node.get_attribute("name")?.clone();
+    let transforms=TransformElement::parse_all(node, &document.asset)?;
+
+    let (location,matrix)=parse_location(node, &document.asset)?;
 
     for instance in node.children.iter(){
         if instance.name.as_str()=="instance_geometry" {
@@ -103,6 +137,8 @@ pub fn parse_node(
                         id:id,
                         name:name,
                         location:location,
+                        matrix:matrix,
+                        transforms:transforms,
                         joined:joined,
                         controller:match bone {
                             Some( bone ) => Controller::Bone( bone ),
@@ -128,6 +164,8 @@ pub fn parse_node(
                         id:id,
                         name:name,
                         location:location,
+                        matrix:matrix,
+                        transforms:transforms,
                         joined:joined,
                         controller:match bone {
                             Some( bone ) => Controller::Bone( bone ),
@@ -138,15 +176,71 @@ pub fn parse_node(
             }
 
             return Ok(());
-        }else if instance.name.as_str()=="instance_light" {//TODO:add light and light node
+        }else if instance.name.as_str()=="instance_light" {
+            let light_id=instance.get_attribute("url")?.trim_left_matches('#');
+
+            //light may have been skipped, because its type is not supported
+            let joined=match document.lights.get(light_id){
+                Some( light ) => light.clone(),
+                None => {
+                    document.warnings.push( format!("Node \"{}\" is skipped, because light \"{}\" does not exists", name, light_id) );
+                    return Ok(());
+                },
+            };
+
+            match lights.entry(name.clone()){
+                Entry::Occupied(_) => return Err(Error::Other( format!("Duplicate light node with name \"{}\"",&name) )),
+                Entry::Vacant(entry) => {entry.insert(
+                    Node::<Light>{
+                        id:id,
+                        name:name,
+                        location:location,
+                        matrix:matrix,
+                        transforms:transforms,
+                        joined:joined,
+                        controller:match bone {
+                            Some( bone ) => Controller::Bone( bone ),
+                            None => Controller::Model,
+                        }
+                    }
+                );},
+            }
+
             return Ok(());
         }else if instance.name.as_str()=="instance_controller" {
-            let skin_id=instance.get_attribute("url")?.trim_left_matches('#');
+            let controller_id=instance.get_attribute("url")?.trim_left_matches('#');
 
-            let skin=match skins_by_id.get(skin_id) {
+            let skin=match document.skins.get(controller_id) {
                 Some( skin ) =>
                     skin.clone(),
-                None => return Err(Error::Other( format!("Skin with id \"{}\" does not exists",skin_id) )),
+                None => {
+                    let morph=match document.morphs.get(controller_id) {
+                        Some( morph ) => morph.clone(),
+                        None => return Err(Error::Other( format!("Skin or morph with id \"{}\" does not exists",controller_id) )),
+                    };
+
+                    let joined=match document.geometries.get(&morph.geometry_id){
+                        Some( geometry ) => geometry.clone(),
+                        None => return Err(Error::Other( format!("Geometry with id \"{}\" does not exists",&morph.geometry_id) )),
+                    };
+
+                    match geometries.entry(name.clone()){
+                        Entry::Occupied(_) => return Err(Error::Other( format!("Duplicate geometry node with name \"{}\"",&name) )),
+                        Entry::Vacant(entry) => {entry.insert(
+                            Node::<Geometry>{
+                                id:id,
+                                name:name,
+                                location:location,
+                                matrix:matrix,
+                                transforms:transforms,
+                                joined:joined,
+                                controller:Controller::Morph(morph),
+                            }
+                        );},
+                    }
+
+                    return Ok(());
+                },
             };
 
             let joined=match document.geometries.get(&skin.geometry_id){
@@ -154,26 +248,45 @@ pub fn parse_node(
                 None => return Err(Error::Other( format!("Geometry with id \"{}\" does not exists",&skin.geometry_id) )),
             };
 
+            let mut skeleton_roots=Vec::new();
+
+            for skeleton_element in instance.children.iter(){
+                if skeleton_element.name.as_str()=="skeleton" {
+                    skeleton_roots.push( skeleton_element.get_text()?.trim().trim_left_matches('#').to_string() );
+                }
+            }
+
+            //skeleton may be declared after this node, so controller is set when all nodes of scene have been read
             match geometries.entry(name.clone()){
                 Entry::Occupied(_) => return Err(Error::Other( format!("Duplicate geometry node with name \"{}\"",&name) )),
                 Entry::Vacant(entry) => {entry.insert(
                     Node::<Geometry>{
                         id:id,
-                        name:name,
+                        name:name.clone(),
                         location:location,
+                        matrix:matrix,
+                        transforms:transforms,
                         joined:joined,
-                        controller:Controller::Skin(skin),
+                        controller:Controller::Model,
                     }
                 );},
             }
 
+            skin_bindings.push(
+                SkinBinding{
+                    node_name:name,
+                    skin:skin,
+                    skeleton_roots:skeleton_roots,
+                }
+            );
+
             return Ok(());
         }
     }
 
     for root_bone in node.children.iter(){
         if root_bone.name.as_str()=="node" && root_bone.get_attribute("type")?.as_str()=="JOINT" { //This is skeleton
-            let skeleton=Arc::new( Skeleton::parse(node, document, skins_by_id, id.clone(), location.clone(), geometries, cameras, skeletons)? );
+            let skeleton=Arc::new( Skeleton::parse(node, document, id.clone(), location.clone(), geometries, cameras, lights, skeletons, skin_bindings)? );
 
             let controller=match bone {
                 Some( bone ) => return Err(Error::Other( format!("Skeleton with id \"{}\" can not be joined to bone (id:\"{}\")", id, bone.id) )),
@@ -195,6 +308,8 @@ pub fn parse_node(
                         id:id,
                         name:name,
                         location:location,
+                        matrix:matrix,
+                        transforms:transforms,
                         joined:skeleton,
                         controller:controller,
                     }
@@ -210,3 +325,54 @@ pub fn parse_node(
         child_element_name:String::from("instance"),
     })
 }
+
+///Sets skin controllers of nodes. Skeleton is found by root joints from `<skeleton>`,
+///if they are absent, the only skeleton, that contains all joints of skin, is used.
+pub fn bind_skins(
+    document:&Document,
+    geometries:&mut HashMap<String,Node<Geometry>>,
+    skin_bindings:Vec<SkinBinding>
+) -> Result<(),Error>{
+    for skin_binding in skin_bindings.into_iter(){
+        let mut found_skeleton:Option<Arc<Skeleton>>=None;
+
+        for root in skin_binding.skeleton_roots.iter(){
+            let skeleton=match find_bone_skeleton(&document.skeletons, root)? {
+                Some( skeleton ) => skeleton.clone(),
+                None => return Err(Error::Other( format!("Skeleton root joint \"{}\" of node \"{}\" does not exists", root, &skin_binding.node_name) )),
+            };
+
+            match found_skeleton {
+                Some( ref found_skeleton ) => {
+                    if found_skeleton.id!=skeleton.id {
+                        return Err(Error::Other( format!("Skeleton root joints of node \"{}\" belong to different skeletons \"{}\" and \"{}\"", &skin_binding.node_name, found_skeleton.id, skeleton.id) ));
+                    }
+                },
+                None => {},
+            }
+
+            found_skeleton=Some(skeleton);
+        }
+
+        let skeleton=match found_skeleton {
+            Some( skeleton ) => skeleton,
+            None => {
+                let mut skeleton_ids:Vec<&String>=document.skeletons.iter().filter(|&(_,skeleton)| skin_binding.skin.bind(skeleton).is_ok()).map(|(id,_)| id).collect();
+                skeleton_ids.sort();
+
+                match skeleton_ids.len() {
+                    0 => return Err(Error::Other( format!("Node \"{}\" has no <skeleton> and no skeleton contains all joints of skin \"{}\"", &skin_binding.node_name, skin_binding.skin.id) )),
+                    1 => document.skeletons[skeleton_ids[0]].clone(),
+                    _ => return Err(Error::Other( format!("Node \"{}\" has no <skeleton> and joints of skin \"{}\" are contained in several skeletons: {}", &skin_binding.node_name, skin_binding.skin.id, skeleton_ids.iter().map(|id| id.as_str()).collect::<Vec<&str>>().join(", ")) )),
+                }
+            },
+        };
+
+        match geometries.get_mut(&skin_binding.node_name) {
+            Some( node ) => node.controller=Controller::Skin(skin_binding.skin, skeleton),
+            None => return Err(Error::Other( format!("Node \"{}\" of skin \"{}\" does not exists", &skin_binding.node_name, skin_binding.skin.id) )),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,317 @@
+use Error;
+
+use Skeleton;
+use Joint;
+use Channel;
+use ChannelValue;
+use SampleMode;
+use TargetAddress;
+use Selector;
+use Location;
+use Position;
+use Scale;
+use Quaternion;
+use Matrix;
+use TransformElement;
+
+///Local locations of bones, indexed by `Bone.index`
+#[derive(Clone)]
+pub struct Pose{
+    pub locations:Vec<Location>,
+    ///`<rotate>` elements of bones with current angles, rotation is composed of them, when channel changes an angle
+    pub rotations:Vec<Vec<TransformElement>>,
+}
+
+impl Pose{
+    ///Pose, that bones have in skeleton(`<matrix>` of joint nodes)
+    pub fn rest(skeleton:&Skeleton) -> Pose{
+        Pose{
+            locations:skeleton.bones_array.iter().map(|bone| bone.location.clone()).collect(),
+            rotations:skeleton.bones_array.iter().map(|bone| rotate_elements(&bone.transforms)).collect(),
+        }
+    }
+
+    ///Sets part of location of bone, that channel with this address animates: `translate`/`location`, `scale`,
+    ///their members(`.X` or `(0)`), angles of `<rotate>` elements(`rotateX.ANGLE`), elements of matrix
+    ///or whole location. Angle replaces angle of `<rotate>` element with this sid, then rotation is composed of
+    ///`<rotate>` elements of bone in order of document. Axes of members are axes of converted(Y-up) document.
+    pub fn apply_channel(&mut self, bone_index:usize, address:&TargetAddress, value:ChannelValue) -> Result<(),Error> {
+        let location=match self.locations.get_mut(bone_index) {
+            Some( location ) => location,
+            None => return Err( Error::Other(format!("Pose has no bone with index {}", bone_index)) ),
+        };
+
+        let sid=match address.sids.last() {
+            Some( sid ) => sid.as_str(),
+            None => "",
+        };
+
+        let applied=match (sid, value) {
+            (_, ChannelValue::Location( new_location )) if address.selector==Selector::All => {
+                *location=new_location;
+                true
+            },
+            ("translate", value) | ("location", value) => {
+                let position=&location.position;
+
+                match set_vec3([position.x, position.y, position.z], &address.selector, value) {
+                    Some( v ) => { location.position=Position::new(v[0], v[1], v[2]); true },
+                    None => false,
+                }
+            },
+            ("scale", value) => {
+                let scale=&location.scale;
+
+                match set_vec3([scale.x, scale.y, scale.z], &address.selector, value) {
+                    Some( v ) => { location.scale=Scale::new(v[0], v[1], v[2]); true },
+                    None => false,
+                }
+            },
+            (_, ChannelValue::Float( value )) => {
+                let rotate_index=match self.rotations.get(bone_index) {
+                    Some( rotations ) if is_angle(&address.selector) =>
+                        rotations.iter().position(|rotate| rotate.sid.as_ref().map(|rotate_sid| rotate_sid.as_str())==Some(sid)),
+                    _ => None,
+                };
+
+                match (rotate_index, &address.selector) {
+                    (Some( rotate_index ), _) => {
+                        let rotations=&mut self.rotations[bone_index];
+                        rotations[rotate_index].values[3]=value;
+                        location.rotation=compose_rotations(rotations);
+                        true
+                    },
+                    (None, &Selector::Index2(row, col)) if row<4 && col<4 => {
+                        let mut matrix=location.to_matrix();
+                        matrix.mat[row*4+col]=value;
+                        *location=matrix.decompose();
+                        true
+                    },
+                    _ => false,
+                }
+            },
+            _ => false,
+        };
+
+        if !applied {
+            return Err( Error::Other(format!("Channel with target \"{}/{}\" can not be applied to bone", address.id, address.sids.join("/"))) );
+        }
+
+        Ok(())
+    }
+
+    ///Applies value of channel at time(see `apply_channel`), if address targets bone of skeleton
+    pub fn apply_sampled(&mut self, skeleton:&Skeleton, address:&TargetAddress, channel:&Channel, time:f32, mode:SampleMode) -> Result<(),Error> {
+        match skeleton.bones.get(&address.id) {
+            Some( bone ) => self.apply_channel(bone.index, address, channel.sample(time, mode)),
+            None => Ok(()),
+        }
+    }
+
+    ///Pose, that changes nothing, if it is added as additive layer
+    pub fn identity(bones_count:usize) -> Pose{
+        Pose{
+            locations:vec![Location::identity(); bones_count],
+            rotations:vec![Vec::new(); bones_count],
+        }
+    }
+
+    fn check_bones_count(&self, other:&Pose) -> Result<(),Error>{
+        if self.locations.len()!=other.locations.len() {
+            return Err(Error::Other( format!("Poses have different count of bones: {} and {}", self.locations.len(), other.locations.len()) ));
+        }
+
+        Ok(())
+    }
+
+    ///Lerps positions and scales, slerps rotations, w=0 gives a, w=1 gives b
+    pub fn blend(a:&Pose, b:&Pose, w:f32) -> Result<Pose,Error>{
+        a.check_bones_count(b)?;
+
+        let pose=Pose{
+            locations:a.locations.iter().zip(b.locations.iter()).map(|(a,b)| a.interpolate(b, w)).collect(),
+            rotations:a.rotations.iter().zip(b.rotations.iter()).map(|(a,b)| blend_rotate_elements(a, b, w)).collect(),
+        };
+
+        Ok(pose)
+    }
+
+    ///Additive layer, that turns reference pose into pose.
+    ///Use `Pose::blend(&Pose::identity(n), &delta, w)` to weight it.
+    pub fn difference(pose:&Pose, reference:&Pose) -> Result<Pose,Error>{
+        pose.check_bones_count(reference)?;
+
+        let ratio=|value:f32, reference:f32| if reference.abs()>0.00001 { value/reference } else { 1.0 };
+
+        let pose=Pose{
+            locations:pose.locations.iter().zip(reference.locations.iter()).map(|(location,reference)|
+                Location::new(
+                    Position::new(location.position.x-reference.position.x, location.position.y-reference.position.y, location.position.z-reference.position.z),
+                    Scale::new(ratio(location.scale.x, reference.scale.x), ratio(location.scale.y, reference.scale.y), ratio(location.scale.z, reference.scale.z)),
+                    reference.rotation.conjugate()*location.rotation.clone()
+                )
+            ).collect(),
+            rotations:vec![Vec::new(); pose.locations.len()],
+        };
+
+        Ok(pose)
+    }
+
+    ///Applies additive layer(see `Pose::difference`) to base pose
+    pub fn add(base:&Pose, delta:&Pose) -> Result<Pose,Error>{
+        base.check_bones_count(delta)?;
+
+        let pose=Pose{
+            locations:base.locations.iter().zip(delta.locations.iter()).map(|(base,delta)|
+                Location::new(
+                    Position::new(base.position.x+delta.position.x, base.position.y+delta.position.y, base.position.z+delta.position.z),
+                    Scale::new(base.scale.x*delta.scale.x, base.scale.y*delta.scale.y, base.scale.z*delta.scale.z),
+                    base.rotation.clone()*delta.rotation.clone()
+                )
+            ).collect(),
+            rotations:base.rotations.clone(),
+        };
+
+        Ok(pose)
+    }
+
+    ///World matrices of bones, location of skeleton node is included
+    pub fn world_matrices(&self, skeleton:&Skeleton) -> Result<Vec<Matrix>,Error>{
+        if self.locations.len()!=skeleton.bones_array.len() {
+            return Err(Error::Other( format!("Pose has {} bones, but skeleton \"{}\" has {}", self.locations.len(), skeleton.id, skeleton.bones_array.len()) ));
+        }
+
+        let skeleton_matrix=skeleton.location.to_matrix();
+        let mut world_matrices:Vec<Matrix>=Vec::with_capacity(self.locations.len());
+
+        //parent always has smaller index than its children
+        for (bone,location) in skeleton.bones_array.iter().zip(self.locations.iter()) {
+            let parent_matrix=match bone.parent {
+                Some( parent_index ) => &world_matrices[parent_index],
+                None => &skeleton_matrix,
+            };
+
+            let world_matrix=parent_matrix.multiply(&location.to_matrix());
+            world_matrices.push(world_matrix);
+        }
+
+        Ok(world_matrices)
+    }
+
+    ///Skinning matrices: world matrix of bone of each joint multiplied by its inverse bind matrix,
+    ///joints without bone get identity matrix
+    pub fn matrix_palette(&self, skeleton:&Skeleton, joints:&[Joint]) -> Result<Vec<Matrix>,Error>{
+        let world_matrices=self.world_matrices(skeleton)?;
+
+        let palette=joints.iter().map(|joint|
+            match joint.bone_index {
+                Some( bone_index ) => world_matrices[bone_index].multiply(&joint.inverse_bind_matrix),
+                None => Matrix::identity(),
+            }
+        ).collect();
+
+        Ok(palette)
+    }
+}
+
+///Replaces whole vector or its member(`.X`, `(0)`)
+fn set_vec3(mut current:[f32;3], selector:&Selector, value:ChannelValue) -> Option<[f32;3]> {
+    match (selector, value) {
+        (&Selector::All, ChannelValue::Vec3( v )) => Some( v ),
+        (selector, ChannelValue::Float( v )) => {
+            let index=vec3_member(selector)?;
+
+            current[index]=v;
+            Some( current )
+        },
+        _ => None,
+    }
+}
+
+///Index of member of vector, `.X` or `(0)`
+pub fn vec3_member(selector:&Selector) -> Option<usize> {
+    match *selector {
+        Selector::Member( ref member ) => match member.as_str() {
+            "X" => Some(0),
+            "Y" => Some(1),
+            "Z" => Some(2),
+            _ => None,
+        },
+        Selector::Index( index ) if index<3 => Some(index),
+        _ => None,
+    }
+}
+
+///Axis of angle, that address targets, like `rotateX.ANGLE` or `rotationY(3)`
+pub fn angle_axis(address:&TargetAddress) -> Option<usize> {
+    match address.sids.last() {
+        Some( sid ) if is_angle(&address.selector) => rotation_axis(sid),
+        _ => None,
+    }
+}
+
+///Angle of `<rotate>`, `.ANGLE` or `(3)`
+fn is_angle(selector:&Selector) -> bool {
+    match *selector {
+        Selector::Member( ref member ) => member=="ANGLE",
+        Selector::Index( index ) => index==3,
+        _ => false,
+    }
+}
+
+///`<rotate>` elements of node in order of document
+pub fn rotate_elements(transforms:&[TransformElement]) -> Vec<TransformElement> {
+    transforms.iter().filter(|transform| transform.name=="rotate").cloned().collect()
+}
+
+///Rotation of `<rotate>` elements, they are composed in order of document
+fn compose_rotations(rotations:&[TransformElement]) -> Quaternion {
+    let mut rotation=Quaternion::identity();
+
+    for rotate in rotations.iter() {
+        let v=&rotate.values;
+        let length=(v[0]*v[0] + v[1]*v[1] + v[2]*v[2]).sqrt();
+
+        if length==0.0 {
+            continue;
+        }
+
+        let half=v[3].to_radians()/2.0;
+        let s=half.sin()/length;
+
+        rotation=rotation*Quaternion::new(v[0]*s, v[1]*s, v[2]*s, half.cos());
+    }
+
+    rotation
+}
+
+///Lerps angles of same `<rotate>` elements, otherwise elements of `a` are kept
+fn blend_rotate_elements(a:&[TransformElement], b:&[TransformElement], w:f32) -> Vec<TransformElement> {
+    let mut rotations=a.to_vec();
+
+    if a.len()==b.len() && a.iter().zip(b.iter()).all(|(a,b)| a.sid==b.sid) {
+        for (rotate,other) in rotations.iter_mut().zip(b.iter()) {
+            rotate.values[3]+=(other.values[3]-rotate.values[3])*w;
+        }
+    }
+
+    rotations
+}
+
+///Axis of `rotateX`, `rotationY` and so on
+fn rotation_axis(sid:&str) -> Option<usize> {
+    let axis=if sid.starts_with("rotation") {
+        &sid["rotation".len()..]
+    }else if sid.starts_with("rotate") {
+        &sid["rotate".len()..]
+    }else{
+        return None;
+    };
+
+    match axis {
+        "X" => Some(0),
+        "Y" => Some(1),
+        "Z" => Some(2),
+        _ => None,
+    }
+}
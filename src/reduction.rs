@@ -0,0 +1,274 @@
+use Error;
+
+use Channel;
+use ChannelValue;
+use ChannelValues;
+use Clip;
+use Interpolation;
+use Location;
+use SampleMode;
+
+///Difference between values of keys, it is used as tolerance of reduction too
+#[derive(Copy,Clone,PartialEq,Debug)]
+pub struct KeyError{
+    ///distance between positions of locations
+    pub position:f32,
+    ///angle between rotations of locations, in radians
+    pub rotation:f32,
+    ///difference of scales of locations
+    pub scale:f32,
+    ///difference of float and vec3 values
+    pub value:f32,
+}
+
+///Result of reduction of keys
+#[derive(Copy,Clone,PartialEq,Debug)]
+pub struct Reduction{
+    pub keys_before:usize,
+    pub keys_after:usize,
+    pub max_error:KeyError,
+}
+
+impl KeyError{
+    pub fn zero() -> KeyError {
+        KeyError::uniform(0.0)
+    }
+
+    pub fn uniform(error:f32) -> KeyError {
+        KeyError{
+            position:error,
+            rotation:error,
+            scale:error,
+            value:error,
+        }
+    }
+
+    pub fn between(a:&ChannelValue, b:&ChannelValue) -> KeyError {
+        let mut error=KeyError::zero();
+
+        match (a,b) {
+            (&ChannelValue::Float( a ), &ChannelValue::Float( b )) =>
+                error.value=(a-b).abs(),
+            (&ChannelValue::Vec3( a ), &ChannelValue::Vec3( b )) =>
+                error.value=a.iter().zip(b.iter()).map(|(a,b)| (a-b).abs()).fold(0.0, f32::max),
+            (&ChannelValue::Location( ref a ), &ChannelValue::Location( ref b )) =>
+                error=Self::between_locations(a, b),
+            _ => {},
+        }
+
+        error
+    }
+
+    fn between_locations(a:&Location, b:&Location) -> KeyError {
+        let dx=a.position.x-b.position.x;
+        let dy=a.position.y-b.position.y;
+        let dz=a.position.z-b.position.z;
+
+        KeyError{
+            position:(dx*dx + dy*dy + dz*dz).sqrt(),
+            rotation:2.0*a.rotation.dot(&b.rotation).abs().min(1.0).acos(),
+            scale:(a.scale.x-b.scale.x).abs().max( (a.scale.y-b.scale.y).abs() ).max( (a.scale.z-b.scale.z).abs() ),
+            value:0.0,
+        }
+    }
+
+    pub fn max(&self, other:&KeyError) -> KeyError {
+        KeyError{
+            position:self.position.max(other.position),
+            rotation:self.rotation.max(other.rotation),
+            scale:self.scale.max(other.scale),
+            value:self.value.max(other.value),
+        }
+    }
+
+    pub fn is_within(&self, tolerance:&KeyError) -> bool {
+        self.position<=tolerance.position && self.rotation<=tolerance.rotation &&
+            self.scale<=tolerance.scale && self.value<=tolerance.value
+    }
+}
+
+impl Reduction{
+    ///How many times count of keys has been decreased
+    pub fn compression_ratio(&self) -> f32 {
+        if self.keys_after==0 {
+            1.0
+        }else{
+            self.keys_before as f32/self.keys_after as f32
+        }
+    }
+
+    fn add(&self, other:&Reduction) -> Reduction {
+        Reduction{
+            keys_before:self.keys_before+other.keys_before,
+            keys_after:self.keys_after+other.keys_after,
+            max_error:self.max_error.max(&other.max_error),
+        }
+    }
+}
+
+impl Channel{
+    ///Samples channel with fixed rate from first to last key, last key is kept.
+    ///Curves are baked into keys, so tangents are dropped.
+    pub fn resample(&self, fps:f32) -> Result<Channel,Error> {
+        if !(fps>0.0) {
+            return Err(Error::Other( format!("Channel \"{}\" can not be resampled with {} fps", self.target, fps) ));
+        }
+
+        let (start,end)=(self.start_time(), self.end_time());
+        let frames_count=((end-start)*fps).ceil() as usize;
+
+        let mut channel=Channel{
+            target:self.target.clone(),
+            times:Vec::with_capacity(frames_count+1),
+            values:self.values.empty_like(),
+            interpolation:Vec::with_capacity(frames_count+1),
+            in_tangents:Vec::new(),
+            out_tangents:Vec::new(),
+        };
+
+        for frame in 0..frames_count+1 {
+            let time=(start+frame as f32/fps).min(end);
+
+            //steps of channel must stay steps
+            let interpolation=match self.times.iter().rposition(|key_time| *key_time<=time) {
+                Some( key ) if self.interpolation[key]==Interpolation::Step => Interpolation::Step,
+                _ => Interpolation::Linear,
+            };
+
+            channel.times.push(time);
+            channel.values.push( self.sample(time, SampleMode::Clamp) )?;
+            channel.interpolation.push(interpolation);
+        }
+
+        Ok( channel )
+    }
+
+    ///Removes keys of LINEAR segments, that can be restored by interpolation of neighbour keys within tolerance.
+    ///First and last keys are kept, channel with less than 2 keys is not changed.
+    pub fn reduce(&self, tolerance:&KeyError) -> Result<(Channel,Reduction),Error> {
+        let keys_count=self.times.len();
+
+        if keys_count<2 {
+            let reduction=Reduction{
+                keys_before:keys_count,
+                keys_after:keys_count,
+                max_error:KeyError::zero(),
+            };
+
+            return Ok( (self.clone(),reduction) );
+        }
+
+        let mut kept=vec![0];
+        let mut max_error=KeyError::zero();
+
+        let mut anchor=0;
+
+        while anchor<keys_count-1 {
+            //farthest key, that may be next one
+            let mut next=anchor+1;
+            let mut next_error=KeyError::zero();
+
+            for candidate in anchor+2..keys_count {
+                if self.interpolation[candidate-1]!=Interpolation::Linear || self.interpolation[anchor]!=Interpolation::Linear {
+                    break;
+                }
+
+                let error=self.get_skip_error(anchor, candidate);
+
+                if !error.is_within(tolerance) {
+                    break;
+                }
+
+                next=candidate;
+                next_error=error;
+            }
+
+            max_error=max_error.max(&next_error);
+            kept.push(next);
+            anchor=next;
+        }
+
+        let has_tangents=self.in_tangents.len()>0 && self.out_tangents.len()>0;
+
+        let mut channel=Channel{
+            target:self.target.clone(),
+            times:Vec::with_capacity(kept.len()),
+            values:self.values.empty_like(),
+            interpolation:Vec::with_capacity(kept.len()),
+            in_tangents:Vec::new(),
+            out_tangents:Vec::new(),
+        };
+
+        for key in kept.iter() {
+            channel.times.push( self.times[*key] );
+            channel.values.push( self.values.get(*key) )?;
+            channel.interpolation.push( self.interpolation[*key] );
+
+            if has_tangents {
+                channel.in_tangents.push( self.in_tangents[*key].clone() );
+                channel.out_tangents.push( self.out_tangents[*key].clone() );
+            }
+        }
+
+        let reduction=Reduction{
+            keys_before:keys_count,
+            keys_after:kept.len(),
+            max_error:max_error,
+        };
+
+        Ok( (channel,reduction) )
+    }
+
+    ///Largest error of keys between first and last ones, if they are restored by line
+    fn get_skip_error(&self, first:usize, last:usize) -> KeyError {
+        let (t0,t1)=(self.times[first], self.times[last]);
+        let mut error=KeyError::zero();
+
+        for key in first+1..last {
+            let s=if t1>t0 { (self.times[key]-t0)/(t1-t0) } else { 0.0 };
+
+            let restored=match self.values {
+                ChannelValues::Float( ref list ) =>
+                    ChannelValue::Float( list[first]+(list[last]-list[first])*s ),
+                ChannelValues::Vec3( ref list ) => {
+                    let (a,b)=(list[first], list[last]);
+
+                    ChannelValue::Vec3( [a[0]+(b[0]-a[0])*s, a[1]+(b[1]-a[1])*s, a[2]+(b[2]-a[2])*s] )
+                },
+                ChannelValues::Location( ref list ) =>
+                    ChannelValue::Location( list[first].interpolate(&list[last], s) ),
+            };
+
+            error=error.max( &KeyError::between(&restored, &self.values.get(key)) );
+        }
+
+        error
+    }
+}
+
+impl Clip{
+    pub fn resample(&self, fps:f32) -> Result<Clip,Error> {
+        let mut channels=Vec::with_capacity(self.channels.len());
+
+        for channel in self.channels.iter() {
+            channels.push( channel.resample(fps)? );
+        }
+
+        Ok( Clip{ name:self.name.clone(), duration:self.duration, channels:channels } )
+    }
+
+    ///Reduces keys of all channels, reduction is summed up for clip
+    pub fn reduce(&self, tolerance:&KeyError) -> Result<(Clip,Reduction),Error> {
+        let mut channels=Vec::with_capacity(self.channels.len());
+        let mut total=Reduction{ keys_before:0, keys_after:0, max_error:KeyError::zero() };
+
+        for channel in self.channels.iter() {
+            let (channel,reduction)=channel.reduce(tolerance)?;
+
+            channels.push(channel);
+            total=total.add(&reduction);
+        }
+
+        Ok( (Clip{ name:self.name.clone(), duration:self.duration, channels:channels }, total) )
+    }
+}
@@ -0,0 +1,280 @@
+use Error;
+
+use std::collections::HashMap;
+
+use Bone;
+use Channel;
+use ChannelValue;
+use Clip;
+use Interpolation;
+use Location;
+use Pose;
+use Position;
+use Scale;
+use Selector;
+use Skeleton;
+use Tangent;
+use TargetAddress;
+
+use pose::{angle_axis,vec3_member};
+
+impl Skeleton{
+    ///Height of root bone above lowest bone in rest pose.
+    ///If skeleton has no bones below root, height of root above origin is used.
+    pub fn leg_length(&self) -> Result<f32,Error> {
+        let root_index=match self.roots().first() {
+            Some( root_index ) => *root_index,
+            None => return Err(Error::Other( format!("Skeleton \"{}\" has no bones", self.id) )),
+        };
+
+        let world_matrices=Pose::rest(self).world_matrices(self)?;
+        let root_height=world_matrices[root_index].mat[7];
+        let lowest=world_matrices.iter().map(|matrix| matrix.mat[7]).fold(root_height, f32::min);
+
+        if root_height-lowest>0.00001 {
+            Ok( root_height-lowest )
+        }else{
+            Ok( root_height.abs() )
+        }
+    }
+}
+
+///Part of location of bone, that channel animates
+#[derive(Copy,Clone)]
+enum Part{
+    ///matrix or whole location
+    Whole,
+    ///vector or its member
+    Translation(Option<usize>),
+    Scale(Option<usize>),
+    ///angle about axis
+    Angle(usize),
+}
+
+impl Part{
+    fn parse(address:&TargetAddress) -> Option<Part> {
+        let member=match address.selector {
+            Selector::All => None,
+            ref selector => vec3_member(selector),
+        };
+
+        match (address.sids.last().map(|sid| sid.as_str()), &address.selector) {
+            (Some("translate"), &Selector::All) | (Some("location"), &Selector::All) => Some( Part::Translation(None) ),
+            (Some("translate"), _) | (Some("location"), _) => member.map(|index| Part::Translation(Some(index))),
+            (Some("scale"), &Selector::All) => Some( Part::Scale(None) ),
+            (Some("scale"), _) => member.map(|index| Part::Scale(Some(index))),
+            (_, &Selector::All) => Some( Part::Whole ),
+            _ => angle_axis(address).map(Part::Angle),
+        }
+    }
+}
+
+///Bones of both skeletons and scale of translation
+struct BonePair<'a>{
+    from:&'a Bone,
+    to:&'a Bone,
+    translation_scale:f32,
+}
+
+impl<'a> BonePair<'a>{
+    ///Offset from rest position of source bone is applied to rest position of target bone
+    fn retarget_position(&self, position:[f32;3]) -> [f32;3] {
+        let from=&self.from.location.position;
+        let to=&self.to.location.position;
+
+        [
+            to.x+(position[0]-from.x)*self.translation_scale,
+            to.y+(position[1]-from.y)*self.translation_scale,
+            to.z+(position[2]-from.z)*self.translation_scale,
+        ]
+    }
+
+    ///Ratio of rest scales of bones, that scale of source bone is multiplied by
+    fn scale_ratio(&self) -> [f32;3] {
+        let ratio=|from:f32, to:f32| if from.abs()>0.00001 { to/from } else { 0.0 };
+        let (from,to)=(&self.from.location.scale, &self.to.location.scale);
+
+        [ratio(from.x, to.x), ratio(from.y, to.y), ratio(from.z, to.z)]
+    }
+
+    fn retarget_scale(&self, scale:[f32;3]) -> [f32;3] {
+        let ratio=self.scale_ratio();
+        let to=&self.to.location.scale;
+        let retarget=|value:f32, ratio:f32, to:f32| if ratio!=0.0 { value*ratio } else { to };
+
+        [retarget(scale[0], ratio[0], to.x), retarget(scale[1], ratio[1], to.y), retarget(scale[2], ratio[2], to.z)]
+    }
+
+    ///Angle about axis changes by difference of rest angles, other rest angles of bones must be same
+    fn angle_offset(&self, axis:usize) -> Option<f32> {
+        let from=self.from.location.rotation.euler_angles();
+        let to=self.to.location.rotation.euler_angles();
+
+        if (0..3).filter(|other| *other!=axis).all(|other| (from[other]-to[other]).abs()<0.001) {
+            Some( to[axis]-from[axis] )
+        }else{
+            None
+        }
+    }
+
+    fn retarget_value(&self, value:&ChannelValue, part:Part) -> Option<ChannelValue> {
+        let retargeted=match (value, part) {
+            (&ChannelValue::Location( ref location ), Part::Whole) => {
+                let position=self.retarget_position([location.position.x, location.position.y, location.position.z]);
+
+                //rotation relative to rest rotation of source bone is applied to rest rotation of target bone
+                let relative=self.from.location.rotation.conjugate()*location.rotation.clone();
+                let rotation=self.to.location.rotation.clone()*relative;
+
+                let scale=self.retarget_scale([location.scale.x, location.scale.y, location.scale.z]);
+
+                ChannelValue::Location( Location::new(
+                    Position::new(position[0], position[1], position[2]),
+                    Scale::new(scale[0], scale[1], scale[2]),
+                    rotation
+                ))
+            },
+            (&ChannelValue::Vec3( vector ), Part::Translation(None)) => ChannelValue::Vec3( self.retarget_position(vector) ),
+            (&ChannelValue::Float( value ), Part::Translation(Some( index ))) => {
+                let (from,to)=(&self.from.location.position, &self.to.location.position);
+                let (from,to)=([from.x, from.y, from.z][index], [to.x, to.y, to.z][index]);
+
+                ChannelValue::Float( to+(value-from)*self.translation_scale )
+            },
+            (&ChannelValue::Vec3( vector ), Part::Scale(None)) => ChannelValue::Vec3( self.retarget_scale(vector) ),
+            (&ChannelValue::Float( value ), Part::Scale(Some( index ))) => {
+                let from=&self.from.location.scale;
+                let mut scale=[from.x, from.y, from.z];
+                scale[index]=value;
+
+                ChannelValue::Float( self.retarget_scale(scale)[index] )
+            },
+            (&ChannelValue::Float( angle ), Part::Angle( axis )) => ChannelValue::Float( angle+self.angle_offset(axis)? ),
+            _ => return None,
+        };
+
+        Some( retargeted )
+    }
+
+    ///HERMITE tangents are derivatives, so they are only scaled like offsets from rest pose
+    fn retarget_derivative(&self, value:&ChannelValue, part:Part) -> Option<ChannelValue> {
+        let retargeted=match (value, part) {
+            (&ChannelValue::Vec3( v ), Part::Translation(None)) => {
+                let k=self.translation_scale;
+                ChannelValue::Vec3( [v[0]*k, v[1]*k, v[2]*k] )
+            },
+            (&ChannelValue::Float( v ), Part::Translation(Some( _ ))) => ChannelValue::Float( v*self.translation_scale ),
+            (&ChannelValue::Vec3( v ), Part::Scale(None)) => {
+                let ratio=self.scale_ratio();
+                ChannelValue::Vec3( [v[0]*ratio[0], v[1]*ratio[1], v[2]*ratio[2]] )
+            },
+            (&ChannelValue::Float( v ), Part::Scale(Some( index ))) => ChannelValue::Float( v*self.scale_ratio()[index] ),
+            (&ChannelValue::Float( v ), Part::Angle( axis )) => {
+                self.angle_offset(axis)?;
+                ChannelValue::Float( v )
+            },
+            _ => return None,
+        };
+
+        Some( retargeted )
+    }
+
+    ///BEZIER control points are values, HERMITE tangents are derivatives
+    fn retarget_tangent(&self, tangent:&Tangent, interpolation:Interpolation, part:Part) -> Option<Tangent> {
+        let value=match interpolation {
+            Interpolation::Hermite => self.retarget_derivative(&tangent.value, part)?,
+            _ => self.retarget_value(&tangent.value, part)?,
+        };
+
+        Some( Tangent{ time:tangent.time, value:value } )
+    }
+}
+
+///Moves channels of clip from bones of one skeleton to bones of other one.
+///`map` pairs names of bones of `from` with names of bones of `to`, not mapped bones are paired by same name.
+///Channels of bones without pair are dropped. Angles about axes(`rotateX.ANGLE`) are moved, if rest rotations
+///of bones differ only about that axis, other channels, that can not be moved, give error.
+///Root translation is scaled by ratio of leg lengths of skeletons.
+pub fn retarget(clip:&Clip, from:&Skeleton, to:&Skeleton, map:&HashMap<String,String>) -> Result<Clip,Error> {
+    let from_leg=from.leg_length()?;
+    let to_leg=to.leg_length()?;
+
+    let root_scale=if from_leg>0.00001 && to_leg>0.00001 {
+        to_leg/from_leg
+    }else{
+        1.0
+    };
+
+    let mut channels=Vec::with_capacity(clip.channels.len());
+
+    for channel in clip.channels.iter() {
+        let address=TargetAddress::parse(&channel.target)?;
+
+        let from_bone=match from.get_bone_by_id(&address.id) {
+            Some( bone ) => bone,
+            None => continue,
+        };
+
+        let to_name=match map.get(&from_bone.name) {
+            Some( name ) => name,
+            None => &from_bone.name,
+        };
+
+        let to_bone=match to.get_bone_by_name(to_name) {
+            Some( bone ) => bone,
+            None => continue,
+        };
+
+        let pair=BonePair{
+            from:from_bone,
+            to:to_bone,
+            translation_scale:if to_bone.parent.is_none() { root_scale } else { 1.0 },
+        };
+
+        //channels of other bones and unknown parts of bone can not be moved correctly
+        let error=|| Error::Other( format!("Channel \"{}\" can not be retargeted to bone \"{}\"", channel.target, to_bone.id) );
+
+        let part=match Part::parse(&address) {
+            Some( part ) => part,
+            None => return Err( error() ),
+        };
+
+        let mut values=channel.values.empty_like();
+
+        for key in 0..channel.times.len() {
+            match pair.retarget_value(&channel.values.get(key), part) {
+                Some( value ) => values.push(value)?,
+                None => return Err( error() ),
+            }
+        }
+
+        //out tangent of key belongs to segment after key, in tangent belongs to segment before key
+        let mut in_tangents=Vec::with_capacity(channel.in_tangents.len());
+        let mut out_tangents=Vec::with_capacity(channel.out_tangents.len());
+
+        for (key,tangent) in channel.in_tangents.iter().enumerate() {
+            match pair.retarget_tangent(tangent, channel.interpolation[key.max(1)-1], part) {
+                Some( tangent ) => in_tangents.push(tangent),
+                None => return Err( error() ),
+            }
+        }
+
+        for (key,tangent) in channel.out_tangents.iter().enumerate() {
+            match pair.retarget_tangent(tangent, channel.interpolation[key], part) {
+                Some( tangent ) => out_tangents.push(tangent),
+                None => return Err( error() ),
+            }
+        }
+
+        channels.push( Channel{
+            target:format!("{}{}", to_bone.id, &channel.target[address.id.len()..]),
+            times:channel.times.clone(),
+            values:values,
+            interpolation:channel.interpolation.clone(),
+            in_tangents:in_tangents,
+            out_tangents:out_tangents,
+        });
+    }
+
+    Ok( Clip{ name:clip.name.clone(), duration:clip.duration, channels:channels } )
+}
@@ -0,0 +1,180 @@
+use Error;
+
+use Channel;
+use ChannelValue;
+use ChannelValues;
+use Clip;
+use Location;
+use Position;
+use Quaternion;
+use Scale;
+use TargetAddress;
+
+///Axes of motion of root bone, that are moved to root motion. Y is up axis.
+#[derive(Copy,Clone,PartialEq,Debug)]
+pub struct RootMotionAxes{
+    pub x:bool,
+    pub y:bool,
+    pub z:bool,
+    ///rotation around up axis
+    pub yaw:bool,
+}
+
+///Motion of root bone, that has been extracted from clip
+#[derive(Clone)]
+pub struct RootMotion{
+    pub bone_id:String,
+    pub times:Vec<f32>,
+    ///motion from previous key to this key in space of parent of root bone, first delta is identity
+    pub deltas:Vec<Location>,
+}
+
+impl RootMotionAxes{
+    ///Walking on ground: horizontal motion and turns, height stays in clip
+    pub fn horizontal() -> RootMotionAxes {
+        RootMotionAxes{
+            x:true,
+            y:false,
+            z:true,
+            yaw:true,
+        }
+    }
+
+    fn mask(&self, position:[f32;3], base:[f32;3]) -> [f32;3] {
+        [
+            if self.x { base[0] } else { position[0] },
+            if self.y { base[1] } else { position[1] },
+            if self.z { base[2] } else { position[2] },
+        ]
+    }
+}
+
+impl RootMotion{
+    ///Motion from first key to last key
+    pub fn total(&self) -> Location {
+        let mut total=Location::identity();
+
+        for delta in self.deltas.iter() {
+            total.position=Position::new(
+                total.position.x+delta.position.x,
+                total.position.y+delta.position.y,
+                total.position.z+delta.position.z
+            );
+            total.rotation=delta.rotation.clone()*total.rotation;
+        }
+
+        total
+    }
+}
+
+///Rotation around Y axis, that is part of rotation
+fn get_yaw(rotation:&Quaternion) -> Quaternion {
+    let length=(rotation.y*rotation.y + rotation.w*rotation.w).sqrt();
+
+    if length<0.00001 {
+        Quaternion::identity()
+    }else{
+        Quaternion::new(0.0, rotation.y/length, 0.0, rotation.w/length)
+    }
+}
+
+impl Clip{
+    ///Moves motion of root bone by masked axes to root motion, channel of bone stays in place of its first key.
+    ///Bone must be animated by `transform` matrices or `translate` vectors(they have no yaw).
+    pub fn extract_root_motion(&mut self, bone_id:&str, axes:RootMotionAxes) -> Result<RootMotion,Error> {
+        for channel in self.channels.iter_mut() {
+            let address=TargetAddress::parse(&channel.target)?;
+
+            if address.id!=bone_id {
+                continue;
+            }
+
+            let is_translation=address.sids==["translate"] || address.sids==["location"];
+
+            match channel.values {
+                ChannelValues::Location( _ ) => return Ok( extract_from_locations(channel, bone_id, axes) ),
+                ChannelValues::Vec3( _ ) if is_translation => return Ok( extract_from_translations(channel, bone_id, axes) ),
+                _ => {},
+            }
+        }
+
+        Err(Error::Other( format!("Clip \"{}\" has no transform or translate channel of bone \"{}\"", self.name, bone_id) ))
+    }
+}
+
+fn extract_from_locations(channel:&mut Channel, bone_id:&str, axes:RootMotionAxes) -> RootMotion {
+    let locations=match channel.values {
+        ChannelValues::Location( ref mut locations ) => locations,
+        _ => unreachable!(),
+    };
+
+    let base_position=[locations[0].position.x, locations[0].position.y, locations[0].position.z];
+    let base_yaw=get_yaw(&locations[0].rotation);
+
+    let mut deltas=Vec::with_capacity(locations.len());
+    let mut previous=(base_position, base_yaw.clone());
+
+    for location in locations.iter_mut() {
+        let position=[location.position.x, location.position.y, location.position.z];
+        let yaw=get_yaw(&location.rotation);
+
+        let moved=axes.mask([0.0;3], [position[0]-previous.0[0], position[1]-previous.0[1], position[2]-previous.0[2]]);
+        let turn=if axes.yaw { yaw.clone()*previous.1.conjugate() } else { Quaternion::identity() };
+
+        deltas.push( Location::new(Position::new(moved[0], moved[1], moved[2]), Scale::new(1.0, 1.0, 1.0), turn) );
+
+        let in_place=axes.mask(position, base_position);
+        location.position=Position::new(in_place[0], in_place[1], in_place[2]);
+
+        if axes.yaw {
+            location.rotation=base_yaw.clone()*yaw.conjugate()*location.rotation.clone();
+        }
+
+        previous=(position, yaw);
+    }
+
+    RootMotion{
+        bone_id:String::from(bone_id),
+        times:channel.times.clone(),
+        deltas:deltas,
+    }
+}
+
+fn extract_from_translations(channel:&mut Channel, bone_id:&str, axes:RootMotionAxes) -> RootMotion {
+    let (base,deltas)={
+        let values=match channel.values {
+            ChannelValues::Vec3( ref mut values ) => values,
+            _ => unreachable!(),
+        };
+
+        let base=values[0];
+        let mut deltas=Vec::with_capacity(values.len());
+        let mut previous=base;
+
+        for value in values.iter_mut() {
+            let moved=axes.mask([0.0;3], [value[0]-previous[0], value[1]-previous[1], value[2]-previous[2]]);
+            deltas.push( Location::new(Position::new(moved[0], moved[1], moved[2]), Scale::new(1.0, 1.0, 1.0), Quaternion::identity()) );
+
+            previous=*value;
+            *value=axes.mask(*value, base);
+        }
+
+        (base, deltas)
+    };
+
+    //tangents of extracted axes become flat
+    for tangent in channel.in_tangents.iter_mut().chain(channel.out_tangents.iter_mut()) {
+        let flat=match tangent.value {
+            ChannelValue::Vec3( value ) => axes.mask(value, base),
+            _ => continue,
+        };
+
+        tangent.value=ChannelValue::Vec3( flat );
+    }
+
+    RootMotion{
+        bone_id:String::from(bone_id),
+        times:channel.times.clone(),
+        deltas:deltas,
+    }
+}
@@ -7,14 +7,15 @@ use std::collections::hash_map::Entry;
 use std::sync::Arc;
 
 use node::parse_node;
+use node::bind_skins;
 
 use Node;
 use Document;
 
 use Camera;
+use Light;
 use Geometry;
 use Skeleton;
-use Skin;
 use TreePrinter;
 
 pub struct Scene{
@@ -22,30 +23,36 @@ pub struct Scene{
     pub name:String,
     pub geometries:HashMap<String,Node<Geometry>>,
     pub cameras:HashMap<String,Node<Camera>>,
+    pub lights:HashMap<String,Node<Light>>,
     pub skeletons:HashMap<String,Node<Skeleton>>,
 }
 
 impl Scene{
-    pub fn parse(scene:&Element, document:&mut Document, skins_by_id:&HashMap<String,Arc<Skin>>) -> Result<Scene,Error>{
+    pub fn parse(scene:&Element, document:&mut Document) -> Result<Scene,Error>{
         let id=scene.get_attribute("id")?.clone();
         let name=scene.get_attribute("name")?.clone();
 
         let mut geometries=HashMap::new();
         let mut cameras=HashMap::new();
+        let mut lights=HashMap::new();
         let mut skeletons=HashMap::new();
+        let mut skin_bindings=Vec::new();
 
         for node_element in scene.children.iter(){
             if node_element.name.as_str()=="node" {
-                parse_node(node_element, document, skins_by_id, None, &mut geometries, &mut cameras, &mut skeletons)?;
+                parse_node(node_element, document, None, &mut geometries, &mut cameras, &mut lights, &mut skeletons, &mut skin_bindings)?;
             }
         }
 
+        bind_skins(document, &mut geometries, skin_bindings)?;
+
         Ok(
             Scene{
                 id:id,
                 name:name,
                 geometries:geometries,
                 cameras:cameras,
+                lights:lights,
                 skeletons:skeletons,
             }
         )
@@ -56,7 +63,8 @@ impl Scene{
 
         self.print_geometries( printer.new_branch(false) );
         self.print_skeletons( printer.new_branch(false) );
-        self.print_cameras( printer.new_branch(true) );
+        self.print_cameras( printer.new_branch(false) );
+        self.print_lights( printer.new_branch(true) );
     }
 
     fn print_geometries(&self, printer:TreePrinter) {
@@ -83,6 +91,14 @@ impl Scene{
         }
     }
 
+    fn print_lights(&self, printer:TreePrinter) {
+        println!("Lights");
+
+        for (last,(_,light)) in self.lights.iter().clone().enumerate().map(|i| (i.0==self.lights.len()-1,i.1) ){
+            light.print( printer.new_branch(last) );
+        }
+    }
+
     /*
     pub fn print_tree(&self, last_scene:bool){
         use print_branch;
@@ -143,12 +159,12 @@ impl Scene{
     */
 }
 
-pub fn parse_scenes(root:&Element, document:&mut Document, skins_by_id:HashMap<String,Arc<Skin>>) -> Result<(), Error>{
+pub fn parse_scenes(root:&Element, document:&mut Document) -> Result<(), Error>{
     let scenes_element=root.get_element("library_visual_scenes")?;
 
     for scene_element in scenes_element.children.iter(){
         if scene_element.name.as_str()=="visual_scene" {
-            let scene=Scene::parse(scene_element, document, &skins_by_id)?;
+            let scene=Scene::parse(scene_element, document)?;
 
             match document.scenes.entry(scene.id.clone()){
                 Entry::Occupied(_) => return Err(Error::Other( format!("Dublicate scene with id \"{}\"", &scene.id) )),
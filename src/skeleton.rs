@@ -3,30 +3,33 @@ use XMLElement;
 use xmltree::Element;
 
 use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::collections::hash_map::Entry;
 use std::sync::Arc;
 
 use node::parse_node;
+use node::parse_location;
+use node::SkinBinding;
 
 use std::fmt::Display;
 use std::fmt;
 
 use Geometry;
 use Camera;
+use Light;
 use Document;
-use Asset;
-use Axis;
-use Editor;
 use Node;
 use Skin;
+use Animation;
+use Pose;
+use SampleMode;
 use TreePrinter;
 
 use Location;
 use Matrix;
-use Position;
-use Quaternion;
-use Scale;
+use TransformElement;
 
+///Bones are stored in depth-first order, so parent always has smaller index than its children
 pub struct Skeleton{
     pub id:String,
     pub location:Location,
@@ -38,12 +41,13 @@ impl Skeleton {
     pub fn parse(
         skeleton_element:&Element,
         document:&mut Document,
-        skins_by_id:&HashMap<String,Arc<Skin>>,
         id:String,
         location:Location,
         geometries:&mut HashMap<String,Node<Geometry>>,
         cameras:&mut HashMap<String,Node<Camera>>,
+        lights:&mut HashMap<String,Node<Light>>,
         skeletons:&mut HashMap<String,Node<Skeleton>>,
+        skin_bindings:&mut Vec<SkinBinding>,
     ) -> Result<Skeleton,Error> {
         let mut bones_array=Vec::new();
         let mut bones=HashMap::new();
@@ -53,13 +57,11 @@ impl Skeleton {
                 let node_type=node_element.get_attribute("type")?;
 
                 if node_type.as_str()=="JOINT" {
-                    Bone::parse(node_element, document, skins_by_id, id.clone(), None, geometries, cameras, skeletons, &mut bones_array, &mut bones)?;
+                    Bone::parse(node_element, document, id.clone(), None, geometries, cameras, lights, skeletons, skin_bindings, &mut bones_array, &mut bones)?;
                 }
             }
         }
 
-        //Bone::parse(root_bone_element, document, skins_by_id, id.clone(), None, geometries, cameras, skeletons, &mut bones_array, &mut bones)?;
-
         let skeleton=Skeleton{
             id:id,
             location:location,
@@ -70,15 +72,191 @@ impl Skeleton {
         Ok( skeleton )
     }
 
+    ///Indices of bones without parent, skeleton may have several roots(IK helpers)
+    pub fn roots(&self) -> Vec<usize> {
+        self.bones_array.iter().filter(|bone| bone.parent.is_none()).map(|bone| bone.index).collect()
+    }
+
+    ///Indices of parent, grandparent and so on up to root, None if skeleton has no such bone
+    pub fn parent_chain(&self, bone_index:usize) -> Option<Vec<usize>> {
+        let mut chain=Vec::new();
+        let mut parent=match self.bones_array.get(bone_index) {
+            Some( bone ) => bone.parent,
+            None => return None,
+        };
+
+        while let Some( parent_index )=parent {
+            chain.push(parent_index);
+            parent=self.bones_array[parent_index].parent;
+        }
+
+        Some( chain )
+    }
+
+    pub fn depth_first(&self) -> DepthFirstIter<'_> {
+        let mut stack=self.roots();
+        stack.reverse();
+
+        DepthFirstIter{
+            bones_array:&self.bones_array,
+            stack:stack,
+        }
+    }
+
+    pub fn breadth_first(&self) -> BreadthFirstIter<'_> {
+        BreadthFirstIter{
+            bones_array:&self.bones_array,
+            queue:self.roots().into_iter().collect(),
+        }
+    }
+
+    pub fn get_bone_by_id(&self, id:&str) -> Option<&Arc<Bone>> {
+        self.bones.get(id)
+    }
+
+    pub fn get_bone_by_sid(&self, sid:&str) -> Option<&Arc<Bone>> {
+        self.bones_array.iter().find(|bone| bone.sid==sid)
+    }
+
+    pub fn get_bone_by_name(&self, name:&str) -> Option<&Arc<Bone>> {
+        self.bones_array.iter().find(|bone| bone.name==name)
+    }
+
+    ///Rest pose with channels of animations, that target bones of this skeleton(see `Pose::apply_channel`).
+    ///Times outside of keys are clamped.
+    pub fn pose_at(&self, animations:&[Arc<Animation>], time:f32) -> Result<Pose,Error> {
+        let mut pose=Pose::rest(self);
+
+        for sampler in animations.iter().flat_map(|animation| animation.all_samplers()) {
+            if self.bones.contains_key(&sampler.address.id) {
+                pose.apply_sampled(self, &sampler.address, &sampler.decode()?, time, SampleMode::Clamp)?;
+            }
+        }
+
+        Ok( pose )
+    }
+
+    ///Removes bones, that have no weights in skins and no weighted descendants.
+    ///Returns new skeleton and new index of each old bone. Skins can be bound to new skeleton,
+    ///their joints without weights, whose bones have been removed, have no bone index.
+    pub fn prune_unused(&self, skins:&[&Skin]) -> Result<(Skeleton,Vec<Option<usize>>),Error> {
+        let mut keep=vec![false;self.bones_array.len()];
+
+        for skin in skins.iter() {
+            for bone_index in skin.weighted_bones(self)? {
+                keep[bone_index]=true;
+            }
+        }
+
+        //children have larger indices, so walk from leaves to roots
+        for bone in self.bones_array.iter().rev() {
+            if keep[bone.index] {
+                match bone.parent {
+                    Some( parent_index ) => keep[parent_index]=true,
+                    None => {},
+                }
+            }
+        }
+
+        let mut remap=vec![None;self.bones_array.len()];
+        let mut new_index=0;
+
+        for bone in self.bones_array.iter() {
+            if keep[bone.index] {
+                remap[bone.index]=Some(new_index);
+                new_index+=1;
+            }
+        }
+
+        let mut bones_array=Vec::with_capacity(new_index);
+        let mut bones=HashMap::new();
+
+        for bone in self.bones_array.iter().filter(|bone| keep[bone.index]) {
+            let new_bone=Arc::new( Bone{
+                id:bone.id.clone(),
+                sid:bone.sid.clone(),
+                name:bone.name.clone(),
+                skeleton_id:bone.skeleton_id.clone(),
+                index:remap[bone.index].unwrap(),
+                parent:bone.parent.map(|parent_index| remap[parent_index].unwrap()),
+                children:bone.children.iter().filter_map(|child_index| remap[*child_index]).collect(),
+
+                location:bone.location.clone(),
+                matrix:bone.matrix.clone(),
+                transforms:bone.transforms.clone(),
+            } );
+
+            bones.insert(new_bone.id.clone(), new_bone.clone());
+            bones_array.push(new_bone);
+        }
+
+        let skeleton=Skeleton{
+            id:self.id.clone(),
+            location:self.location.clone(),
+            bones_array:bones_array,
+            bones:bones,
+        };
+
+        Ok( (skeleton, remap) )
+    }
+
     pub fn print(&self, printer:TreePrinter) {
         println!("Skeleton id:\"{}\"", self.id);
 
-        if self.bones_array.len()>0 {
-            self.bones_array[0].print( printer.new_branch(true), &self.bones_array );
+        let roots=self.roots();
+
+        for (last,root) in roots.iter().enumerate().map(|i| (i.0==roots.len()-1,i.1) ){
+            self.bones_array[*root].print( printer.new_branch(last), &self.bones_array );
         }
     }
 }
 
+///Skeleton, that contains bone with id, error if several skeletons contain it
+pub fn find_bone_skeleton<'a>(skeletons:&'a HashMap<String,Arc<Skeleton>>, bone_id:&str) -> Result<Option<&'a Arc<Skeleton>>,Error> {
+    let mut skeleton_ids:Vec<&String>=skeletons.keys().filter(|id| skeletons[*id].bones.contains_key(bone_id)).collect();
+    skeleton_ids.sort();
+
+    match skeleton_ids.len() {
+        0 => Ok( None ),
+        1 => Ok( Some(&skeletons[skeleton_ids[0]]) ),
+        _ => Err( Error::Other(format!("Bone \"{}\" belongs to several skeletons: {}", bone_id, skeleton_ids.iter().map(|id| id.as_str()).collect::<Vec<&str>>().join(", "))) ),
+    }
+}
+
+pub struct DepthFirstIter<'a>{
+    bones_array:&'a Vec<Arc<Bone>>,
+    stack:Vec<usize>,
+}
+
+impl<'a> Iterator for DepthFirstIter<'a>{
+    type Item=&'a Arc<Bone>;
+
+    fn next(&mut self) -> Option<&'a Arc<Bone>> {
+        let bone=&self.bones_array[self.stack.pop()?];
+
+        self.stack.extend(bone.children.iter().rev());
+
+        Some(bone)
+    }
+}
+
+pub struct BreadthFirstIter<'a>{
+    bones_array:&'a Vec<Arc<Bone>>,
+    queue:VecDeque<usize>,
+}
+
+impl<'a> Iterator for BreadthFirstIter<'a>{
+    type Item=&'a Arc<Bone>;
+
+    fn next(&mut self) -> Option<&'a Arc<Bone>> {
+        let bone=&self.bones_array[self.queue.pop_front()?];
+
+        self.queue.extend(bone.children.iter());
+
+        Some(bone)
+    }
+}
+
 pub struct Bone{
     pub id:String,
     pub sid:String,
@@ -86,8 +264,13 @@ pub struct Bone{
     pub skeleton_id:String,
     pub index:usize,
     pub parent:Option<usize>,
+    pub children:Vec<usize>,
 
     pub location:Location,
+    ///transformation of joint node converted to Y-up meters, unlike `location` it keeps shear and exact scale
+    pub matrix:Matrix,
+    ///transform elements of joint node, channels refer to them by sid
+    pub transforms:Vec<TransformElement>,
 }
 
 impl Display for Bone{
@@ -100,12 +283,13 @@ impl Bone {
     pub fn parse(
         bone_element:&Element,
         document:&mut Document,
-        skins_by_id:&HashMap<String,Arc<Skin>>,
         skeleton_id:String,
         parent:Option<usize>,
         geometries:&mut HashMap<String,Node<Geometry>>,
         cameras:&mut HashMap<String,Node<Camera>>,
+        lights:&mut HashMap<String,Node<Light>>,
         skeletons:&mut HashMap<String,Node<Skeleton>>,
+        skin_bindings:&mut Vec<SkinBinding>,
         bones_array:&mut Vec<Arc<Bone>>,
         bones:&mut HashMap<String,Arc<Bone>>,
     ) -> Result<(),Error> {
@@ -114,7 +298,18 @@ impl Bone {
         let name=bone_element.get_attribute("name")?.clone();
         let index=bones_array.len();
 
-        let location=Matrix::parse( bone_element.get_element("matrix")?.get_text()? )?.to_location(&document.asset);
+        let (location,matrix)=parse_location(bone_element, &document.asset)?;
+
+        //bones are pushed depth-first, so indices of children are known before they have been read
+        let mut children=Vec::new();
+        let mut child_index=index+1;
+
+        for node_element in bone_element.children.iter(){
+            if Self::is_joint(node_element) {
+                children.push(child_index);
+                child_index+=1+Self::count_joints(node_element);
+            }
+        }
 
         let bone=Arc::new( Bone{
             id:id.clone(),
@@ -123,8 +318,11 @@ impl Bone {
             skeleton_id:skeleton_id.clone(),
             index:index,
             parent:parent,
+            children:children,
 
             location:location,
+            matrix:matrix,
+            transforms:TransformElement::parse_all(bone_element, &document.asset)?,
         } );
 
         bones_array.push(bone.clone());
@@ -139,9 +337,9 @@ impl Bone {
                 let node_type=node_element.get_attribute("type")?;
 
                 if node_type.as_str()=="JOINT" {
-                    Bone::parse(node_element, document, skins_by_id, skeleton_id.clone(), Some(index), geometries, cameras, skeletons, bones_array, bones)?;
+                    Bone::parse(node_element, document, skeleton_id.clone(), Some(index), geometries, cameras, lights, skeletons, skin_bindings, bones_array, bones)?;
                 }else{
-                    parse_node(node_element, document, skins_by_id, Some(bone.clone()), geometries, cameras, skeletons)?;
+                    parse_node(node_element, document, Some(bone.clone()), geometries, cameras, lights, skeletons, skin_bindings)?;
                 }
             }
         }
@@ -149,24 +347,22 @@ impl Bone {
         Ok(())
     }
 
-    pub fn print(&self, printer:TreePrinter, bones_array:&Vec<Arc<Bone>>) {
-        println!("Bone index:{} id:\"{}\" name:\"{}\"", self.index, self.id, self.name);
+    fn is_joint(element:&Element) -> bool {
+        element.name.as_str()=="node" && match element.attributes.get("type") {
+            Some( node_type ) => node_type.as_str()=="JOINT",
+            None => false,
+        }
+    }
 
-        let mut children:Vec<&Arc<Bone>>=Vec::new();
+    fn count_joints(bone_element:&Element) -> usize {
+        bone_element.children.iter().filter(|element| Self::is_joint(element)).map(|element| 1+Self::count_joints(element)).sum()
+    }
 
-        for bone in bones_array.iter() {
-            match bone.parent {
-                Some( parent_index ) => {
-                    if parent_index==self.index {
-                        children.push(bone);
-                    }
-                },
-                None => {},
-            }
-        }
+    pub fn print(&self, printer:TreePrinter, bones_array:&Vec<Arc<Bone>>) {
+        println!("Bone index:{} id:\"{}\" name:\"{}\"", self.index, self.id, self.name);
 
-        for (last,bone) in children.iter().clone().enumerate().map(|i| (i.0==children.len()-1,i.1) ){
-            bone.print( printer.new_branch(last), bones_array );
+        for (last,child) in self.children.iter().enumerate().map(|i| (i.0==self.children.len()-1,i.1) ){
+            bones_array[*child].print( printer.new_branch(last), bones_array );
         }
     }
 }
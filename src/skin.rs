@@ -9,10 +9,15 @@ use std::sync::Arc;
 use Asset;
 use Source;
 use Matrix;
+use Geometry;
+use Mesh;
+use Pose;
+use Quaternion;
 use ArrayIter;
-use Bone;
+use Morph;
+use Skeleton;
+use SourceLayer;
 use TreePrinter;
-use Location;
 
 use source::read_sources;
 use source::select_sources;
@@ -28,9 +33,13 @@ pub struct BonesPerVertex{
 pub struct Skin {
     pub id:String,
     pub geometry_id:String,
-    pub skeleton_id:String,
-    pub skeleton_name:String,
-    pub bind_location:Location,
+    pub name:String,
+    ///morph, that is deformed by this skin, its base geometry is `geometry_id`
+    pub morph:Option<Arc<Morph>>,
+    ///`<bind_shape_matrix>` converted to Y-up meters
+    pub bind_shape_matrix:Matrix,
+    ///`INV_BIND_MATRIX` of each joint converted to Y-up meters
+    pub inverse_bind_matrices:Vec<Matrix>,
     pub sources:Vec<(String,Arc<Source>)>,
     pub additional_sources:HashMap<String,Arc<Source>>,
     pub bones_count_per_vertex:Vec<BonesPerVertex>,
@@ -42,6 +51,48 @@ pub struct BoneIndices{
     pub indices:Vec<usize>,
 }
 
+///Fixed number of joints per vertex, ready for GPU skinning
+pub struct Influences<const N:usize>{
+    ///indices of joints of skin(see `Skin::bind`), unused slots are 0
+    pub joints:Vec<[u16;N]>,
+    ///renormalized weights, unused slots are 0.0
+    pub weights:Vec<[f32;N]>,
+    ///count of vertices, that had more than N joints
+    pub truncated_vertices_count:usize,
+    ///maximal part of weight, that has been dropped from one vertex
+    pub max_weight_error:f32,
+}
+
+#[derive(Copy,Clone,Eq,PartialEq)]
+pub enum SkinningMethod{
+    ///blend of joint matrices
+    Linear,
+    ///blend of joint dual quaternions, keeps volume at twisted joints, scale of joints is ignored
+    DualQuaternion,
+}
+
+///Positions and normals for each vertex index(polygon corner) of mesh
+pub struct DeformedMesh{
+    pub positions:Vec<[f32;3]>,
+    ///empty if mesh has no NORMAL input
+    pub normals:Vec<[f32;3]>,
+}
+
+///Part of skinned mesh, that is deformed by limited number of joints
+pub struct SkinPartition{
+    pub mesh_index:usize,
+    ///indices of joints of skin, position in this list is index of bone in palette of partition
+    pub joints:Vec<usize>,
+    pub mesh:Mesh,
+}
+
+///Joint of skin resolved to bone of skeleton
+pub struct Joint{
+    ///None for joint without weights, whose bone is absent in skeleton(for example pruned one)
+    pub bone_index:Option<usize>,
+    pub inverse_bind_matrix:Matrix,
+}
+
 impl Display for Skin{
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Skin id:\"{}\" for geometry with id \"{}\"", self.id, self.geometry_id)
@@ -49,10 +100,10 @@ impl Display for Skin{
 }
 
 impl Skin {
-    pub fn parse(skin_element:&Element, id:String, skeleton_name:String, asset:&Asset) -> Result<Self, Error> {
+    pub fn parse(skin_element:&Element, id:String, name:String, asset:&Asset) -> Result<Self, Error> {
         let geometry_id=skin_element.get_attribute("source")?.trim_left_matches('#').to_string();
 
-        let bind_location=Matrix::parse(skin_element.get_element("bind_shape_matrix")?.get_text()?)?.to_location(asset);
+        let bind_shape_matrix=Matrix::parse(skin_element.get_element("bind_shape_matrix")?.get_text()?)?;
 
         let all_sources=read_sources(skin_element, asset)?;
 
@@ -63,18 +114,18 @@ impl Skin {
 
         let sources=select_sources(&vertex_weight_element,&all_sources)?;
         let additional_sources=Self::select_additional_sources(&joints_element,&all_sources)?;
+        let inverse_bind_matrices=Self::get_inverse_bind_matrices(&additional_sources)?;
 
         let (bones_count_per_vertex,bones_indices_count)=Self::read_bones_count_per_vertex(&vertex_weight_element)?;
         let bone_indices=Self::read_bone_indices(&vertex_weight_element, bones_indices_count, &sources)?;
 
-        let skeleton_id=skeleton_name.replace(".","_");
-
         let skin=Skin{
             id:id,
             geometry_id:geometry_id,
-            skeleton_id:skeleton_id,
-            skeleton_name:skeleton_name,
-            bind_location:bind_location,
+            name:name,
+            morph:None,
+            bind_shape_matrix:bind_shape_matrix.with_asset(asset),
+            inverse_bind_matrices:inverse_bind_matrices,
             sources:sources,
             additional_sources:additional_sources,
             bones_count_per_vertex:bones_count_per_vertex,
@@ -174,308 +225,489 @@ impl Skin {
         Ok( sources_list )
     }
 
-    pub fn print(&self, printer:TreePrinter) {
-        println!("Skin id:\"{}\" for geometry with id \"{}\"", self.id, self.geometry_id);
+    ///Matrices of `INV_BIND_MATRIX` source, empty if skin has no such source
+    fn get_inverse_bind_matrices(additional_sources:&HashMap<String,Arc<Source>>) -> Result<Vec<Matrix>,Error>{
+        let source=match additional_sources.get("INV_BIND_MATRIX") {
+            Some( source ) => source,
+            None => return Ok( Vec::new() ),
+        };
 
-        self.print_additional_sources( printer.new_branch(false) );
-        self.print_bones_indices( printer.new_branch(true) );
+        match source.layers.values().next() {
+            Some( &SourceLayer::Matrix(ref matrices) ) if source.layers.len()==1 => Ok( matrices.clone() ),
+            _ => Err(Error::Other( format!("INV_BIND_MATRIX source \"{}\" must have one float4x4 layer", source.id) )),
+        }
     }
 
-    fn print_additional_sources(&self, printer:TreePrinter) {
-        println!("Additional sources");
+    pub fn get_joint_names(&self) -> Result<&Vec<String>,Error> {
+        let source=match self.additional_sources.get("JOINT") {
+            Some( source ) => source,
+            None => return Err( Error::Other(format!("Skin \"{}\" has no JOINT source", self.id)) ),
+        };
 
-        for (last,(source_name,source)) in self.additional_sources.iter().clone().enumerate().map(|i| (i.0==self.additional_sources.len()-1,i.1) ){
-            printer.new_branch(last);
-            println!("Source name:\"{}\" id:\"{}\"",source_name,source.id);
+        match source.layers.get("bone_name") {
+            Some( &SourceLayer::Name(ref names) ) => Ok( names ),
+            _ => Err( Error::Other(format!("JOINT source of skin \"{}\" must have name layer", self.id)) ),
         }
     }
 
-    fn print_bones_indices(&self, printer:TreePrinter) {
-        println!("Bones");
+    fn get_weight_inputs(&self) -> Result<(&Vec<usize>,&Vec<usize>,&Vec<f32>),Error> {
+        let joint_indices=match self.bone_indices.get("JOINT") {
+            Some( joint_indices ) => &joint_indices.indices,
+            None => return Err( Error::Other(format!("Vertex weights of skin \"{}\" have no JOINT input", self.id)) ),
+        };
 
-        for (last,(ref name,ref bone_indices)) in self.bone_indices.iter().clone().enumerate().map(|i| (i.0==self.bone_indices.len()-1,i.1) ){
-            printer.new_branch(last);
-            println!("Bone indices for \"{}\" source id:\"{}\"",name,bone_indices.source.id);
-        }
+        let weight_indices=match self.bone_indices.get("WEIGHT") {
+            Some( weight_indices ) => weight_indices,
+            None => return Err( Error::Other(format!("Vertex weights of skin \"{}\" have no WEIGHT input", self.id)) ),
+        };
+
+        let weights=match weight_indices.source.layers.get("weight") {
+            Some( &SourceLayer::F32(ref weights) ) => weights,
+            _ => return Err( Error::Other(format!("WEIGHT source of skin \"{}\" must have float layer", self.id)) ),
+        };
+
+        Ok( (joint_indices, &weight_indices.indices, weights) )
     }
 
-    /*
+    ///Returns N joints with largest weights for each vertex, weights are renormalized
+    pub fn influences<const N:usize>(&self) -> Result<Influences<N>,Error> {
+        let (joint_indices, weight_indices, weights)=self.get_weight_inputs()?;
 
-    fn get_sources_order(sources:&Vec<(String,Arc<Source>)>) -> Vec<SourceOrder> {
-        let mut sources=select_sources(&vertex_weight_element,&all_sources)?;
+        let mut influences=Influences{
+            joints:Vec::with_capacity(self.bones_count_per_vertex.len()),
+            weights:Vec::with_capacity(self.bones_count_per_vertex.len()),
+            truncated_vertices_count:0,
+            max_weight_error:0.0,
+        };
 
-        let mut sources_order=Vec::with_capacity(sources.len());
-        for &(ref source_name, _) in sources.iter() {
-            let source_order=match source_name.as_str() {
-                "JOINT" => SourceOrder::BoneIndex,
-                "WEIGHT" => SourceOrder::Weight,
-                _ => SourceOrder::Ignore,
-            };
+        let mut vertex_influences:Vec<(usize,f32)>=Vec::new();
 
-            sources_order.push(source_order);
-        }
+        for bones_per_vertex in self.bones_count_per_vertex.iter() {
+            vertex_influences.clear();
 
-        sources_order
-    }
+            for i in bones_per_vertex.first_bone_index..bones_per_vertex.first_bone_index+bones_per_vertex.bones_count {
+                let weight=match weights.get(weight_indices[i]) {
+                    Some( weight ) => *weight,
+                    None => return Err( Error::Other(format!("Weight index {} of skin \"{}\" is out of range", weight_indices[i], self.id)) ),
+                };
 
-    fn read_bones_per_vertex(vertex_weight_element:&Element, vertices_count:usize, sources:&Vec<(String,Arc<Source>)>) -> Result<Vec<Vec<(usize,f32)>>, Error>{//read vertices(<p> tag)
-        let sources_count=sources.len();
+                match vertex_influences.iter_mut().find(|influence| influence.0==joint_indices[i]) {
+                    Some( influence ) => influence.1+=weight,
+                    None => vertex_influences.push( (joint_indices[i], weight) ),
+                }
+            }
 
-        let data=vertex_weight_element.get_element("v")?.get_text()?;
+            vertex_influences.sort_by(|a,b| b.1.partial_cmp(&a.1).unwrap_or(::std::cmp::Ordering::Equal));
 
-        let mut bones_per_vertex=Vec::with_capacity(max_bones_count_per_vertex);
-        for i in 0..max_bones_count_per_vertex {
-            bones_per_vertex.push(Vec::with_capacity(vertices_count));
-        }
+            let total_weight:f32=vertex_influences.iter().map(|influence| influence.1).sum();
+            let kept_weight:f32=vertex_influences.iter().take(N).map(|influence| influence.1).sum();
 
-        let mut array_iter=ArrayIter::new(source_data_indices_per_vertex, vertices_count*sources_count, "bones per vertexes");
+            if vertex_influences.len()>N {
+                influences.truncated_vertices_count+=1;
 
-        for i in 0..vertices_count {
-            for j in 0..bones_count_per_vertex[i] {
-                let mut bone_index=0;
-                let mut bone_weight=0.0;
-
-                for (source,source_order) in 0..sources.iter().zip(sources_order.iter()) {
-                    let index=array_iter.read_usize()?;
-
-                    match *source_order {
-                        SourceOrder::BoneIndex => bone_index=i,
-                        SourceOrder::BoneWeight => {
-                            let weight_layer=match source.layers.get("WEIGHT") {
-                                Some( ref weight_layer ) => {
-                                    match *weight_layer {
-                                        SourceLayer::F32 ( ref wl ) => wl,
-                                        _ => return Err( Error::Other("layer WEIGHT of bone weight source must have float type") ),
-                                    }
-                                },
-                                None => return Err( Error::Other("Bone weight source must have WEIGHT layer") ),
-                            };
-
-                            bone_weight = weight_layer[i];
-                        },
-                        SourceOrder::Ignore => {},
+                if total_weight>0.0 {
+                    let weight_error=(total_weight-kept_weight)/total_weight;
+
+                    if weight_error>influences.max_weight_error {
+                        influences.max_weight_error=weight_error;
                     }
                 }
+            }
+
+            let mut vertex_joints=[0u16;N];
+            let mut vertex_weights=[0.0f32;N];
+
+            for (slot,&(joint_index,weight)) in vertex_influences.iter().take(N).enumerate() {
+                if joint_index>u16::max_value() as usize {
+                    return Err( Error::Other(format!("Joint index {} of skin \"{}\" does not fit in u16", joint_index, self.id)) );
+                }
 
-                bones_per_vertex[j].push( (bone_index, bone_weight) );
+                vertex_joints[slot]=joint_index as u16;
+                vertex_weights[slot]=if kept_weight>0.0 { weight/kept_weight } else { 0.0 };
             }
 
-            for j in bones_count_per_vertex[i]..max_bones_count_per_vertex {
-                bones_per_vertex[j].push( (0, 0.0) );
+            influences.joints.push(vertex_joints);
+            influences.weights.push(vertex_weights);
+        }
+
+        Ok( influences )
+    }
+
+    ///Joint indices with non-zero weight for each vertex
+    fn get_vertex_joints(&self) -> Result<Vec<Vec<usize>>,Error> {
+        let (joint_indices, weight_indices, weights)=self.get_weight_inputs()?;
+
+        let mut vertex_joints=Vec::with_capacity(self.bones_count_per_vertex.len());
+
+        for bones_per_vertex in self.bones_count_per_vertex.iter() {
+            let mut joints=Vec::with_capacity(bones_per_vertex.bones_count);
+
+            for i in bones_per_vertex.first_bone_index..bones_per_vertex.first_bone_index+bones_per_vertex.bones_count {
+                let weight=match weights.get(weight_indices[i]) {
+                    Some( weight ) => *weight,
+                    None => return Err( Error::Other(format!("Weight index {} of skin \"{}\" is out of range", weight_indices[i], self.id)) ),
+                };
+
+                if weight!=0.0 && !joints.contains(&joint_indices[i]) {
+                    joints.push(joint_indices[i]);
+                }
             }
+
+            vertex_joints.push(joints);
         }
 
-        Ok(bones_per_vertex)
+        Ok( vertex_joints )
     }
 
-    fn get_bone_names_and_matrixes(sources:&HashMap<String,Arc<Source>>, additional_sources:&HashMap<String,Arc<Source>>) -> Result<Vec<(String,Matrix)>,Error> {
-        let bone_names=match sourses.get("JOINT") {
-            Some( ref source ) => {
-                match source.layers.get("JOINT") {
-                    Some( ref weight_layer ) => {
-                        match *weight_layer {
-                            SourceLayer::Name ( ref wl ) => wl,
-                            _ => return Err( Error::Other("layer WEIGHT of bone weight source must have float type") ),
+    ///Indices of bones of skeleton, that have non-zero weight in this skin
+    pub fn weighted_bones(&self, skeleton:&Skeleton) -> Result<Vec<usize>,Error> {
+        let joints=self.bind(skeleton)?;
+        let mut bones=Vec::new();
+
+        for vertex_joints in self.get_vertex_joints()? {
+            for joint_index in vertex_joints {
+                let bone_index=match joints.get(joint_index).and_then(|joint| joint.bone_index) {
+                    Some( bone_index ) => bone_index,
+                    None => return Err( Error::Other(format!("Joint index {} of skin \"{}\" is out of range", joint_index, self.id)) ),
+                };
+
+                if !bones.contains(&bone_index) {
+                    bones.push(bone_index);
+                }
+            }
+        }
+
+        Ok( bones )
+    }
+
+    ///Splits meshes of geometry to submeshes, each of them is deformed by at most max_joints joints
+    pub fn partition(&self, geometry:&Geometry, max_joints:usize) -> Result<Vec<SkinPartition>,Error> {
+        let vertex_joints=self.get_vertex_joints()?;
+        let mut partitions=Vec::new();
+
+        for (mesh_index,mesh) in geometry.meshes.iter().enumerate() {
+            let vertices=match mesh.vertex_indices.get("VERTEX") {
+                Some( vertices ) => vertices,
+                None => return Err( Error::Other(format!("Mesh \"{}\" has no VERTEX input", mesh.name)) ),
+            };
+
+            let mut joints:Vec<usize>=Vec::new();
+            let mut polygon_indices=Vec::new();
+
+            for (polygon_index,polygon) in mesh.polygons.iter().enumerate() {
+                let mut polygon_joints:Vec<usize>=Vec::new();
+
+                for &vertex_index in vertices.indices[polygon.first_vertex_index..polygon.first_vertex_index+polygon.vertices_count].iter() {
+                    let joints_of_vertex=match vertex_joints.get(vertex_index) {
+                        Some( joints_of_vertex ) => joints_of_vertex,
+                        None => return Err( Error::Other(format!("Vertex {} of mesh \"{}\" has no weights in skin \"{}\"", vertex_index, mesh.name, self.id)) ),
+                    };
+
+                    for joint_index in joints_of_vertex.iter() {
+                        if !polygon_joints.contains(joint_index) {
+                            polygon_joints.push(*joint_index);
                         }
-                    },
-                    None => return Err( Error::Other("Bone weight source must have WEIGHT layer") ),
+                    }
                 }
-            },
-            None => return Err( Error::Other("Bone weight source must have WEIGHT layer") ),
-        };
 
-        let bone_matrixes=match source.layers.get("JOINT") {
-            Some( ref weight_layer ) => {
-                match *weight_layer {
-                    SourceLayer::F32 ( ref wl ) => wl,
-                    _ => return Err( Error::Other("layer WEIGHT of bone weight source must have float type") ),
+                if polygon_joints.len()>max_joints {
+                    return Err( Error::Other(format!("Polygon {} of mesh \"{}\" is deformed by {} joints, but only {} are allowed", polygon_index, mesh.name, polygon_joints.len(), max_joints)) );
                 }
-            },
-            None => return Err( Error::Other("Bone weight source must have WEIGHT layer") ),
-        };
 
-    fn read_sources_order(vertex_weight_element:&Element) -> Result<Vec<SourceOrder>,Error> {
-        let mut sources_order=Vec::new();
+                let new_joints_count=polygon_joints.iter().filter(|joint_index| !joints.contains(joint_index)).count();
 
-        for input_element in vertex_weight_element.children.iter() {
-            if input_element.name.as_str()=="input" {
-                /*
-                let offset_str=input_element.get_attribute("offset")?;
+                if joints.len()+new_joints_count>max_joints {
+                    partitions.push( Self::new_partition(mesh, mesh_index, joints, &polygon_indices, partitions.len()) );
 
-                let offset=match offset_str.parse::<usize>(){
-                    Ok ( o ) => o,
-                    Err( _ ) => return Err(Error::ParseIntError( String::from("Offset of skin"), String::from(offset_str)) ),
-                };
+                    joints=Vec::new();
+                    polygon_indices.clear();
+                }
 
-                if offset>2 {
-                    return Err( Error::Other(String::from("Unknown offset of skin 2")) );
+                for joint_index in polygon_joints {
+                    if !joints.contains(&joint_index) {
+                        joints.push(joint_index);
+                    }
                 }
-                */
 
-                let source_order=match input_element.get_attribute("semantic")?.as_str() {
-                    "JOINT" => SourceOrder::BoneIndex,
-                    "WEIGHT" => SourceOrder::Weight,
-                    _ => SourceOrder::Ignore,
-                };
+                polygon_indices.push(polygon_index);
+            }
 
-                sources_order.push(source_order);
+            if polygon_indices.len()>0 {
+                partitions.push( Self::new_partition(mesh, mesh_index, joints, &polygon_indices, partitions.len()) );
             }
-        };
+        }
 
-        Ok( sources_order )
+        Ok( partitions )
     }
 
-    fn read_bones_count_per_vertex(vertex_weight_element:&Element, vertices_count:usize) -> Result<(Vec<usize>,usize),Error> {
-        let mut bones_count_per_vertex=Vec::with_capacity(vertices_count);
+    fn new_partition(mesh:&Mesh, mesh_index:usize, joints:Vec<usize>, polygon_indices:&[usize], partition_index:usize) -> SkinPartition {
+        let mut submesh=mesh.subset(polygon_indices);
+        submesh.name=format!("{}:{}", mesh.name, partition_index);
 
-        let mut count=0;
+        SkinPartition{
+            mesh_index:mesh_index,
+            joints:joints,
+            mesh:submesh,
+        }
+    }
 
-        for (i,n) in text.split(' ').filter(|n|*n!="").take(vertices_count).enumerate(){
-            match v.parse::<f32>(){
-                Ok ( f ) => weights.push( f ),
-                Err( _ ) => return Err(Error::ParseFloatError( String::from("Bone weight"), String::from(v)) ),
-            }
+    ///Returns joint palette: for each joint of skin index of bone and its inverse bind matrix.
+    ///Joints are matched with bones by sid first, then by id. Joints without weights may be absent in skeleton.
+    pub fn bind(&self, skeleton:&Skeleton) -> Result<Vec<Joint>,Error> {
+        let joint_names=self.get_joint_names()?;
 
-            count+=1;
+        if joint_names.len()!=self.inverse_bind_matrices.len() {
+            return Err( Error::Other(format!("Skin \"{}\" has {} joints but {} inverse bind matrices", self.id, joint_names.len(), self.inverse_bind_matrices.len())) );
         }
 
-        //check
-        if count!=bones_count_per_vertex {
-            return Err(Error::Other( format!("Only {} bones count per vertex have been read, expected {}", count, vertices_count) ));
+        let mut weighted=vec![false;joint_names.len()];
+
+        for vertex_joints in self.get_vertex_joints()? {
+            for joint_index in vertex_joints {
+                match weighted.get_mut(joint_index) {
+                    Some( weighted ) => *weighted=true,
+                    None => return Err( Error::Other(format!("Joint index {} of skin \"{}\" is out of range", joint_index, self.id)) ),
+                }
+            }
         }
 
-        let mut max_bones_count_per_vertex=0;
-        for bcpv in bones_count_per_vertex.iter() {
-            if bcpv>max_bones_count_per_vertex {
-                max_bones_count_per_vertex=bcpv;
+        let mut joints=Vec::with_capacity(joint_names.len());
+        let mut unresolved=Vec::new();
+
+        for (joint_index,(joint_name,inverse_bind_matrix)) in joint_names.iter().zip(self.inverse_bind_matrices.iter()).enumerate() {
+            let bone=match skeleton.get_bone_by_sid(joint_name) {
+                Some( bone ) => Some( bone ),
+                None => skeleton.get_bone_by_id(joint_name),
+            };
+
+            match (bone, weighted[joint_index]) {
+                (None, true) => unresolved.push(joint_name.as_str()),
+                (bone, _) => joints.push(
+                    Joint{
+                        bone_index:bone.map(|bone| bone.index),
+                        inverse_bind_matrix:inverse_bind_matrix.clone(),
+                    }
+                ),
             }
         }
 
-        if max_bones_count_per_vertex==0 {
-            return Err(Error::Other( String::from("max bones count per vertex == 0") ) );
+        if unresolved.len()>0 {
+            return Err( Error::Other(format!("Joints {} of skin \"{}\" can not be found in skeleton \"{}\"", unresolved.join(", "), self.id, skeleton.id)) );
         }
 
-        Ok( (bones_count_per_vertex, max_bones_count_per_vertex) )
+        Ok( joints )
     }
 
+    ///Returns deformed positions and normals of each mesh of geometry in pose.
+    ///Morph of skin(`morph`) is not applied, if skin has it, `geometry` should be already blended by weights of morph.
+    pub fn deform(&self, geometry:&Geometry, skeleton:&Skeleton, pose:&Pose, method:SkinningMethod) -> Result<Vec<DeformedMesh>,Error> {
+        let joints=self.bind(skeleton)?;
+        let (joint_indices, weight_indices, weights)=self.get_weight_inputs()?;
 
-    fn read_sources(skin_element:&Element, asset:&Asset) -> Result<(Vec<String>,Vec<Matrix4>,Vec<f32>),Error> {
-        let mut names_of_bones=None;
-        let mut weights=None;
-        let mut matrixes=None;
+        let bind_shape_matrix=&self.bind_shape_matrix;
 
-        for source_element in skin_element.children.iter() {
-            let accessor=source.get_element("technique_common")?.get_element("accessor")?;
-            let accessor_count=accessor.parse_attribute_as_usize("count")?;
-            let accessor_stride=accessor.parse_attribute_as_usize("stride")?;
+        let joint_matrices=pose.matrix_palette(skeleton, &joints)?;
 
-            let param=accessor.get_element("param")?;
-            let param_name=param.get_attribute("name")?;
-            let param_type=param.get_attribute("type")?;
+        //transformation for each vertex of skin
+        let mut vertex_transforms=Vec::with_capacity(self.bones_count_per_vertex.len());
 
-            match param_name.as_str() {
-                "JOINT" => {
-                    if param_type.as_str()!="name" {
-                        return Err( Error::Other( String::from("JOINT param must have name type") ) );
-                    }
+        for bones_per_vertex in self.bones_count_per_vertex.iter() {
+            let mut vertex_joints=Vec::with_capacity(bones_per_vertex.bones_count);
 
-                    names_of_bones=Some( Self::read_names_of_bones( source_element.get_element("Name_array")? )? );
-                },
-                "TRANSFORM" => {
-                    if param_type.as_str()!="float4x4" {
-                        return Err( Error::Other( String::from("TRANSFORM param must have float4x4 type") ) );
-                    }
-
-                    //TODO:read!!
+            for i in bones_per_vertex.first_bone_index..bones_per_vertex.first_bone_index+bones_per_vertex.bones_count {
+                let joint_matrix=match joint_matrices.get(joint_indices[i]) {
+                    Some( joint_matrix ) => joint_matrix,
+                    None => return Err( Error::Other(format!("Joint index {} of skin \"{}\" is out of range", joint_indices[i], self.id)) ),
+                };
 
-                },
-                "WEIGHT" => {
-                    if param_type.as_str()!="float" {
-                        return Err( Error::Other( String::from("WEIGHT param must have float type") ) );
-                    }
+                let weight=match weights.get(weight_indices[i]) {
+                    Some( weight ) => *weight,
+                    None => return Err( Error::Other(format!("Weight index {} of skin \"{}\" is out of range", weight_indices[i], self.id)) ),
+                };
 
-                    weights=Some( Self::read_weights( source_element.get_element("float_array")? )? );
-                },
-                _ => {},
+                vertex_joints.push( (joint_matrix, weight) );
             }
+
+            let transform=match method {
+                SkinningMethod::Linear => VertexTransform::Matrix( blend_matrices(&vertex_joints).multiply(bind_shape_matrix) ),
+                SkinningMethod::DualQuaternion => VertexTransform::DualQuaternion( blend_dual_quaternions(&vertex_joints), bind_shape_matrix ),
+            };
+
+            vertex_transforms.push(transform);
         }
 
-        let mut names_of_bones=match names_of_bones {
-            Some( nob ) => nob,
-            None => return Err( Error::Other( String::from("no names of bones") ) ),
-        };
+        let mut deformed_meshes=Vec::with_capacity(geometry.meshes.len());
 
-        let mut weights=match weights {
-            Some( w ) => w,
-            None => return Err( Error::Other( String::from("no weights of bones") ) ),
-        };
+        for mesh in geometry.meshes.iter() {
+            let vertices=match mesh.vertex_indices.get("VERTEX") {
+                Some( vertices ) => vertices,
+                None => return Err( Error::Other(format!("Mesh \"{}\" has no VERTEX input", mesh.name)) ),
+            };
 
-        let mut matrixes=match matrixes {
-            Some( m ) => m,
-            None => return Err( Error::Other( String::from("no matrixes of bones") ) ),
-        };
+            let positions=get_xyz_layers(&vertices.source)?;
+
+            if positions.0.len()!=vertex_transforms.len() {
+                return Err( Error::Other(format!("Skin \"{}\" has {} vertices, but mesh \"{}\" has {}", self.id, vertex_transforms.len(), mesh.name, positions.0.len())) );
+            }
+
+            let mut deformed_mesh=DeformedMesh{
+                positions:Vec::with_capacity(vertices.indices.len()),
+                normals:Vec::new(),
+            };
+
+            for &vertex_index in vertices.indices.iter() {
+                let position=[positions.0[vertex_index], positions.1[vertex_index], positions.2[vertex_index]];
+
+                deformed_mesh.positions.push( vertex_transforms[vertex_index].transform_point(position) );
+            }
+
+            match mesh.vertex_indices.get("NORMAL") {
+                Some( normal_indices ) => {
+                    let normals=get_xyz_layers(&normal_indices.source)?;
+
+                    deformed_mesh.normals.reserve(normal_indices.indices.len());
+
+                    for (&vertex_index,&normal_index) in vertices.indices.iter().zip(normal_indices.indices.iter()) {
+                        let normal=[normals.0[normal_index], normals.1[normal_index], normals.2[normal_index]];
+
+                        deformed_mesh.normals.push( normalize(vertex_transforms[vertex_index].transform_vector(normal)) );
+                    }
+                },
+                None => {},
+            }
 
-        if names_of_bones.len() != matrixes.len() {
-            return Err( Error::Other( format!("count of bones ({}) and count of matrixes ({}) mismatch", names_of_bones.len(), matrixes.len()) ) );
+            deformed_meshes.push(deformed_mesh);
         }
 
-        Ok(names_of_bones,weights,matrixes)
+        Ok( deformed_meshes )
     }
 
-    fn read_names_of_bones(name_array_element:&Element) -> Result<Vec<String>,Error> {
-        let names_of_bones_count=name_array_element.parse_attribute_as_usize("count")?;
-        let text=name_array_element.get_text()?;
+    pub fn print(&self, printer:TreePrinter) {
+        println!("Skin id:\"{}\" for geometry with id \"{}\"", self.id, self.geometry_id);
 
-        let mut names_of_bones=Vec::with_capacity(names_of_bones_count);
+        self.print_additional_sources( printer.new_branch(false) );
+        self.print_bones_indices( printer.new_branch(true) );
+    }
 
-        let mut count=0;
+    fn print_additional_sources(&self, printer:TreePrinter) {
+        println!("Additional sources");
+
+        for (last,(source_name,source)) in self.additional_sources.iter().clone().enumerate().map(|i| (i.0==self.additional_sources.len()-1,i.1) ){
+            printer.new_branch(last);
+            println!("Source name:\"{}\" id:\"{}\"",source_name,source.id);
+        }
+    }
 
-        for (i,n) in text.split(' ').filter(|n|*n!="").take(names_of_bones_count).enumerate(){
-            names_of_bones.push(String::from(n));
+    fn print_bones_indices(&self, printer:TreePrinter) {
+        println!("Bones");
 
-            count+=1;
+        for (last,(ref name,ref bone_indices)) in self.bone_indices.iter().clone().enumerate().map(|i| (i.0==self.bone_indices.len()-1,i.1) ){
+            printer.new_branch(last);
+            println!("Bone indices for \"{}\" source id:\"{}\"",name,bone_indices.source.id);
         }
+    }
+}
 
-        //check
-        if count!=names_of_bones_count {
-            return Err(Error::Other( format!("Only {} names of bones have been read, expected {}", count, names_of_bones_count) ));
+enum VertexTransform<'a>{
+    Matrix(Matrix),
+    DualQuaternion((Quaternion,Quaternion), &'a Matrix),
+}
+
+impl<'a> VertexTransform<'a>{
+    fn transform_point(&self, p:[f32;3]) -> [f32;3] {
+        match *self {
+            VertexTransform::Matrix( ref matrix ) => matrix.transform_point(p),
+            VertexTransform::DualQuaternion( (ref real, ref dual), bind_shape_matrix ) => {
+                let p=real.rotate( bind_shape_matrix.transform_point(p) );
+
+                //translation is 2*dual*conjugate(real)
+                [
+                    p[0] + 2.0*(real.w*dual.x - dual.w*real.x + real.y*dual.z - real.z*dual.y),
+                    p[1] + 2.0*(real.w*dual.y - dual.w*real.y + real.z*dual.x - real.x*dual.z),
+                    p[2] + 2.0*(real.w*dual.z - dual.w*real.z + real.x*dual.y - real.y*dual.x),
+                ]
+            },
         }
+    }
 
-        Ok( names_of_bones )
+    fn transform_vector(&self, v:[f32;3]) -> [f32;3] {
+        match *self {
+            VertexTransform::Matrix( ref matrix ) => matrix.transform_vector(v),
+            VertexTransform::DualQuaternion( (ref real, _), bind_shape_matrix ) =>
+                real.rotate( bind_shape_matrix.transform_vector(v) ),
+        }
     }
+}
 
-    fn read_weights(float_array_element:&Element) -> Result<Vec<String>,Error> {
-        let weights_count=float_array_element.parse_attribute_as_usize("count")?;
-        let text=float_array_element.get_text()?;
+fn blend_matrices(joints:&Vec<(&Matrix,f32)>) -> Matrix {
+    let mut mat=[0.0;16];
 
-        let mut weights=Vec::with_capacity(weights_count);
+    for &(joint_matrix,weight) in joints.iter() {
+        for i in 0..16 {
+            mat[i]+=joint_matrix.mat[i]*weight;
+        }
+    }
 
-        let mut count=0;
+    Matrix::from(mat)
+}
 
-        for (i,n) in text.split(' ').filter(|n|*n!="").take(weights_count).enumerate(){
-            match v.parse::<f32>(){
-                Ok ( f ) => weights.push( f ),
-                Err( _ ) => return Err(Error::ParseFloatError( String::from("Bone weight"), String::from(v)) ),
-            }
+fn blend_dual_quaternions(joints:&Vec<(&Matrix,f32)>) -> (Quaternion,Quaternion) {
+    let mut real=Quaternion::new(0.0, 0.0, 0.0, 0.0);
+    let mut dual=Quaternion::new(0.0, 0.0, 0.0, 0.0);
+    let mut first_real:Option<Quaternion>=None;
 
-            count+=1;
-        }
+    for &(joint_matrix,weight) in joints.iter() {
+        let location=joint_matrix.decompose();
+        let q=location.rotation;
+        let t=[location.position.x, location.position.y, location.position.z];
 
-        //check
-        if count!=weights_count {
-            return Err(Error::Other( format!("Only {} weights of bones have been read, expected {}", count, weights_count) ));
+        //all quaternions should be in one hemisphere
+        let weight=match first_real {
+            Some( ref first_real ) if first_real.dot(&q)<0.0 => -weight,
+            _ => weight,
+        };
+
+        if first_real.is_none() {
+            first_real=Some(q.clone());
         }
 
-        Ok( weights )
+        //dual part is 0.5*(t,0)*q
+        let d=Quaternion::new(
+            0.5*( t[0]*q.w + t[1]*q.z - t[2]*q.y),
+            0.5*(-t[0]*q.z + t[1]*q.w + t[2]*q.x),
+            0.5*( t[0]*q.y - t[1]*q.x + t[2]*q.w),
+            -0.5*(t[0]*q.x + t[1]*q.y + t[2]*q.z),
+        );
+
+        real=Quaternion::new(real.x+q.x*weight, real.y+q.y*weight, real.z+q.z*weight, real.w+q.w*weight);
+        dual=Quaternion::new(dual.x+d.x*weight, dual.y+d.y*weight, dual.z+d.z*weight, dual.w+d.w*weight);
+    }
+
+    let magnitude=real.magnitude();
+
+    if magnitude==0.0 {
+        return (Quaternion::identity(), Quaternion::new(0.0, 0.0, 0.0, 0.0));
     }
-    */
+
+    (
+        Quaternion::new(real.x/magnitude, real.y/magnitude, real.z/magnitude, real.w/magnitude),
+        Quaternion::new(dual.x/magnitude, dual.y/magnitude, dual.z/magnitude, dual.w/magnitude),
+    )
 }
 
-/*
-enum SourceOrder {
-    BoneIndex,
-    BoneWeight,
-    Ignore,
+fn get_xyz_layers(source:&Source) -> Result<(&Vec<f32>,&Vec<f32>,&Vec<f32>),Error> {
+    match (source.layers.get("X"), source.layers.get("Y"), source.layers.get("Z")) {
+        (Some( &SourceLayer::F32(ref x) ), Some( &SourceLayer::F32(ref y) ), Some( &SourceLayer::F32(ref z) )) => Ok( (x,y,z) ),
+        _ => Err( Error::Other(format!("Source \"{}\" must have float X, Y and Z layers", source.id)) ),
+    }
 }
 
-*/
+fn normalize(v:[f32;3]) -> [f32;3] {
+    let length=(v[0]*v[0] + v[1]*v[1] + v[2]*v[2]).sqrt();
+
+    if length>0.0 {
+        [v[0]/length, v[1]/length, v[2]/length]
+    }else{
+        v
+    }
+}
@@ -8,14 +8,9 @@ use std::collections::hash_map::Entry;
 
 use Asset;
 use Axis;
-use Editor;
 use ArrayIter;
 
-use Location;
 use Matrix;
-use Position;
-use Quaternion;
-use Scale;
 
 #[derive(Clone, Eq, PartialEq)]
 pub enum LayerType{
@@ -57,7 +52,7 @@ pub enum DataType{
     F32,
     I32,
     Name,
-    Location,
+    Matrix,
 }
 
 impl DataType{
@@ -66,23 +61,25 @@ impl DataType{
             DataType::F32 => "f32",
             DataType::I32 => "i32",
             DataType::Name => "name",
-            DataType::Location => "location",
+            DataType::Matrix => "matrix",
         }
     }
 
     pub fn get_size(&self) -> usize{
         match *self{
-            DataType::Location => 16,//from matrix 4*4
+            DataType::Matrix => 16,//matrix 4*4
             _ => 1,
         }
     }
 }
 
+#[derive(Clone)]
 pub enum SourceLayer{
     F32(Vec<f32>),
     I32(Vec<i32>),
     Name(Vec<String>),
-    Location(Vec<Location>),
+    ///float4x4 converted to Y-up meters
+    Matrix(Vec<Matrix>),
 }
 
 impl SourceLayer{
@@ -91,7 +88,7 @@ impl SourceLayer{
             SourceLayer::F32(_) => "f32",
             SourceLayer::I32(_) => "i32",
             SourceLayer::Name(_) => "name",
-            SourceLayer::Location(_) => "location",
+            SourceLayer::Matrix(_) => "matrix",
         }
     }
 
@@ -100,11 +97,12 @@ impl SourceLayer{
             SourceLayer::F32( ref list ) => list.len(),
             SourceLayer::I32( ref list ) => list.len(),
             SourceLayer::Name( ref list ) => list.len(),
-            SourceLayer::Location( ref list ) => list.len(),
+            SourceLayer::Matrix( ref list ) => list.len(),
         }
     }
 }
 
+#[derive(Clone)]
 pub struct Source{
     pub id:String,
     pub short_vertex_format:String,
@@ -139,6 +137,24 @@ impl Source{
         )
     }
 
+    pub fn scale_positions(&mut self, scale:f32) {
+        self.scale_layers(&["X","Y","Z"], scale);
+    }
+
+    ///Multiplies float layers with these names
+    pub fn scale_layers(&mut self, layer_names:&[&str], scale:f32) {
+        for layer_name in layer_names.iter() {
+            match self.layers.get_mut(*layer_name) {
+                Some( &mut SourceLayer::F32(ref mut list) ) => {
+                    for v in list.iter_mut() {
+                        *v*=scale;
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
     fn get_array_and_size(source:&Element) -> Result<(&String, usize),Error> {
         for data_element in source.children.iter() {
             if data_element.name.ends_with("_array") {
@@ -178,9 +194,9 @@ impl Source{
                 let param_data_type_str=param_element.get_attribute("type")?.as_str();
                 let param_type=match param_data_type_str{
                     "float" => DataType::F32,
-                    "name" => DataType::Name,
-                    "float4x4" => DataType::Location,
-                    _ => return Err(Error::Other( format!("Expected float,name or float4x4 but {} has been found",param_data_type_str) )),
+                    "name" | "IDREF" => DataType::Name,
+                    "float4x4" => DataType::Matrix,
+                    _ => return Err(Error::Other( format!("Expected float,name,IDREF or float4x4 but {} has been found",param_data_type_str) )),
                 };
 
                 params.push((param_name, standard_layer_type, param_type));
@@ -269,7 +285,7 @@ impl Source{
                 DataType::F32 => SourceLayer::F32( Vec::with_capacity(accessor_count) ),
                 DataType::I32 => SourceLayer::I32( Vec::with_capacity(accessor_count) ),
                 DataType::Name => SourceLayer::Name( Vec::with_capacity(accessor_count) ),
-                DataType::Location => SourceLayer::Location(  Vec::with_capacity(accessor_count) ),
+                DataType::Matrix => SourceLayer::Matrix( Vec::with_capacity(accessor_count) ),
             };
 
             layers_data.push(layer_data);
@@ -286,21 +302,19 @@ impl Source{
                         list.push( array_iter.read_i32()? ),
                     SourceLayer::Name( ref mut list ) =>
                         list.push( String::from(array_iter.read_str()?) ),
-                    SourceLayer::Location( ref mut list ) => {
+                    SourceLayer::Matrix( ref mut list ) => {
                         let mut mat=[0.0;16];
                         for k in 0..16 {
                             mat[k]=array_iter.read_f32()?;
                         }
 
-                        let location=Matrix::from(mat).to_location(asset);
-
-                        list.push( location );
+                        list.push( Matrix::from(mat).with_asset(asset) );
                     },
                 }
             }
         }
 
-        if asset.editor==Editor::Blender {//layer
+        if asset.fixups.flip_x {//layer
             //invert x axis(blender uses left-side coordination system)
             for source_layer_index in 0..params.len() {
                 if params[source_layer_index].1==LayerType::X {
@@ -337,12 +351,29 @@ impl Source{
 }
 
 pub fn read_sources(element:&Element, asset:&Asset) -> Result<HashMap<String,Arc<Source>>,Error>{
+    //find sources of positions, they should be converted to meters
+    let mut position_source_ids=Vec::new();
+
+    for vertices_element in element.children.iter(){
+        if vertices_element.name.as_str()=="vertices" {
+            for input_element in vertices_element.children.iter(){
+                if input_element.name.as_str()=="input" && input_element.get_attribute("semantic")?.as_str()=="POSITION" {
+                    position_source_ids.push( input_element.get_attribute("source")?.trim_left_matches('#').to_string() );
+                }
+            }
+        }
+    }
+
     //read sources
     let mut sources=HashMap::new();
 
     for source_element in element.children.iter(){
         if source_element.name.as_str()=="source" {
-            let source=Source::parse(&source_element, asset)?;
+            let mut source=Source::parse(&source_element, asset)?;
+
+            if asset.unit_scale()!=1.0 && position_source_ids.contains(&source.id) {
+                source.scale_positions(asset.unit_scale());
+            }
 
             match sources.entry(source.id.clone()){
                 Entry::Occupied(_) => return Err(Error::Other( format!("Duplicate source with id \"{}\"", &source.id) )),
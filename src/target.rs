@@ -0,0 +1,216 @@
+use Error;
+use StringExt;
+
+use Document;
+use TransformElement;
+
+use skeleton::find_bone_skeleton;
+
+///Part of address after the last sid
+#[derive(Clone,Eq,PartialEq,Debug)]
+pub enum Selector{
+    ///whole value
+    All,
+    ///member, like `.X` or `.ANGLE`
+    Member(String),
+    ///element of array, like `(3)`
+    Index(usize),
+    ///element of matrix, like `(3)(2)`, row goes first
+    Index2(usize,usize),
+}
+
+///Address of animated value, like "Hand_l/transform", "Camera-camera/xfov" or "Cube/rotationZ.ANGLE"
+#[derive(Clone,Eq,PartialEq,Debug)]
+pub struct TargetAddress{
+    ///id of element or "." for element, that contains animation
+    pub id:String,
+    pub sids:Vec<String>,
+    pub selector:Selector,
+}
+
+///Animated value, that target address has been resolved to
+#[derive(Clone,PartialEq,Debug)]
+pub enum Target{
+    ///transform element(`<matrix>`, `<translate>`, `<rotate>`, `<scale>`) of node, that is not bone
+    NodeTransform{node_id:String, transform_sid:String, selector:Selector},
+    ///transform element of bone
+    Bone{skeleton_id:String, bone_index:usize, transform_sid:String, selector:Selector},
+    Camera{camera_id:String, parameter:String, selector:Selector},
+    Light{light_id:String, parameter:String, selector:Selector},
+    ///parameter of material or of its effect
+    Material{material_id:String, parameter:String, selector:Selector},
+    MorphWeight{morph_id:String, index:usize},
+}
+
+impl TargetAddress{
+    pub fn parse(address:&str) -> Result<TargetAddress,Error>{
+        let mut parts=address.split('/');
+
+        let id=match parts.next() {
+            Some( id ) if id.len()>0 => String::from(id),
+            _ => return Err(Error::Other( format!("Target address \"{}\" has no id", address) )),
+        };
+
+        let mut sids:Vec<String>=parts.map(|sid| String::from(sid)).collect();
+
+        //selector can follow only sid, ids may contain dots
+        let selector=match sids.pop() {
+            Some( last_sid ) => {
+                let (sid,selector)=Self::parse_selector(&last_sid, address)?;
+                sids.push(sid);
+
+                selector
+            },
+            None => Selector::All,
+        };
+
+        if sids.iter().any(|sid| sid.len()==0) {
+            return Err(Error::Other( format!("Target address \"{}\" has empty sid", address) ));
+        }
+
+        let target_address=TargetAddress{
+            id:id,
+            sids:sids,
+            selector:selector,
+        };
+
+        Ok( target_address )
+    }
+
+    fn parse_selector(sid:&str, address:&str) -> Result<(String,Selector),Error> {
+        match sid.find('(') {
+            Some( pos ) => {
+                let indices_str=&sid[pos..];
+
+                if !indices_str.starts_with('(') || !indices_str.ends_with(')') {
+                    return Err(Error::Other( format!("Target address \"{}\" has bad array index", address) ));
+                }
+
+                let mut indices=Vec::with_capacity(2);
+
+                for index_str in indices_str[1..indices_str.len()-1].split(")(") {
+                    indices.push( index_str.parse_as_usize("index of target address")? );
+                }
+
+                let selector=match indices.len() {
+                    1 => Selector::Index(indices[0]),
+                    2 => Selector::Index2(indices[0], indices[1]),
+                    _ => return Err(Error::Other( format!("Target address \"{}\" has more than 2 indices", address) )),
+                };
+
+                return Ok( (String::from(&sid[..pos]), selector) );
+            },
+            None => {},
+        }
+
+        match sid.find('.') {
+            Some( pos ) => Ok( (String::from(&sid[..pos]), Selector::Member(String::from(&sid[pos+1..]))) ),
+            None => Ok( (String::from(sid), Selector::All) ),
+        }
+    }
+
+    ///Finds element with id of address: bone, morph, camera, light, material(or its effect) or node.
+    ///Address with "." id can be resolved only by `resolve_from`.
+    pub fn resolve(&self, document:&Document) -> Result<Target,Error> {
+        if self.id=="." {
+            return Err(Error::Other( format!("Target address \"./{}\" is relative to element, that contains animation", self.sids.join("/")) ));
+        }
+
+        self.resolve_id(document, &self.id)
+    }
+
+    ///Like `resolve`, but "." id means element with `element_id`, that contains animation
+    pub fn resolve_from(&self, document:&Document, element_id:&str) -> Result<Target,Error> {
+        if self.id=="." {
+            self.resolve_id(document, element_id)
+        }else{
+            self.resolve_id(document, &self.id)
+        }
+    }
+
+    fn resolve_id(&self, document:&Document, id:&str) -> Result<Target,Error> {
+        let parameter=self.sids.join("/");
+
+        match find_bone_skeleton(&document.skeletons, id)? {
+            Some( skeleton ) => {
+                let bone=match skeleton.bones.get(id) {
+                    Some( bone ) => bone,
+                    None => return Err(Error::Other( format!("Skeleton \"{}\" has no bone \"{}\"", skeleton.id, id) )),
+                };
+
+                return Ok( Target::Bone{
+                    skeleton_id:skeleton.id.clone(),
+                    bone_index:bone.index,
+                    transform_sid:self.get_transform_sid(id, &bone.transforms)?,
+                    selector:self.selector.clone(),
+                } );
+            },
+            None => {},
+        }
+
+        match document.morphs.get(id) {
+            Some( morph ) => {
+                let index=match self.selector {
+                    Selector::Index( index ) => index,
+                    Selector::All if morph.targets.len()==1 => 0,
+                    _ => return Err(Error::Other( format!("Target address of morph \"{}\" must have index of weight", id) )),
+                };
+
+                if index>=morph.targets.len() {
+                    return Err(Error::Other( format!("Morph \"{}\" has {} targets, but weight {} is animated", id, morph.targets.len(), index) ));
+                }
+
+                return Ok( Target::MorphWeight{ morph_id:morph.id.clone(), index:index } );
+            },
+            None => {},
+        }
+
+        if document.cameras.contains_key(id) {
+            return Ok( Target::Camera{ camera_id:id.to_string(), parameter:parameter, selector:self.selector.clone() } );
+        }
+
+        if document.lights.contains_key(id) {
+            return Ok( Target::Light{ light_id:id.to_string(), parameter:parameter, selector:self.selector.clone() } );
+        }
+
+        //several materials may share effect, first of them in order of ids is chosen
+        let mut material_ids:Vec<&String>=document.materials.keys().collect();
+        material_ids.sort();
+
+        match material_ids.into_iter().map(|material_id| &document.materials[material_id]).find(|material| material.id==id || material.effect_id==id) {
+            Some( material ) =>
+                return Ok( Target::Material{ material_id:material.id.clone(), parameter:parameter, selector:self.selector.clone() } ),
+            None => {},
+        }
+
+        let mut node_transforms=None;
+
+        for scene in document.scenes.values() {
+            node_transforms=node_transforms
+                .or_else(|| scene.geometries.values().find(|node| node.id==id).map(|node| &node.transforms))
+                .or_else(|| scene.cameras.values().find(|node| node.id==id).map(|node| &node.transforms))
+                .or_else(|| scene.lights.values().find(|node| node.id==id).map(|node| &node.transforms))
+                .or_else(|| scene.skeletons.values().find(|node| node.id==id).map(|node| &node.transforms));
+        }
+
+        match node_transforms {
+            Some( transforms ) =>
+                return Ok( Target::NodeTransform{ node_id:id.to_string(), transform_sid:self.get_transform_sid(id, transforms)?, selector:self.selector.clone() } ),
+            None => {},
+        }
+
+        Err(Error::Other( format!("Element with id \"{}\" of target address does not exists", id) ))
+    }
+
+    fn get_transform_sid(&self, id:&str, transforms:&Vec<TransformElement>) -> Result<String,Error> {
+        if self.sids.len()!=1 {
+            return Err(Error::Other( format!("Target address of node \"{}\" must have sid of one transform element", id) ));
+        }
+
+        if !transforms.iter().any(|transform| transform.sid.as_ref()==Some(&self.sids[0])) {
+            return Err(Error::Other( format!("Node \"{}\" has no transform element with sid \"{}\"", id, self.sids[0]) ));
+        }
+
+        Ok( self.sids[0].clone() )
+    }
+}
@@ -0,0 +1,167 @@
+use Error;
+
+use Animation;
+use Channel;
+use ChannelValues;
+use Clip;
+use Interpolation;
+use Quaternion;
+use TargetAddress;
+
+///Values of keys closer than this are equal
+const EPSILON:f32=0.00001;
+
+///Keys per second of tracks, that curves(BEZIER, HERMITE and so on) are baked to
+pub const CURVE_FPS:f32=30.0;
+
+#[derive(Clone)]
+pub enum TrackValues{
+    Translation(Vec<[f32;3]>),
+    Rotation(Vec<Quaternion>),
+    Scale(Vec<[f32;3]>),
+}
+
+///Translation, rotation or scale keys of one node(bone)
+#[derive(Clone)]
+pub struct TrsTrack{
+    pub node_id:String,
+    pub times:Vec<f32>,
+    pub values:TrackValues,
+    pub interpolation:Vec<Interpolation>,
+}
+
+impl TrackValues{
+    pub fn get_length(&self) -> usize {
+        match *self{
+            TrackValues::Translation( ref list ) => list.len(),
+            TrackValues::Rotation( ref list ) => list.len(),
+            TrackValues::Scale( ref list ) => list.len(),
+        }
+    }
+
+    fn is_same(&self, a:usize, b:usize) -> bool {
+        let same=|a:&[f32;3], b:&[f32;3]| a.iter().zip(b.iter()).all(|(a,b)| (a-b).abs()<=EPSILON);
+
+        match *self{
+            TrackValues::Translation( ref list ) => same(&list[a], &list[b]),
+            TrackValues::Scale( ref list ) => same(&list[a], &list[b]),
+            TrackValues::Rotation( ref list ) => list[a].dot(&list[b]).abs()>=1.0-EPSILON,
+        }
+    }
+
+    fn select(&self, keys:&[usize]) -> TrackValues {
+        match *self{
+            TrackValues::Translation( ref list ) => TrackValues::Translation( keys.iter().map(|key| list[*key]).collect() ),
+            TrackValues::Rotation( ref list ) => TrackValues::Rotation( keys.iter().map(|key| list[*key].clone()).collect() ),
+            TrackValues::Scale( ref list ) => TrackValues::Scale( keys.iter().map(|key| list[*key]).collect() ),
+        }
+    }
+}
+
+impl TrsTrack{
+    fn new(node_id:&str, channel:&Channel, values:TrackValues) -> TrsTrack {
+        TrsTrack{
+            node_id:String::from(node_id),
+            times:channel.times.clone(),
+            values:values,
+            interpolation:channel.interpolation.clone(),
+        }
+    }
+
+    ///Track keeps one value
+    pub fn is_constant(&self) -> bool {
+        (1..self.times.len()).all(|key| self.values.is_same(0, key))
+    }
+
+    ///Removes keys, that have same value as both neighbour keys
+    fn remove_repeated_keys(&self) -> TrsTrack {
+        let keys_count=self.times.len();
+
+        let keys:Vec<usize>=(0..keys_count).filter(|key|
+            *key==0 || *key==keys_count-1 || !self.values.is_same(key-1, *key) || !self.values.is_same(*key, key+1)
+        ).collect();
+
+        TrsTrack{
+            node_id:self.node_id.clone(),
+            times:keys.iter().map(|key| self.times[*key]).collect(),
+            values:self.values.select(&keys),
+            interpolation:keys.iter().map(|key| self.interpolation[*key]).collect(),
+        }
+    }
+}
+
+impl Channel{
+    ///Splits `transform` matrices into translation, rotation and scale tracks,
+    ///`translate` and `scale` vec3 channels become one track. Other channels give no tracks.
+    ///Tracks have only STEP and LINEAR keys, channels with curves are resampled with `CURVE_FPS`.
+    pub fn to_trs_tracks(&self) -> Result<Vec<TrsTrack>,Error> {
+        let has_curves=self.interpolation.iter().any(|interpolation| *interpolation!=Interpolation::Step && *interpolation!=Interpolation::Linear);
+
+        if has_curves {
+            return self.resample(CURVE_FPS)?.to_trs_tracks();
+        }
+
+        let address=TargetAddress::parse(&self.target)?;
+
+        let tracks=match self.values {
+            ChannelValues::Location( ref locations ) => {
+                let mut rotations:Vec<Quaternion>=Vec::with_capacity(locations.len());
+
+                //q and -q are same rotation, but slerp between keys of different signs takes long path
+                for location in locations.iter() {
+                    let rotation=match rotations.last() {
+                        Some( previous ) if previous.dot(&location.rotation)<0.0 => {
+                            let r=&location.rotation;
+                            Quaternion::new(-r.x, -r.y, -r.z, -r.w)
+                        },
+                        _ => location.rotation.clone(),
+                    };
+
+                    rotations.push(rotation);
+                }
+
+                vec![
+                    TrsTrack::new(&address.id, self, TrackValues::Translation( locations.iter().map(|l| [l.position.x, l.position.y, l.position.z]).collect() )),
+                    TrsTrack::new(&address.id, self, TrackValues::Rotation( rotations )),
+                    TrsTrack::new(&address.id, self, TrackValues::Scale( locations.iter().map(|l| [l.scale.x, l.scale.y, l.scale.z]).collect() )),
+                ]
+            },
+            ChannelValues::Vec3( ref values ) if address.sids==["translate"] || address.sids==["location"] =>
+                vec![ TrsTrack::new(&address.id, self, TrackValues::Translation( values.clone() )) ],
+            ChannelValues::Vec3( ref values ) if address.sids==["scale"] =>
+                vec![ TrsTrack::new(&address.id, self, TrackValues::Scale( values.clone() )) ],
+            _ => Vec::new(),
+        };
+
+        Ok( tracks.into_iter().map(|track| track.remove_repeated_keys()).collect() )
+    }
+}
+
+impl Animation{
+    ///Translation, rotation and scale tracks of channels of animation.
+    ///Constant tracks are removed, nodes keep their values from scene for them.
+    pub fn to_trs_tracks(&self) -> Result<Vec<TrsTrack>,Error> {
+        collect_tracks( &self.channels()? )
+    }
+}
+
+impl Clip{
+    ///Translation, rotation and scale tracks of channels of clip, constant tracks are removed
+    pub fn to_trs_tracks(&self) -> Result<Vec<TrsTrack>,Error> {
+        collect_tracks( &self.channels )
+    }
+}
+
+fn collect_tracks(channels:&[Channel]) -> Result<Vec<TrsTrack>,Error> {
+    let mut tracks=Vec::new();
+
+    for channel in channels.iter() {
+        for track in channel.to_trs_tracks()? {
+            if !track.is_constant() {
+                tracks.push(track);
+            }
+        }
+    }
+
+    Ok( tracks )
+}
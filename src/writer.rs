@@ -0,0 +1,944 @@
+use Error;
+
+use std::io::Write;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use Document;
+use Animation;
+use AnimationClip;
+use Bone;
+use Camera;
+use Channel;
+use ChannelValue;
+use ChannelValues;
+use ColorOrTexture;
+use Controller;
+use Effect;
+use Geometry;
+use Interpolation;
+use Light;
+use LightKind;
+use Location;
+use Material;
+use Matrix;
+use Mesh;
+use Morph;
+use MorphMethod;
+use Node;
+use Pose;
+use SampleMode;
+use Sampler;
+use Scene;
+use Skeleton;
+use Skin;
+use Source;
+use SourceLayer;
+use Tangent;
+use TransformElement;
+use CURVE_FPS;
+
+use pose::rotate_elements;
+
+///Options of `Document::write`
+#[derive(Copy,Clone)]
+pub struct WriteOptions{
+    ///meshes, that have only triangles, are written as `<triangles>` instead of `<polylist>`
+    pub triangles:bool,
+    ///every element begins on new line with indentation
+    pub indent:bool,
+}
+
+impl Default for WriteOptions{
+    fn default() -> WriteOptions{
+        WriteOptions{
+            triangles:false,
+            indent:true,
+        }
+    }
+}
+
+///Element of written document, attributes keep their order
+struct XmlNode{
+    name:&'static str,
+    attributes:Vec<(&'static str,String)>,
+    text:Option<String>,
+    children:Vec<XmlNode>,
+}
+
+impl XmlNode{
+    fn new(name:&'static str) -> XmlNode{
+        XmlNode{
+            name:name,
+            attributes:Vec::new(),
+            text:None,
+            children:Vec::new(),
+        }
+    }
+
+    fn attribute(mut self, name:&'static str, value:&str) -> XmlNode{
+        self.attributes.push( (name, String::from(value)) );
+        self
+    }
+
+    fn text(mut self, text:String) -> XmlNode{
+        self.text=Some(text);
+        self
+    }
+
+    fn child(mut self, child:XmlNode) -> XmlNode{
+        self.children.push(child);
+        self
+    }
+
+    fn push(&mut self, child:XmlNode){
+        self.children.push(child);
+    }
+
+    fn write<W:Write>(&self, writer:&mut W, depth:usize, indent:bool) -> ::std::io::Result<()>{
+        let (tab,new_line)=if indent { ("  ".repeat(depth), "\n") } else { (String::new(), "") };
+
+        write!(writer, "{}<{}", tab, self.name)?;
+
+        for &(name, ref value) in self.attributes.iter() {
+            write!(writer, " {}=\"{}\"", name, escape(value))?;
+        }
+
+        if self.children.len()==0 {
+            match self.text {
+                Some( ref text ) => write!(writer, ">{}</{}>{}", escape(text), self.name, new_line),
+                None => write!(writer, "/>{}", new_line),
+            }
+        }else{
+            write!(writer, ">{}", new_line)?;
+
+            for child in self.children.iter() {
+                child.write(writer, depth+1, indent)?;
+            }
+
+            write!(writer, "{}</{}>{}", tab, self.name, new_line)
+        }
+    }
+}
+
+fn escape(text:&str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn join<T:ToString>(values:&[T]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+fn matrix_text(matrix:&Matrix) -> String {
+    join(&matrix.mat)
+}
+
+fn url(id:&str) -> String {
+    format!("#{}", id)
+}
+
+///Values of map in order of their keys, so written documents do not change from run to run
+pub fn sorted<'a, T>(map:&'a HashMap<String,T>) -> Vec<&'a T> {
+    let mut keys:Vec<&String>=map.keys().collect();
+    keys.sort();
+
+    keys.into_iter().map(|key| &map[key]).collect()
+}
+
+///`<source>` with `float_array`, params are (name,type), stride is count of floats per element
+fn float_source(id:&str, params:&[(&str,&str)], stride:usize, values:&[f32]) -> XmlNode {
+    let array_id=format!("{}-array", id);
+    let count=if stride>0 { values.len()/stride } else { 0 };
+
+    let mut accessor=XmlNode::new("accessor")
+        .attribute("source", &url(&array_id))
+        .attribute("count", &count.to_string())
+        .attribute("stride", &stride.to_string());
+
+    for &(name,param_type) in params.iter() {
+        accessor.push( XmlNode::new("param").attribute("name", name).attribute("type", param_type) );
+    }
+
+    XmlNode::new("source").attribute("id", id)
+        .child( XmlNode::new("float_array").attribute("id", &array_id).attribute("count", &values.len().to_string()).text(join(values)) )
+        .child( XmlNode::new("technique_common").child(accessor) )
+}
+
+///`<source>` with `Name_array` or `IDREF_array` of one param
+fn name_source(id:&str, array_name:&'static str, param:&str, param_type:&str, names:&[String]) -> XmlNode {
+    let array_id=format!("{}-array", id);
+
+    let accessor=XmlNode::new("accessor")
+        .attribute("source", &url(&array_id))
+        .attribute("count", &names.len().to_string())
+        .attribute("stride", "1")
+        .child( XmlNode::new("param").attribute("name", param).attribute("type", param_type) );
+
+    XmlNode::new("source").attribute("id", id)
+        .child( XmlNode::new(array_name).attribute("id", &array_id).attribute("count", &names.len().to_string()).text(join(names)) )
+        .child( XmlNode::new("technique_common").child(accessor) )
+}
+
+///Writes layers of source in order of `short_vertex_format`, layer names become COLLADA param names
+fn source_node(source:&Source) -> Result<XmlNode,Error> {
+    let layer_names:Vec<&str>=source.short_vertex_format.split(',').collect();
+    let mut layers=Vec::with_capacity(layer_names.len());
+
+    for layer_name in layer_names.iter() {
+        match source.layers.get(*layer_name) {
+            Some( layer ) => layers.push( (*layer_name, layer) ),
+            None => return Err(Error::Other( format!("Source \"{}\" has no layer \"{}\"", source.id, layer_name) )),
+        }
+    }
+
+    let count=layers[0].1.get_length();
+
+    let all_names=layers.iter().all(|&(_,layer)| match *layer { SourceLayer::Name(_) => true, _ => false });
+
+    if all_names {
+        return match *layers[0].1 {
+            SourceLayer::Name( ref names ) if layers.len()==1 => Ok( name_source(&source.id, "Name_array", get_param_name(layers[0].0), "name", names) ),
+            _ => Err(Error::Other( format!("Source \"{}\" has several name layers", source.id) )),
+        };
+    }
+
+    let mut params=Vec::with_capacity(layers.len());
+    let mut stride=0;
+
+    for &(layer_name,layer) in layers.iter() {
+        let param_type=match *layer {
+            SourceLayer::F32(_) | SourceLayer::I32(_) => "float",
+            SourceLayer::Matrix(_) => "float4x4",
+            SourceLayer::Name(_) => return Err(Error::Other( format!("Source \"{}\" mixes names and numbers", source.id) )),
+        };
+
+        stride+=if param_type=="float4x4" { 16 } else { 1 };
+        params.push( (get_param_name(layer_name), param_type) );
+    }
+
+    let mut values=Vec::with_capacity(count*stride);
+
+    for i in 0..count {
+        for &(_,layer) in layers.iter() {
+            match *layer {
+                SourceLayer::F32( ref list ) => values.push( list[i] ),
+                SourceLayer::I32( ref list ) => values.push( list[i] as f32 ),
+                SourceLayer::Matrix( ref list ) => values.extend_from_slice( &list[i].mat ),
+                SourceLayer::Name(_) => unreachable!(),
+            }
+        }
+    }
+
+    Ok( float_source(&source.id, &params, stride, &values) )
+}
+
+///Name of param of layer, that has been renamed while source has been read
+fn get_param_name(layer_name:&str) -> &str {
+    match layer_name {
+        "U" => "S",
+        "V" => "T",
+        "bone_name" => "JOINT",
+        "location" => "TRANSFORM",
+        "weight" => "WEIGHT",
+        _ => layer_name,
+    }
+}
+
+fn input(semantic:&str, source_id:&str) -> XmlNode {
+    XmlNode::new("input").attribute("semantic", semantic).attribute("source", &url(source_id))
+}
+
+fn input_with_offset(semantic:&str, source_id:&str, offset:usize) -> XmlNode {
+    input(semantic, source_id).attribute("offset", &offset.to_string())
+}
+
+///Pushes sources, that have not been written yet
+fn push_sources<'a, I:Iterator<Item=&'a Arc<Source>>>(element:&mut XmlNode, written:&mut Vec<String>, sources:I) -> Result<(),Error> {
+    for source in sources {
+        if !written.contains(&source.id) {
+            element.push( source_node(source)? );
+            written.push( source.id.clone() );
+        }
+    }
+
+    Ok(())
+}
+
+fn geometry_node(geometry:&Geometry, options:&WriteOptions) -> Result<XmlNode,Error> {
+    let mut mesh_element=XmlNode::new("mesh");
+    let mut written=Vec::new();
+
+    push_sources(&mut mesh_element, &mut written, geometry.meshes.iter().flat_map(|mesh| mesh.sources.iter().map(|&(_,ref source)| source)))?;
+
+    //VERTEX input refers <vertices>, that is synonym of positions
+    let vertices_id=format!("{}-vertices", geometry.id);
+    let position_source=geometry.meshes.iter().flat_map(|mesh| mesh.sources.iter()).find(|&&(ref semantic,_)| semantic.as_str()=="VERTEX");
+
+    match position_source {
+        Some( &(_,ref source) ) => mesh_element.push( XmlNode::new("vertices").attribute("id", &vertices_id).child( input("POSITION", &source.id) ) ),
+        None => {},
+    }
+
+    for mesh in geometry.meshes.iter() {
+        mesh_element.push( polygons_node(mesh, &vertices_id, options)? );
+    }
+
+    let mut element=XmlNode::new("geometry").attribute("id", &geometry.id).attribute("name", &geometry.name);
+    element.push(mesh_element);
+
+    Ok( element )
+}
+
+fn polygons_node(mesh:&Mesh, vertices_id:&str, options:&WriteOptions) -> Result<XmlNode,Error> {
+    let is_triangles=options.triangles && mesh.polygons.iter().all(|polygon| polygon.vertices_count==3);
+
+    let mut element=XmlNode::new(if is_triangles { "triangles" } else { "polylist" });
+
+    match mesh.material {
+        Some( ref material ) => element=element.attribute("material", material),
+        None => {},
+    }
+
+    element=element.attribute("count", &mesh.polygons.len().to_string());
+
+    let mut indices=Vec::with_capacity(mesh.sources.len());
+
+    for (offset,&(ref semantic,ref source)) in mesh.sources.iter().enumerate() {
+        let source_id=if semantic.as_str()=="VERTEX" { vertices_id } else { source.id.as_str() };
+        element.push( input_with_offset(semantic, source_id, offset) );
+
+        match mesh.vertex_indices.get(semantic) {
+            Some( vertex_indices ) => indices.push( &vertex_indices.indices ),
+            None => return Err(Error::Other( format!("Mesh \"{}\" has no indices of source \"{}\"", mesh.name, semantic) )),
+        }
+    }
+
+    if !is_triangles {
+        let vcount:Vec<usize>=mesh.polygons.iter().map(|polygon| polygon.vertices_count).collect();
+        element.push( XmlNode::new("vcount").text(join(&vcount)) );
+    }
+
+    let vertices_count=mesh.polygons.iter().map(|polygon| polygon.vertices_count).sum::<usize>();
+    let mut p=Vec::with_capacity(vertices_count*indices.len());
+
+    for i in 0..vertices_count {
+        for source_indices in indices.iter() {
+            p.push( source_indices[i] );
+        }
+    }
+
+    element.push( XmlNode::new("p").text(join(&p)) );
+
+    Ok( element )
+}
+
+fn skin_node(skin:&Skin) -> Result<XmlNode,Error> {
+    let skin_source=match skin.morph {
+        Some( ref morph ) => &morph.id,
+        None => &skin.geometry_id,
+    };
+
+    let mut skin_element=XmlNode::new("skin").attribute("source", &url(skin_source))
+        .child( XmlNode::new("bind_shape_matrix").text(join(&skin.bind_shape_matrix.mat)) );
+
+    let mut written=Vec::new();
+    let mut additional_sources:Vec<(&String,&Arc<Source>)>=skin.additional_sources.iter().collect();
+    additional_sources.sort_by_key(|&(semantic,_)| if semantic.as_str()=="JOINT" { 0 } else { 1 });
+
+    push_sources(&mut skin_element, &mut written, additional_sources.iter().map(|&(_,source)| source))?;
+
+    push_sources(&mut skin_element, &mut written, skin.sources.iter().map(|&(_,ref source)| source))?;
+
+    let mut joints=XmlNode::new("joints");
+
+    for &(semantic,source) in additional_sources.iter() {
+        joints.push( input(semantic, &source.id) );
+    }
+
+    skin_element.push(joints);
+
+    let mut vertex_weights=XmlNode::new("vertex_weights").attribute("count", &skin.bones_count_per_vertex.len().to_string());
+    let mut indices=Vec::with_capacity(skin.sources.len());
+
+    for (offset,&(ref semantic,ref source)) in skin.sources.iter().enumerate() {
+        vertex_weights.push( input_with_offset(semantic, &source.id, offset) );
+
+        match skin.bone_indices.get(semantic) {
+            Some( bone_indices ) => indices.push( &bone_indices.indices ),
+            None => return Err(Error::Other( format!("Skin \"{}\" has no indices of source \"{}\"", skin.id, semantic) )),
+        }
+    }
+
+    let vcount:Vec<usize>=skin.bones_count_per_vertex.iter().map(|bones| bones.bones_count).collect();
+    let bones_count=vcount.iter().sum::<usize>();
+    let mut v=Vec::with_capacity(bones_count*indices.len());
+
+    for i in 0..bones_count {
+        for source_indices in indices.iter() {
+            v.push( source_indices[i] );
+        }
+    }
+
+    vertex_weights.push( XmlNode::new("vcount").text(join(&vcount)) );
+    vertex_weights.push( XmlNode::new("v").text(join(&v)) );
+    skin_element.push(vertex_weights);
+
+    Ok( XmlNode::new("controller").attribute("id", &skin.id).attribute("name", &skin.name).child(skin_element) )
+}
+
+fn morph_node(morph:&Morph) -> XmlNode {
+    let method=match morph.method {
+        MorphMethod::Normalized => "NORMALIZED",
+        MorphMethod::Relative => "RELATIVE",
+    };
+
+    let targets_id=format!("{}-targets", morph.id);
+    let weights_id=format!("{}-weights", morph.id);
+
+    let morph_element=XmlNode::new("morph").attribute("source", &url(&morph.geometry_id)).attribute("method", method)
+        .child( name_source(&targets_id, "IDREF_array", "MORPH_TARGET", "IDREF", &morph.targets) )
+        .child( float_source(&weights_id, &[("MORPH_WEIGHT","float")], 1, &morph.weights) )
+        .child( XmlNode::new("targets")
+            .child( input("MORPH_TARGET", &targets_id) )
+            .child( input("MORPH_WEIGHT", &weights_id) )
+        );
+
+    XmlNode::new("controller").attribute("id", &morph.id).attribute("name", &morph.name).child(morph_element)
+}
+
+fn camera_node(camera:&Camera) -> XmlNode {
+    let mut perspective=XmlNode::new("perspective");
+
+    //schema allows xfov and yfov or one of them with aspect_ratio
+    let fovs=[("xfov", camera.perspective.x_fov), ("yfov", camera.perspective.y_fov)];
+
+    for &(name,fov) in fovs.iter() {
+        match fov {
+            Some( fov ) => perspective=perspective.child( XmlNode::new(name).text(fov.to_string()) ),
+            None => {},
+        }
+    }
+
+    match (camera.perspective.x_fov, camera.perspective.y_fov, camera.perspective.aspect_ratio) {
+        (Some(_), Some(_), _) | (_, _, None) => {},
+        (_, _, Some( aspect_ratio )) => perspective=perspective.child( XmlNode::new("aspect_ratio").text(aspect_ratio.to_string()) ),
+    }
+
+    let perspective=perspective
+        .child( XmlNode::new("znear").text(camera.perspective.z_near.to_string()) )
+        .child( XmlNode::new("zfar").text(camera.perspective.z_far.to_string()) );
+
+    XmlNode::new("camera").attribute("id", &camera.id).attribute("name", &camera.name)
+        .child( XmlNode::new("optics").child( XmlNode::new("technique_common").child(perspective) ) )
+}
+
+fn light_node(light:&Light) -> XmlNode {
+    let kind=match light.kind {
+        LightKind::Ambient => "ambient",
+        LightKind::Directional => "directional",
+        LightKind::Point => "point",
+        LightKind::Spot => "spot",
+    };
+
+    XmlNode::new("light").attribute("id", &light.id).attribute("name", &light.name)
+        .child( XmlNode::new("technique_common")
+            .child( XmlNode::new(kind).child( XmlNode::new("color").attribute("sid", "color").text(join(&light.color)) ) )
+        )
+}
+
+fn material_node(material:&Material) -> XmlNode {
+    XmlNode::new("material").attribute("id", &material.id).attribute("name", &material.name)
+        .child( XmlNode::new("instance_effect").attribute("url", &url(&material.effect_id)) )
+}
+
+fn effect_node(effect:&Effect) -> XmlNode {
+    let mut shading=XmlNode::new(effect.shading.print_shading());
+
+    let colors=[("emission", &effect.emission), ("ambient", &effect.ambient), ("diffuse", &effect.diffuse), ("specular", &effect.specular)];
+
+    for &(name,value) in colors.iter() {
+        match *value {
+            Some( ColorOrTexture::Color( ref color ) ) =>
+                shading.push( XmlNode::new(name).child( XmlNode::new("color").attribute("sid", name).text(join(color)) ) ),
+            Some( ColorOrTexture::Texture{ ref texture, ref texcoord } ) =>
+                shading.push( XmlNode::new(name).child( XmlNode::new("texture").attribute("texture", texture).attribute("texcoord", texcoord) ) ),
+            None => {},
+        }
+    }
+
+    match effect.shininess {
+        Some( shininess ) => shading.push( XmlNode::new("shininess").child( XmlNode::new("float").attribute("sid", "shininess").text(shininess.to_string()) ) ),
+        None => {},
+    }
+
+    //opacity is written as is, reader of this document applies no fixups
+    shading.push( XmlNode::new("transparency").child( XmlNode::new("float").attribute("sid", "transparency").text(effect.opacity.to_string()) ) );
+
+    XmlNode::new("effect").attribute("id", &effect.id).attribute("name", &effect.name)
+        .child( XmlNode::new("profile_COMMON")
+            .child( XmlNode::new("technique").attribute("sid", "common").child(shading) )
+        )
+}
+
+///Writes animation without samplers of baked nodes, `baked` channels are added to it
+fn animation_node(animation:&Animation, baked_nodes:&HashMap<String,Channel>, baked:&[(&String,&Channel)]) -> Result<XmlNode,Error> {
+    let mut element=XmlNode::new("animation");
+
+    match animation.id {
+        Some( ref id ) => element=element.attribute("id", id),
+        None => {},
+    }
+
+    match animation.name {
+        Some( ref name ) => element=element.attribute("name", name),
+        None => {},
+    }
+
+    let mut samplers=Vec::with_capacity(animation.samplers.len());
+    let mut channels=Vec::with_capacity(animation.samplers.len());
+
+    for sampler in animation.samplers.iter() {
+        if baked_nodes.contains_key(&sampler.address.id) {
+            continue;
+        }
+
+        //keep name of param of float values, like ANGLE
+        let param=match sampler.sources.get("OUTPUT") {
+            Some( source ) => get_param_name(&source.short_vertex_format),
+            None => "VALUE",
+        };
+
+        let (sources,sampler_element,channel_element)=channel_nodes(&sampler.id, &sampler.target, param, &sampler.decode()?);
+
+        element.children.extend(sources);
+        samplers.push(sampler_element);
+        channels.push(channel_element);
+    }
+
+    for &(node_id,channel) in baked.iter() {
+        let (sources,sampler_element,channel_element)=channel_nodes(&format!("{}-transform-sampler", node_id), &channel.target, "TRANSFORM", channel);
+
+        element.children.extend(sources);
+        samplers.push(sampler_element);
+        channels.push(channel_element);
+    }
+
+    element.children.extend(samplers);
+    element.children.extend(channels);
+
+    for child in animation.children.iter() {
+        element.push( animation_node(child, baked_nodes, &[])? );
+    }
+
+    Ok( element )
+}
+
+///Sources, sampler and channel of decoded channel, so they do not depend on axes of source document.
+///`param` is name of float values.
+fn channel_nodes(sampler_id:&str, target:&str, param:&str, channel:&Channel) -> (Vec<XmlNode>,XmlNode,XmlNode) {
+    let mut sources=Vec::with_capacity(5);
+
+    let input_id=format!("{}-input", sampler_id);
+    let output_id=format!("{}-output", sampler_id);
+    let interpolation_id=format!("{}-interpolation", sampler_id);
+
+    sources.push( float_source(&input_id, &[("TIME","float")], 1, &channel.times) );
+
+    let output=match channel.values {
+        ChannelValues::Float( ref values ) => float_source(&output_id, &[(param,"float")], 1, values),
+        ChannelValues::Vec3( ref values ) => {
+            let flat:Vec<f32>=values.iter().flat_map(|value| value.iter().cloned()).collect();
+            float_source(&output_id, &[("X","float"), ("Y","float"), ("Z","float")], 3, &flat)
+        },
+        ChannelValues::Location( ref values ) => {
+            let flat:Vec<f32>=values.iter().flat_map(|value| value.to_matrix().mat.to_vec()).collect();
+            float_source(&output_id, &[("TRANSFORM","float4x4")], 16, &flat)
+        },
+    };
+
+    sources.push(output);
+
+    let interpolations:Vec<String>=channel.interpolation.iter().map(|interpolation| String::from(interpolation.print_interpolation())).collect();
+    sources.push( name_source(&interpolation_id, "Name_array", "INTERPOLATION", "name", &interpolations) );
+
+    let mut sampler=XmlNode::new("sampler").attribute("id", sampler_id)
+        .child( input("INPUT", &input_id) )
+        .child( input("OUTPUT", &output_id) )
+        .child( input("INTERPOLATION", &interpolation_id) );
+
+    for &(semantic,suffix,tangents) in [("IN_TANGENT", "intangent", &channel.in_tangents), ("OUT_TANGENT", "outtangent", &channel.out_tangents)].iter() {
+        match tangent_source(&format!("{}-{}", sampler_id, suffix), tangents) {
+            Some( source ) => {
+                sampler.push( input(semantic, &format!("{}-{}", sampler_id, suffix)) );
+                sources.push(source);
+            },
+            None => {},
+        }
+    }
+
+    let channel_element=XmlNode::new("channel").attribute("source", &url(sampler_id)).attribute("target", target);
+
+    (sources, sampler, channel_element)
+}
+
+///Tangents of float channels are (time,value) pairs, tangents of vec3 channels have no times(like reader expects),
+///matrices have no tangents
+fn tangent_source(id:&str, tangents:&[Tangent]) -> Option<XmlNode> {
+    let mut values=Vec::with_capacity(tangents.len()*3);
+    let mut params:&[(&str,&str)]=&[];
+
+    for tangent in tangents.iter() {
+        match (tangent.time, &tangent.value) {
+            (Some( time ), &ChannelValue::Float( value )) => {
+                values.push(time);
+                values.push(value);
+                params=&[("X","float"), ("Y","float")];
+            },
+            (None, &ChannelValue::Float( value )) => {
+                values.push(value);
+                params=&[("X","float")];
+            },
+            (_, &ChannelValue::Vec3( vector )) => {
+                values.extend_from_slice(&vector);
+                params=&[("X","float"), ("Y","float"), ("Z","float")];
+            },
+            (_, &ChannelValue::Location(_)) => return None,
+        }
+    }
+
+    if values.len()==0 || values.len()!=tangents.len()*params.len() {
+        return None;
+    }
+
+    Some( float_source(id, params, params.len(), &values) )
+}
+
+///Nodes are written with one `<matrix sid="transform">`, so channels of parts of transformation of node
+///(`translate`, `rotateX.ANGLE` and so on) are baked into one matrix channel by id of node.
+///Curves are resampled with `CURVE_FPS`, keys are STEP only if all channels step there.
+fn bake_node_channels(document:&Document) -> Result<HashMap<String,Channel>,Error> {
+    let mut rest_locations:HashMap<&String,(&Location,&Vec<TransformElement>)>=HashMap::new();
+
+    for scene in document.scenes.values() {
+        for node in scene.geometries.values() {
+            rest_locations.insert(&node.id, (&node.location, &node.transforms));
+        }
+
+        for node in scene.cameras.values() {
+            rest_locations.insert(&node.id, (&node.location, &node.transforms));
+        }
+
+        for node in scene.lights.values() {
+            rest_locations.insert(&node.id, (&node.location, &node.transforms));
+        }
+
+        for node in scene.skeletons.values() {
+            rest_locations.insert(&node.id, (&node.location, &node.transforms));
+
+            for bone in node.joined.bones_array.iter() {
+                rest_locations.insert(&bone.id, (&bone.location, &bone.transforms));
+            }
+        }
+    }
+
+    let mut node_samplers:HashMap<&String,Vec<&Sampler>>=HashMap::new();
+
+    for animation in sorted(&document.animations) {
+        for sampler in animation.all_samplers() {
+            if rest_locations.contains_key(&sampler.address.id) {
+                node_samplers.entry(&sampler.address.id).or_insert_with(Vec::new).push(sampler);
+            }
+        }
+    }
+
+    let mut baked=HashMap::new();
+
+    for (node_id,mut samplers) in node_samplers.into_iter() {
+        let is_part=|sampler:&Sampler| sampler.address.sids.last().map(|sid| sid.as_str())!=Some("transform");
+
+        if !samplers.iter().any(|sampler| is_part(sampler)) {
+            continue;
+        }
+
+        //whole matrices go first, parts are applied to them
+        samplers.sort_by_key(|sampler| is_part(sampler));
+
+        let mut channels=Vec::with_capacity(samplers.len());
+
+        for sampler in samplers.iter() {
+            let channel=sampler.decode()?;
+            let has_curves=channel.interpolation.iter().any(|interpolation| *interpolation!=Interpolation::Step && *interpolation!=Interpolation::Linear);
+
+            channels.push( (&sampler.address, if has_curves { channel.resample(CURVE_FPS)? } else { channel }) );
+        }
+
+        let mut times:Vec<f32>=channels.iter().flat_map(|&(_,ref channel)| channel.times.iter().cloned()).collect();
+        times.sort_by(|a,b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        times.dedup_by(|a,b| (*a-*b).abs()<0.00001);
+
+        let mut channel=Channel{
+            target:format!("{}/transform", node_id),
+            times:Vec::with_capacity(times.len()),
+            values:ChannelValues::Location( Vec::with_capacity(times.len()) ),
+            interpolation:Vec::with_capacity(times.len()),
+            in_tangents:Vec::new(),
+            out_tangents:Vec::new(),
+        };
+
+        for time in times {
+            let (rest_location,transforms)=rest_locations[node_id];
+            let mut pose=Pose{ locations:vec![rest_location.clone()], rotations:vec![rotate_elements(transforms)] };
+            let mut is_step=true;
+
+            for &(address,ref part) in channels.iter() {
+                pose.apply_channel(0, address, part.sample(time, SampleMode::Clamp))?;
+
+                //channel is constant before first key and after last one
+                is_step=is_step && match part.times.iter().rposition(|key_time| *key_time<=time) {
+                    Some( key ) if key<part.times.len()-1 => part.interpolation[key]==Interpolation::Step,
+                    _ => true,
+                };
+            }
+
+            channel.times.push(time);
+            channel.values.push( ChannelValue::Location(pose.locations.swap_remove(0)) )?;
+            channel.interpolation.push( if is_step { Interpolation::Step } else { Interpolation::Linear } );
+        }
+
+        baked.insert(node_id.clone(), channel);
+    }
+
+    Ok( baked )
+}
+
+fn clip_node(clip:&AnimationClip) -> XmlNode {
+    let mut element=XmlNode::new("animation_clip")
+        .attribute("id", &clip.id)
+        .attribute("name", &clip.name)
+        .attribute("start", &clip.start.to_string())
+        .attribute("end", &clip.end.to_string());
+
+    for animation in clip.animations.iter() {
+        match animation.id {
+            Some( ref id ) => element.push( XmlNode::new("instance_animation").attribute("url", &url(id)) ),
+            None => {},
+        }
+    }
+
+    element
+}
+
+///Nodes of scene, that have been attached to bones, by id of bone
+type AttachedNodes=HashMap<String,Vec<XmlNode>>;
+
+fn scene_node(scene:&Scene) -> XmlNode {
+    let mut attached:AttachedNodes=HashMap::new();
+    let mut nodes=Vec::new();
+
+    {
+        let mut place=|bone:Option<&Arc<Bone>>, element:XmlNode| match bone {
+            Some( bone ) => attached.entry(bone.id.clone()).or_insert_with(Vec::new).push(element),
+            None => nodes.push(element),
+        };
+
+        for node in sorted(&scene.geometries) {
+            let instance=match node.controller {
+                Controller::Skin( ref skin, ref skeleton ) => {
+                    let mut instance=XmlNode::new("instance_controller").attribute("url", &url(&skin.id));
+
+                    for root in skeleton.roots() {
+                        instance.push( XmlNode::new("skeleton").text(url(&skeleton.bones_array[root].id)) );
+                    }
+
+                    instance
+                },
+                Controller::Morph( ref morph ) => XmlNode::new("instance_controller").attribute("url", &url(&morph.id)),
+                _ => XmlNode::new("instance_geometry").attribute("url", &url(&node.joined.id)),
+            };
+
+            place( get_bone(&node.controller), instance_node(node, instance) );
+        }
+
+        for node in sorted(&scene.cameras) {
+            let instance=XmlNode::new("instance_camera").attribute("url", &url(&node.joined.id));
+            place( get_bone(&node.controller), instance_node(node, instance) );
+        }
+
+        for node in sorted(&scene.lights) {
+            let instance=XmlNode::new("instance_light").attribute("url", &url(&node.joined.id));
+            place( get_bone(&node.controller), instance_node(node, instance) );
+        }
+    }
+
+    for node in sorted(&scene.skeletons) {
+        let mut element=XmlNode::new("node").attribute("id", &node.id).attribute("name", &node.name).attribute("type", "NODE")
+            .child( XmlNode::new("matrix").attribute("sid", "transform").text(matrix_text(&node.matrix)) );
+
+        for root in node.joined.roots() {
+            element.push( bone_node(&node.joined, root, &mut attached) );
+        }
+
+        nodes.push(element);
+    }
+
+    let mut element=XmlNode::new("visual_scene").attribute("id", &scene.id).attribute("name", &scene.name);
+    element.children=nodes;
+
+    element
+}
+
+fn get_bone(controller:&Controller) -> Option<&Arc<Bone>> {
+    match *controller {
+        Controller::Bone( ref bone ) => Some(bone),
+        _ => None,
+    }
+}
+
+fn instance_node<T>(node:&Node<T>, instance:XmlNode) -> XmlNode {
+    XmlNode::new("node").attribute("id", &node.id).attribute("name", &node.name).attribute("type", "NODE")
+        .child( XmlNode::new("matrix").attribute("sid", "transform").text(matrix_text(&node.matrix)) )
+        .child(instance)
+}
+
+fn bone_node(skeleton:&Skeleton, bone_index:usize, attached:&mut AttachedNodes) -> XmlNode {
+    let bone=&skeleton.bones_array[bone_index];
+
+    let mut element=XmlNode::new("node")
+        .attribute("id", &bone.id)
+        .attribute("name", &bone.name)
+        .attribute("sid", &bone.sid)
+        .attribute("type", "JOINT")
+        .child( XmlNode::new("matrix").attribute("sid", "transform").text(matrix_text(&bone.matrix)) );
+
+    for child in bone.children.iter() {
+        element.push( bone_node(skeleton, *child, attached) );
+    }
+
+    match attached.remove(&bone.id) {
+        Some( nodes ) => element.children.extend(nodes),
+        None => {},
+    }
+
+    element
+}
+
+impl Document{
+    ///Writes document as COLLADA 1.4.1. Data is written as it has been read:
+    ///Y is up axis, units are meters and fixups of editor have been applied already.
+    ///Nodes have one `<matrix>`, so channels of their `translate`, `rotateX` and so on become matrix channels.
+    pub fn write<W:Write>(&self, writer:&mut W, options:WriteOptions) -> Result<(),Error> {
+        let root=self.to_xml(&options)?;
+
+        let result=write!(writer, "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n")
+            .and_then(|_| root.write(writer, 0, options.indent));
+
+        match result {
+            Ok( _ ) => Ok(()),
+            Err( e ) => Err(Error::WriteError(e)),
+        }
+    }
+
+    fn to_xml(&self, options:&WriteOptions) -> Result<XmlNode,Error> {
+        let asset=XmlNode::new("asset")
+            .child( XmlNode::new("contributor").child( XmlNode::new("authoring_tool").text(String::from("collada-rs")) ) )
+            .child( XmlNode::new("created").text(self.asset.created.clone()) )
+            .child( XmlNode::new("modified").text(self.asset.modified.clone()) )
+            .child( XmlNode::new("unit").attribute("name", "meter").attribute("meter", "1") )
+            .child( XmlNode::new("up_axis").text(String::from("Y_UP")) );
+
+        let mut root=XmlNode::new("COLLADA")
+            .attribute("xmlns", "http://www.collada.org/2005/11/COLLADASchema")
+            .attribute("version", "1.4.1")
+            .child(asset);
+
+        if self.animations.len()>0 {
+            let baked_nodes=bake_node_channels(self)?;
+            let mut written_nodes=Vec::new();
+            let mut library=XmlNode::new("library_animations");
+
+            //baked channel of node is written to first animation, that animates node
+            for animation in sorted(&self.animations) {
+                let mut baked=Vec::new();
+
+                for sampler in animation.all_samplers() {
+                    match baked_nodes.get_key_value(&sampler.address.id) {
+                        Some( (node_id,channel) ) if !written_nodes.contains(node_id) => {
+                            written_nodes.push(node_id.clone());
+                            baked.push( (node_id,channel) );
+                        },
+                        _ => {},
+                    }
+                }
+
+                library.push( animation_node(animation, &baked_nodes, &baked)? );
+            }
+
+            root.push(library);
+        }
+
+        if self.clips.len()>0 {
+            let mut library=XmlNode::new("library_animation_clips");
+            library.children=sorted(&self.clips).into_iter().map(|clip| clip_node(clip)).collect();
+            root.push(library);
+        }
+
+        //library_cameras is required by reader
+        let mut cameras=XmlNode::new("library_cameras");
+        cameras.children=sorted(&self.cameras).into_iter().map(|camera| camera_node(camera)).collect();
+        root.push(cameras);
+
+        if self.lights.len()>0 {
+            let mut library=XmlNode::new("library_lights");
+            library.children=sorted(&self.lights).into_iter().map(|light| light_node(light)).collect();
+            root.push(library);
+        }
+
+        if self.effects.len()>0 {
+            let mut library=XmlNode::new("library_effects");
+            library.children=sorted(&self.effects).into_iter().map(|effect| effect_node(effect)).collect();
+            root.push(library);
+        }
+
+        if self.materials.len()>0 {
+            let mut library=XmlNode::new("library_materials");
+            library.children=sorted(&self.materials).into_iter().map(|material| material_node(material)).collect();
+            root.push(library);
+        }
+
+        if self.geometries.len()>0 {
+            let mut library=XmlNode::new("library_geometries");
+
+            for geometry in sorted(&self.geometries) {
+                library.push( geometry_node(geometry, options)? );
+            }
+
+            root.push(library);
+        }
+
+        if self.skins.len()>0 || self.morphs.len()>0 {
+            let mut library=XmlNode::new("library_controllers");
+
+            for morph in sorted(&self.morphs) {
+                library.push( morph_node(morph) );
+            }
+
+            for skin in sorted(&self.skins) {
+                library.push( skin_node(skin)? );
+            }
+
+            root.push(library);
+        }
+
+        let mut scenes=XmlNode::new("library_visual_scenes");
+        scenes.children=sorted(&self.scenes).into_iter().map(|scene| scene_node(scene)).collect();
+        root.push(scenes);
+
+        match self.scenes.keys().min() {
+            Some( scene_id ) => root.push( XmlNode::new("scene").child( XmlNode::new("instance_visual_scene").attribute("url", &url(scene_id)) ) ),
+            None => {},
+        }
+
+        Ok( root )
+    }
+}
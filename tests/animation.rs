@@ -0,0 +1,211 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::{Channel,ChannelValue,ChannelValues,Interpolation,SampleMode,Tangent};
+
+#[test]
+fn decode_channels(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+
+    for (_,animation) in document.animations.iter() {
+        let channels=animation.channels().unwrap();
+        assert_eq!(channels.len(), 1);
+
+        let sampler=&animation.samplers[0];
+        let channel=&channels[0];
+        assert_eq!(channel.target, sampler.target);
+        assert_eq!(channel.times.len(), sampler.keyframes_count);
+        assert!(channel.interpolation.iter().all(|interpolation| *interpolation==Interpolation::Linear));
+
+        for pair in channel.times.windows(2) {
+            assert!(pair[0]<pair[1]);
+        }
+
+        match channel.values {
+            ChannelValues::Location(ref locations) => assert_eq!(locations.len(), sampler.keyframes_count),
+            _ => panic!("transform animation must be decoded into locations"),
+        }
+    }
+}
+
+#[test]
+fn nested_animations(){
+    let document=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+    let animation=document.animations.get("Hips-anim").unwrap();
+    assert_eq!(animation.name, Some(String::from("Hips")));
+    assert_eq!(animation.samplers.len(), 0);
+    assert_eq!(animation.children.len(), 2);
+    assert_eq!(animation.children[0].id, Some(String::from("Hips-translate")));
+    assert_eq!(animation.children[1].id, None);
+    assert_eq!(animation.children[1].samplers.len(), 2);
+
+    let samplers=animation.all_samplers();
+    let targets:Vec<&str>=samplers.iter().map(|sampler| sampler.target.as_str()).collect();
+    assert_eq!(targets, vec!["Hips/translate", "Hips/rotateY.ANGLE", "Spine/rotateX.ANGLE"]);
+    assert_eq!(samplers[2].bone_id, "Spine");
+    assert!(samplers.iter().all(|sampler| sampler.skeleton_id==Some(String::from("Rig"))));
+
+    let channels=animation.channels().unwrap();
+    assert_eq!(channels[0].interpolation, vec![Interpolation::Linear, Interpolation::Step, Interpolation::Linear]);
+
+    //tangents are (time,value) pairs, flat at keys, so curve eases in and out
+    let rotate=&channels[1];
+    assert_eq!(rotate.in_tangents[1].time, Some(1.333333));
+    assert!((sample_float(rotate, 1.0, SampleMode::Clamp)-45.0).abs()<0.01);
+    let eased=sample_float(rotate, 0.5, SampleMode::Clamp);
+    assert!(eased>0.0 && eased<22.5);
+    assert_eq!(channels[2].interpolation, vec![Interpolation::Linear;2]);
+}
+
+fn float_channel(interpolation:Interpolation, in_tangents:Vec<Tangent>, out_tangents:Vec<Tangent>) -> Channel {
+    Channel{
+        target:String::from("Node/weight"),
+        times:vec![0.0, 1.0, 3.0],
+        values:ChannelValues::Float(vec![0.0, 3.0, 1.0]),
+        interpolation:vec![interpolation;3],
+        in_tangents:in_tangents,
+        out_tangents:out_tangents,
+    }
+}
+
+fn sample_float(channel:&Channel, time:f32, mode:SampleMode) -> f32 {
+    match channel.sample(time, mode) {
+        ChannelValue::Float(value) => value,
+        _ => panic!("float channel must give float value"),
+    }
+}
+
+#[test]
+fn sample_float_channel(){
+    let linear=float_channel(Interpolation::Linear, Vec::new(), Vec::new());
+    assert_eq!(sample_float(&linear, 0.5, SampleMode::Clamp), 1.5);
+    assert_eq!(sample_float(&linear, 2.0, SampleMode::Clamp), 2.0);
+    assert_eq!(sample_float(&linear, -1.0, SampleMode::Clamp), 0.0);
+    assert_eq!(sample_float(&linear, 5.0, SampleMode::Clamp), 1.0);
+    assert_eq!(sample_float(&linear, 3.5, SampleMode::Loop), 1.5);
+
+    let step=float_channel(Interpolation::Step, Vec::new(), Vec::new());
+    assert_eq!(sample_float(&step, 0.9, SampleMode::Clamp), 0.0);
+
+    //control points on thirds of segments give straight lines
+    let tangent=|time:f32, value:f32| Tangent{time:Some(time), value:ChannelValue::Float(value)};
+    let bezier=float_channel(
+        Interpolation::Bezier,
+        vec![tangent(-1.0/3.0, -1.0), tangent(2.0/3.0, 2.0), tangent(7.0/3.0, 5.0/3.0)],
+        vec![tangent(1.0/3.0, 1.0), tangent(5.0/3.0, 7.0/3.0), tangent(11.0/3.0, 1.0/3.0)],
+    );
+    assert!((sample_float(&bezier, 0.5, SampleMode::Clamp)-1.5).abs()<0.001);
+    assert!((sample_float(&bezier, 2.0, SampleMode::Clamp)-2.0).abs()<0.001);
+
+    //hermite with zero tangents is flat at keys
+    let flat=|_| Tangent{time:None, value:ChannelValue::Float(0.0)};
+    let hermite=float_channel(Interpolation::Hermite, (0..3).map(&flat).collect(), (0..3).map(&flat).collect());
+    assert!((sample_float(&hermite, 0.5, SampleMode::Clamp)-1.5).abs()<0.001);
+    assert!(sample_float(&hermite, 0.1, SampleMode::Clamp)<0.3);
+}
+
+#[test]
+fn pose_at_time(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let skeleton=document.skeletons.get("Guy").unwrap();
+    let animations:Vec<_>=document.animations.values().cloned().collect();
+
+    let channels=animations[0].channels().unwrap();
+    let channel=&channels[0];
+    let bone=skeleton.get_bone_by_id(&animations[0].samplers[0].bone_id).unwrap();
+
+    let first_key=match channel.values {
+        ChannelValues::Location(ref locations) => locations[0].clone(),
+        _ => panic!("transform animation must be decoded into locations"),
+    };
+
+    let pose=skeleton.pose_at(&animations, channel.start_time()-1.0).unwrap();
+    let location=&pose.locations[bone.index];
+    assert_eq!(location.position.x, first_key.position.x);
+    assert_eq!(location.rotation.w, first_key.rotation.w);
+
+    let middle=(channel.times[0]+channel.times[1])*0.5;
+    let pose=skeleton.pose_at(&animations, middle).unwrap();
+    assert!((pose.locations[bone.index].rotation.magnitude()-1.0).abs()<0.001);
+    assert_eq!(pose.world_matrices(skeleton).unwrap().len(), skeleton.bones_array.len());
+
+    let document=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+    let skeleton=document.skeletons.get("Rig").unwrap();
+    let animations:Vec<_>=document.animations.values().cloned().collect();
+
+    let hips=skeleton.get_bone_by_id("Hips").unwrap();
+    let pose=skeleton.pose_at(&animations, 0.5).unwrap();
+    assert_eq!(pose.locations[hips.index].position.y, 1.5);
+    let pose=skeleton.pose_at(&animations, 1.5).unwrap();
+    assert_eq!(pose.locations[hips.index].position.y, 2.0);
+    assert_eq!(pose.locations[hips.index].position.x, 0.0);
+}
+
+#[test]
+fn pose_with_rotation_channels(){
+    let document=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+    let skeleton=document.skeletons.get("Rig").unwrap();
+    let animations:Vec<_>=document.animations.values().cloned().collect();
+
+    let hips=skeleton.get_bone_by_id("Hips").unwrap();
+    let spine=skeleton.get_bone_by_id("Spine").unwrap();
+
+    let pose=skeleton.pose_at(&animations, 0.0).unwrap();
+    assert!((pose.locations[hips.index].rotation.w-1.0).abs()<0.0001);
+
+    //Hips/rotateY.ANGLE goes from 0 to 90 degrees, Spine/rotateX.ANGLE from 0 to 45
+    let pose=skeleton.pose_at(&animations, 2.0).unwrap();
+    let rotation=&pose.locations[hips.index].rotation;
+    assert!(rotation.x.abs()<0.0001 && rotation.z.abs()<0.0001);
+    assert!((rotation.y-0.7071).abs()<0.0001 && (rotation.w-0.7071).abs()<0.0001);
+
+    let angles=pose.locations[spine.index].rotation.euler_angles();
+    assert!((angles[0]-45.0).abs()<0.01 && angles[1].abs()<0.01 && angles[2].abs()<0.01);
+
+    //rotation does not change position
+    assert_eq!(pose.locations[hips.index].position.y, 2.0);
+
+    let pose=skeleton.pose_at(&animations, 1.0).unwrap();
+    let angle_y=pose.locations[hips.index].rotation.euler_angles()[1];
+    assert!(angle_y>0.0 && angle_y<90.0);
+
+    let mut pose=collada::Pose::rest(skeleton);
+    let address=collada::TargetAddress::parse("Hips/translate.Y").unwrap();
+    pose.apply_channel(hips.index, &address, ChannelValue::Float(3.0)).unwrap();
+    assert_eq!(pose.locations[hips.index].position.y, 3.0);
+
+    let address=collada::TargetAddress::parse("Hips/visibility").unwrap();
+    assert!(pose.apply_channel(hips.index, &address, ChannelValue::Float(1.0)).is_err());
+
+    //angles of <rotate> elements are kept, so angle about X is not lost, when Y is 90 degrees
+    let angle=|sid:&str| collada::TargetAddress::parse(&format!("Hips/{}.ANGLE", sid)).unwrap();
+    pose.apply_channel(hips.index, &angle("rotateY"), ChannelValue::Float(90.0)).unwrap();
+    pose.apply_channel(hips.index, &angle("rotateX"), ChannelValue::Float(30.0)).unwrap();
+    pose.apply_channel(hips.index, &angle("rotateX"), ChannelValue::Float(45.0)).unwrap();
+
+    let expected=collada::Quaternion::with_euler_angles([45.0, 90.0, 0.0]);
+    assert!(pose.locations[hips.index].rotation.dot(&expected).abs()>0.9999);
+    assert!(pose.apply_channel(hips.index, &angle("rotateW"), ChannelValue::Float(45.0)).is_err());
+}
+
+#[test]
+fn animation_clips(){
+    let document=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+    assert_eq!(document.clips.len(), 2);
+
+    let walk=document.clips.get("walk").unwrap();
+    assert_eq!(walk.name, "Walk");
+    assert_eq!((walk.start, walk.end), (0.0, 1.0));
+    assert_eq!(walk.animations.len(), 1);
+    assert_eq!(walk.animations[0].id, Some(String::from("Hips-anim")));
+    assert_eq!(walk.animations[0].samplers.len(), 0);
+    assert_eq!(walk.animations[0].all_samplers()[0].skeleton_id, Some(String::from("Rig")));
+
+    let idle=document.clips.get("idle").unwrap();
+    assert_eq!(idle.name, "idle");
+    assert_eq!((idle.start, idle.end), (1.0, 2.0));
+
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    assert_eq!(document.clips.len(), 0);
+}
@@ -0,0 +1,78 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::{ChannelValues,Editor,Fixups,LoadOptions};
+
+#[test]
+fn detect_editors(){
+    assert_eq!(Editor::detect("Blender 2.78.0 commit date:2016-10-24"), Editor::Blender);
+    assert_eq!(Editor::detect("OpenCOLLADA for 3ds Max;  Version: 1.6;  Revision: 26"), Editor::Max);
+    assert_eq!(Editor::detect("OpenCOLLADA for Autodesk Maya;  Version: 1.6"), Editor::Maya);
+    assert_eq!(Editor::detect("FBX COLLADA exporter"), Editor::FbxCollada);
+    assert_eq!(Editor::detect("Google SketchUp 8.0.4811"), Editor::SketchUp);
+    assert_eq!(Editor::detect("CINEMA4D 17.055 COLLADA Exporter"), Editor::Cinema4D);
+    assert_eq!(Editor::detect("Assimp Collada Exporter"), Editor::Assimp);
+    assert_eq!(Editor::detect("My tool"), Editor::Unknown);
+
+    assert!(Fixups::for_editor(Editor::SketchUp).flip_x);
+    assert!(Fixups::for_editor(Editor::Max).invert_transparency);
+}
+
+#[test]
+fn override_fixups(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    assert_eq!(document.asset.editor, Editor::Blender);
+    assert!(document.asset.fixups.flip_x);
+
+    let options=LoadOptions{
+        editor:None,
+        fixups:Some(Fixups::none()),
+    };
+    let raw_document=collada::Document::parse_with_options(&Path::new("scene.dae"), &options).unwrap();
+    assert_eq!(raw_document.asset.editor, Editor::Blender);
+    assert!(!raw_document.asset.fixups.flip_x);
+
+    let flipped=&document.skeletons.get("Guy").unwrap().bones.get("Torse").unwrap().location.position;
+    let raw=&raw_document.skeletons.get("Guy").unwrap().bones.get("Torse").unwrap().location.position;
+    assert_eq!(flipped.x, -raw.x);
+    assert_eq!(flipped.y, raw.y);
+}
+
+#[test]
+fn scale_to_meters(){
+    //unit is applied without fixups too
+    let options=LoadOptions{
+        editor:None,
+        fixups:Some(Fixups::none()),
+    };
+
+    let document=collada::Document::parse_with_options(&Path::new("tests/data/centimeters.dae"), &options).unwrap();
+    assert_eq!(document.asset.unit.ratio, 0.01);
+
+    let close=|a:f32, b:f32| (a-b).abs()<0.00001;
+
+    let camera=document.cameras.get("Camera-camera").unwrap();
+    assert!(close(camera.perspective.z_near, 0.1));
+    assert!(close(camera.perspective.z_far, 100.0));
+
+    let skeleton=document.skeletons.get("Rig").unwrap();
+    assert!(close(skeleton.bones.get("Hand").unwrap().location.position.y, 1.0));
+
+    let skin=document.skins.get("Sleeve-skin").unwrap();
+    assert!(close(skin.bind_shape_matrix.mat[7], 0.5));
+    assert!(close(skin.inverse_bind_matrices[0].mat[7], -1.0));
+    assert!(close(skin.inverse_bind_matrices[1].mat[7], -2.0));
+
+    let channels=document.animations.get("Arm-anim").unwrap().channels().unwrap();
+
+    match channels.iter().find(|channel| channel.target=="Arm/translate").unwrap().values {
+        ChannelValues::Vec3( ref values ) => assert_eq!(values[1], [0.0, 2.0, 0.0]),
+        _ => panic!("Translation should be vec3"),
+    }
+
+    match channels.iter().find(|channel| channel.target=="Hand/translate.X").unwrap().values {
+        ChannelValues::Float( ref values ) => assert_eq!(values[0], 0.5),
+        _ => panic!("Member of translation should be float"),
+    }
+}
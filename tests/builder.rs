@@ -0,0 +1,124 @@
+extern crate collada;
+
+use std::path::PathBuf;
+use std::fs::File;
+
+use collada::{Document,GeometryBuilder,Location,MeshBuilder,Position,Quaternion,Scale,SceneBuilder,SkeletonBuilder,SourceLayer,WriteOptions};
+
+fn offset(x:f32, y:f32, z:f32) -> Location {
+    Location::new(Position::new(x, y, z), Scale::new(1.0, 1.0, 1.0), Quaternion::identity())
+}
+
+fn build_document() -> Document {
+    let mut document=Document::new();
+
+    let quad=MeshBuilder::new()
+        .material("Floor")
+        .positions(&[[0.0,0.0,0.0], [1.0,0.0,0.0], [1.0,0.0,1.0], [0.0,0.0,1.0]])
+        .normals(&[[0.0,1.0,0.0]; 4])
+        .polygon(&[0,1,2,3]);
+
+    let triangle=MeshBuilder::new()
+        .material("Wall")
+        .positions(&[[0.0,0.0,0.0], [1.0,1.0,0.0], [0.0,1.0,0.0]])
+        .normals(&[[0.0,0.0,1.0]; 3])
+        .triangles(&[0,1,2]);
+
+    let geometry=document.add_geometry( GeometryBuilder::new("Room-mesh", "Room").mesh(quad).mesh(triangle) ).unwrap();
+
+    //children are added before parents
+    let skeleton=document.add_skeleton( SkeletonBuilder::new("Rig")
+        .bone("Rig_Spine", "Spine", Some("Rig_Hips"), offset(0.0, 0.5, 0.0))
+        .bone("Rig_Hips", "Hips", None, offset(0.0, 1.0, 0.0))
+        .bone("Rig_Leg", "Leg", Some("Rig_Hips"), offset(0.2, -0.5, 0.0))
+    ).unwrap();
+
+    document.add_scene( SceneBuilder::new("Scene", "Scene")
+        .geometry("Room", "Room", Location::identity(), &geometry)
+        .skeleton("Rig", &skeleton)
+    ).unwrap();
+
+    document
+}
+
+#[test]
+fn build_geometry_and_skeleton(){
+    let document=build_document();
+
+    let geometry=document.geometries.get("Room-mesh").unwrap();
+    assert_eq!(geometry.meshes.len(), 2);
+    assert_eq!(geometry.meshes[0].id, 0);
+    assert_eq!(geometry.meshes[1].id, 1);
+    assert_eq!(geometry.meshes[1].name, "Room#1");
+    assert_eq!(geometry.meshes[0].short_vertex_format, "&(X,Y,Z) &(X,Y,Z)");
+
+    let triangle=&geometry.meshes[1];
+    assert_eq!(triangle.polygons.len(), 1);
+    assert_eq!(triangle.polygons[0].vertices_count, 3);
+    //positions of meshes are joined
+    assert_eq!(triangle.vertex_indices.get("VERTEX").unwrap().indices, vec![4,5,6]);
+
+    match triangle.sources[0].1.layers.get("Y").unwrap() {
+        &SourceLayer::F32( ref list ) => assert_eq!(list.len(), 7),
+        _ => panic!("Positions should be floats"),
+    }
+
+    let skeleton=document.skeletons.get("Rig").unwrap();
+    let names:Vec<&str>=skeleton.bones_array.iter().map(|bone| bone.name.as_str()).collect();
+    assert_eq!(names, vec!["Hips", "Spine", "Leg"]);
+    assert_eq!(skeleton.bones_array[0].children, vec![1,2]);
+    assert_eq!(skeleton.bones_array[2].parent, Some(0));
+}
+
+#[test]
+fn write_built_document(){
+    let document=build_document();
+
+    let mut path=PathBuf::from(std::env::temp_dir());
+    path.push("collada_builder.dae");
+
+    {
+        let mut file=File::create(&path).unwrap();
+        document.write(&mut file, WriteOptions::default()).unwrap();
+    }
+
+    let written=Document::parse(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let geometry=written.geometries.get("Room-mesh").unwrap();
+    assert_eq!(geometry.meshes.len(), 2);
+    assert_eq!(geometry.meshes[0].polygons[0].vertices_count, 4);
+    assert_eq!(geometry.meshes[1].material, Some(String::from("Wall")));
+
+    let skeleton=written.skeletons.get("Rig").unwrap();
+    assert_eq!(skeleton.bones_array.len(), 3);
+    assert_eq!(skeleton.get_bone_by_name("Leg").unwrap().parent, Some(0));
+
+    let scene=written.scenes.get("Scene").unwrap();
+    assert!(scene.geometries.contains_key("Room"));
+}
+
+#[test]
+fn reject_invalid_pieces(){
+    let mut document=Document::new();
+
+    let out_of_range=MeshBuilder::new().positions(&[[0.0;3]; 3]).polygon(&[0,1,3]);
+    assert!(document.add_geometry( GeometryBuilder::new("Bad", "Bad").mesh(out_of_range) ).is_err());
+
+    let no_normals=MeshBuilder::new().positions(&[[0.0;3]; 3]).normals(&[[0.0;3]; 2]).polygon(&[0,1,2]);
+    assert!(document.add_geometry( GeometryBuilder::new("Bad", "Bad").mesh(no_normals) ).is_err());
+
+    let orphan=SkeletonBuilder::new("Rig")
+        .bone("Hips", "Hips", None, Location::identity())
+        .bone("Hand", "Hand", Some("Arm"), Location::identity());
+    assert!(document.add_skeleton(orphan).is_err());
+
+    let cycle=SkeletonBuilder::new("Rig")
+        .bone("Hips", "Hips", None, Location::identity())
+        .bone("A", "A", Some("B"), Location::identity())
+        .bone("B", "B", Some("A"), Location::identity());
+    assert!(document.add_skeleton(cycle).is_err());
+
+    assert_eq!(document.geometries.len(), 0);
+    assert_eq!(document.skeletons.len(), 0);
+}
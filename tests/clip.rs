@@ -0,0 +1,128 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::{ChannelValue,ChannelValues,ClipRange,Interpolation,ManifestFormat,SampleMode};
+
+fn range(name:&str, start:f32, end:f32) -> ClipRange {
+    ClipRange{
+        name:String::from(name),
+        start:start,
+        end:end,
+    }
+}
+
+#[test]
+fn split_timeline(){
+    let document=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+    let clips=document.split_animations(&[range("walk", 0.0, 1.0), range("middle", 0.5, 1.5)]).unwrap();
+    assert_eq!(clips.len(), 2);
+    assert_eq!(clips[1].name, "middle");
+    assert_eq!(clips[1].duration, 1.0);
+
+    let walk=&clips[0].channels[0];
+    assert_eq!(walk.target, "Hips/translate");
+    assert_eq!(walk.times, vec![0.0, 1.0]);
+
+    //boundary keys are sampled, inner key keeps its STEP interpolation
+    let middle=&clips[1].channels[0];
+    assert_eq!(middle.times, vec![0.0, 0.5, 1.0]);
+    assert_eq!(middle.interpolation, vec![Interpolation::Linear, Interpolation::Step, Interpolation::Linear]);
+    match middle.values {
+        ChannelValues::Vec3(ref values) => assert_eq!(values, &vec![[0.0,1.5,0.0], [0.0,2.0,0.0], [0.0,2.0,0.0]]),
+        _ => panic!("translate must be decoded into vec3"),
+    }
+
+    //cut bezier segment is replaced by line between sampled values
+    let original=&document.animations.get("Hips-anim").unwrap().channels().unwrap()[1];
+    let rotate=&clips[1].channels[1];
+    assert_eq!(rotate.times, vec![0.0, 1.0]);
+    assert_eq!(rotate.interpolation, vec![Interpolation::Linear;2]);
+    assert_eq!(rotate.in_tangents.len(), 2);
+    match (rotate.sample(1.0, SampleMode::Clamp), original.sample(1.5, SampleMode::Clamp)) {
+        (ChannelValue::Float(cut), ChannelValue::Float(value)) => assert_eq!(cut, value),
+        _ => panic!("rotation angle must be float"),
+    }
+
+    let idle=document.clips.get("idle").unwrap().decode().unwrap();
+    assert_eq!(idle.duration, 1.0);
+    assert_eq!(idle.channels.len(), 3);
+    assert_eq!(idle.channels[0].times, vec![0.0, 1.0]);
+
+    assert!(document.split_animations(&[range("bad", 1.0, 0.0)]).is_err());
+}
+
+#[test]
+fn read_manifests(){
+    let json=r#"{"fps": 10, "clips": [{"name": "walk", "start": 0, "end": 10}, {"name": "run \"fast\"", "start": 10, "end": 25}]}"#;
+    let ranges=ClipRange::parse_manifest(json, ManifestFormat::Json).unwrap();
+    assert_eq!(ranges, vec![range("walk", 0.0, 1.0), range("run \"fast\"", 1.0, 2.5)]);
+
+    let json_array=r#"[ {"name":"idle", "start":0.5, "end":1.5, "loop":true} ]"#;
+    assert_eq!(ClipRange::parse_manifest(json_array, ManifestFormat::Json).unwrap(), vec![range("idle", 0.5, 1.5)]);
+
+    //surrogate pair of escaped character
+    let json_escape=r#"[{"name": "\ud83c\udfc3 run", "start": 0, "end": 1}]"#;
+    assert_eq!(ClipRange::parse_manifest(json_escape, ManifestFormat::Json).unwrap(), vec![range("\u{1f3c3} run", 0.0, 1.0)]);
+
+    let toml="fps = 10 # frames per second\n\n[[clips]]\nname = \"walk\"\nstart = 0\nend = 10\n\n[[ clips ]]\nname = \"run\"\nstart = 10\nend = 25\n";
+    assert_eq!(ClipRange::parse_manifest(toml, ManifestFormat::Toml).unwrap(), vec![range("walk", 0.0, 1.0), range("run", 1.0, 2.5)]);
+
+    let toml_tables="[idle]\nstart = 0.5\nend = 1.5\n\n[\"run fast\"]\nstart = 2\nend = 3\n";
+    assert_eq!(ClipRange::parse_manifest(toml_tables, ManifestFormat::Toml).unwrap(), vec![range("idle", 0.5, 1.5), range("run fast", 2.0, 3.0)]);
+
+    assert!(ClipRange::parse_manifest("{\"clips\": [{\"name\": \"walk\", \"start\": 0}]}", ManifestFormat::Json).is_err());
+    assert!(ClipRange::parse_manifest("[[clips]]\nname = \"walk\"\nstart = 2\nend = 1\n", ManifestFormat::Toml).is_err());
+    assert!(ClipRange::parse_manifest("{\"clips\": [", ManifestFormat::Json).is_err());
+
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let ranges=ClipRange::parse_manifest("[[clips]]\nname = \"all\"\nstart = 0\nend = 1\n", ManifestFormat::Toml).unwrap();
+    let clips=document.split_animations(&ranges).unwrap();
+    assert_eq!(clips[0].channels.len(), 8);
+    assert!(clips[0].channels.iter().all(|channel| channel.times[0]==0.0 && *channel.times.last().unwrap()==1.0));
+}
+
+#[test]
+fn reject_malformed_manifests(){
+    let malformed_json=[
+        r#"[{"name": "walk" "start": 0, "end": 1}]"#,
+        r#"[{"name": "walk", "start": 0, "end": 1},]"#,
+        r#"[{"name": "walk", "start": 0, "end": 1}] []"#,
+        r#"[{"name": "walk", "start": 0x10, "end": 1}]"#,
+        r#"[{"name": "\ud83c", "start": 0, "end": 1}]"#,
+        r#"{"fps": "10", "clips": []}"#,
+        r#"{"fps": 10, "clips": {}}"#,
+        r#"[{"name": 1, "start": 0, "end": 1}]"#,
+    ];
+
+    for text in malformed_json.iter() {
+        assert!(ClipRange::parse_manifest(text, ManifestFormat::Json).is_err(), "{}", text);
+    }
+
+    let malformed_toml=[
+        "[[clips]\nname = \"walk\"\nstart = 0\nend = 1\n",
+        "[[clips]]\nname = walk\nstart = 0\nend = 1\n",
+        "[[clips]]\nname = \"walk\"\nstart = 0\nstart = 1\nend = 1\n",
+        "fps = 0\n[walk]\nstart = 0\nend = 1\n",
+        "speed = 2\n[walk]\nstart = 0\nend = 1\n",
+    ];
+
+    for text in malformed_toml.iter() {
+        assert!(ClipRange::parse_manifest(text, ManifestFormat::Toml).is_err(), "{}", text);
+    }
+
+    //TOML is not read as JSON
+    assert!(ClipRange::parse_manifest("[walk]\nstart = 0\nend = 1\n", ManifestFormat::Json).is_err());
+}
+
+#[test]
+fn read_manifest_files(){
+    let json=ClipRange::read_manifest(&Path::new("tests/data/clips.json")).unwrap();
+    let toml=ClipRange::read_manifest(&Path::new("tests/data/clips.toml")).unwrap();
+
+    assert_eq!(json, vec![range("walk", 0.0, 1.0), range("run", 1.0, 2.5)]);
+    assert_eq!(toml, json);
+
+    assert!(ClipRange::read_manifest(&Path::new("tests/data/rig.dae")).is_err());
+    assert!(ClipRange::read_manifest(&Path::new("tests/data/missing.json")).is_err());
+}
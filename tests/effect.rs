@@ -0,0 +1,55 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::{ColorOrTexture,Fixups,LoadOptions,Shading};
+
+#[test]
+fn invert_transparency(){
+    let document=collada::Document::parse(&Path::new("tests/data/effects.dae")).unwrap();
+
+    let effect=document.effects.get("Skin-effect").unwrap();
+    assert_eq!(effect.shading, Shading::Phong);
+    assert_eq!(effect.shininess, Some(50.0));
+    assert_eq!(effect.opacity, 0.25);
+    assert_eq!(effect.diffuse, Some(ColorOrTexture::Color([0.8, 0.6, 0.5, 1.0])));
+    assert_eq!(effect.base_color(), [0.8, 0.6, 0.5, 0.25]);
+
+    let options=LoadOptions{
+        editor:None,
+        fixups:Some(Fixups{ invert_transparency:true, ..Fixups::none() }),
+    };
+
+    let document=collada::Document::parse_with_options(&Path::new("tests/data/effects.dae"), &options).unwrap();
+    assert_eq!(document.effects.get("Skin-effect").unwrap().opacity, 0.75);
+}
+
+#[test]
+fn skip_unsupported_effects(){
+    let document=collada::Document::parse(&Path::new("tests/data/effects.dae")).unwrap();
+
+    //GLSL effect and effect without shading are skipped
+    assert_eq!(document.effects.len(), 4);
+    assert!(document.warnings.iter().any(|warning| warning.contains("Shader-effect") && warning.contains("profile_COMMON")));
+    assert!(document.warnings.iter().any(|warning| warning.contains("Empty-effect") && warning.contains("shading")));
+
+    //values, that can not be read, get defaults
+    let effect=document.effects.get("Param-effect").unwrap();
+    assert_eq!(effect.shininess, None);
+    assert_eq!(effect.opacity, 1.0);
+    assert!(effect.specular.is_none());
+
+    match effect.diffuse {
+        Some( ColorOrTexture::Color( color ) ) => assert_eq!(color, [0.8, 0.6, 0.5, 1.0]),
+        _ => panic!("Diffuse color is expected"),
+    }
+
+    assert!(document.warnings.iter().any(|warning| warning.contains("Param-effect") && warning.contains("specular")));
+
+    //opacity is combined from transparent color and transparency
+    let glass=document.effects.get("Glass-effect").unwrap();
+    assert!((glass.opacity-0.5).abs()<0.0001, "{}", glass.opacity);
+
+    let fade=document.effects.get("Fade-effect").unwrap();
+    assert!((fade.opacity-0.2).abs()<0.0001, "{}", fade.opacity);
+}
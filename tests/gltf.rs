@@ -0,0 +1,491 @@
+extern crate collada;
+extern crate serde_json;
+
+use std::path::{Path,PathBuf};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use collada::{ChannelValue,Document,SampleMode};
+
+///Shortcuts for exported json, they panic, if value is not expected one
+trait JsonExt{
+    fn at(&self, name:&str) -> &Value;
+    fn array(&self) -> &Vec<Value>;
+    fn number(&self) -> f64;
+    fn index(&self) -> usize;
+    fn string(&self) -> &str;
+    ///Length of array member, that may be absent
+    fn len(&self, name:&str) -> usize;
+}
+
+impl JsonExt for Value{
+    fn at(&self, name:&str) -> &Value {
+        self.get(name).expect(name)
+    }
+
+    fn array(&self) -> &Vec<Value> {
+        self.as_array().expect("array")
+    }
+
+    fn number(&self) -> f64 {
+        self.as_f64().expect("number")
+    }
+
+    fn index(&self) -> usize {
+        self.as_u64().expect("index") as usize
+    }
+
+    fn string(&self) -> &str {
+        self.as_str().expect("string")
+    }
+
+    fn len(&self, name:&str) -> usize {
+        self.get(name).map_or(0, |array| array.array().len())
+    }
+}
+
+fn read_u32(bytes:&[u8], pos:usize) -> usize {
+    (bytes[pos] as usize) | (bytes[pos+1] as usize)<<8 | (bytes[pos+2] as usize)<<16 | (bytes[pos+3] as usize)<<24
+}
+
+///Splits glb to json and binary chunk
+fn read_glb(glb:&[u8]) -> (Value,Vec<u8>) {
+    assert_eq!(&glb[0..4], b"glTF");
+    assert_eq!(read_u32(glb, 4), 2);
+    assert_eq!(read_u32(glb, 8), glb.len());
+
+    let json_length=read_u32(glb, 12);
+    assert_eq!(&glb[16..20], b"JSON");
+    assert_eq!(json_length%4, 0);
+    let json=serde_json::from_slice(&glb[20..20+json_length]).unwrap();
+
+    let bin_start=20+json_length;
+    let bin=if bin_start<glb.len() {
+        let bin_length=read_u32(glb, bin_start);
+        assert_eq!(&glb[bin_start+4..bin_start+8], b"BIN\0");
+        assert_eq!(bin_start+8+bin_length, glb.len());
+
+        glb[bin_start+8..].to_vec()
+    }else{
+        Vec::new()
+    };
+
+    (json, bin)
+}
+
+///Floats of accessor from binary chunk
+fn read_floats(gltf:&Value, bin:&[u8], accessor:usize) -> Vec<f32> {
+    let accessor=&gltf.at("accessors").array()[accessor];
+    assert_eq!(accessor.at("componentType").index(), 5126);
+
+    let view=&gltf.at("bufferViews").array()[accessor.at("bufferView").index()];
+    let offset=view.at("byteOffset").index()+accessor.get("byteOffset").map_or(0, |offset| offset.index());
+
+    (0..view.at("byteLength").index()/4).map(|i| f32::from_bits(read_u32(bin, offset+i*4) as u32)).collect()
+}
+
+///Indices of accessor of primitive from binary chunk
+fn read_indices(gltf:&Value, bin:&[u8], accessor:usize) -> Vec<usize> {
+    let accessor=&gltf.at("accessors").array()[accessor];
+    assert_eq!(accessor.at("componentType").index(), 5125);
+
+    let view=&gltf.at("bufferViews").array()[accessor.at("bufferView").index()];
+    let offset=view.at("byteOffset").index();
+
+    (0..accessor.at("count").index()).map(|i| read_u32(bin, offset+i*4)).collect()
+}
+
+///Column-major matrix of translation, rotation and scale of node
+fn node_matrix(node:&Value) -> [f32;16] {
+    let floats=|name:&str, default:&[f32]| node.get(name).map_or(default.to_vec(), |values| values.array().iter().map(|value| value.number() as f32).collect());
+    let t=floats("translation", &[0.0, 0.0, 0.0]);
+    let q=floats("rotation", &[0.0, 0.0, 0.0, 1.0]);
+    let s=floats("scale", &[1.0, 1.0, 1.0]);
+    let (x,y,z,w)=(q[0], q[1], q[2], q[3]);
+
+    let rotation=[
+        [1.0-2.0*(y*y+z*z), 2.0*(x*y-z*w), 2.0*(x*z+y*w)],
+        [2.0*(x*y+z*w), 1.0-2.0*(x*x+z*z), 2.0*(y*z-x*w)],
+        [2.0*(x*z-y*w), 2.0*(y*z+x*w), 1.0-2.0*(x*x+y*y)],
+    ];
+
+    let mut matrix=[0.0;16];
+
+    for col in 0..3 {
+        for row in 0..3 {
+            matrix[col*4+row]=rotation[row][col]*s[col];
+        }
+
+        matrix[12+col]=t[col];
+    }
+
+    matrix[15]=1.0;
+    matrix
+}
+
+fn multiply(a:&[f32], b:&[f32]) -> [f32;16] {
+    let mut matrix=[0.0;16];
+
+    for col in 0..4 {
+        for row in 0..4 {
+            matrix[col*4+row]=(0..4).map(|k| a[k*4+row]*b[col*4+k]).sum();
+        }
+    }
+
+    matrix
+}
+
+///World matrices of nodes of scene
+fn world_matrices(gltf:&Value) -> Vec<[f32;16]> {
+    let nodes=gltf.at("nodes").array();
+    let mut parents=vec![None; nodes.len()];
+
+    for (index,node) in nodes.iter().enumerate() {
+        for child in node.get("children").map_or(&Vec::new(), |children| children.array()).iter() {
+            parents[child.index()]=Some(index);
+        }
+    }
+
+    (0..nodes.len()).map(|mut index| {
+        let mut matrix=node_matrix(&nodes[index]);
+
+        while let Some( parent ) = parents[index] {
+            matrix=multiply(&node_matrix(&nodes[parent]), &matrix);
+            index=parent;
+        }
+
+        matrix
+    }).collect()
+}
+
+///Value of glTF sampler at time
+fn sample(times:&[f32], values:&[f32], step:bool, time:f32) -> Vec<f32> {
+    let components=values.len()/times.len();
+    let key=times.iter().rposition(|key_time| *key_time<=time).unwrap_or(0);
+    let value=|key:usize| values[key*components..(key+1)*components].to_vec();
+
+    if step || key+1==times.len() || time<=times[0] {
+        return value(key);
+    }
+
+    let s=(time-times[key])/(times[key+1]-times[key]);
+    value(key).iter().zip(value(key+1).iter()).map(|(a,b)| a+(b-a)*s).collect()
+}
+
+///References between arrays are in range, accessors fit into buffer
+fn check_references(gltf:&Value, buffer_length:usize) {
+    assert_eq!(gltf.at("asset").at("version").string(), "2.0");
+
+    let buffer_views=gltf.at("bufferViews").array();
+    for view in buffer_views.iter() {
+        assert_eq!(view.at("byteOffset").index()%4, 0);
+        assert!(view.at("byteOffset").index()+view.at("byteLength").index()<=buffer_length);
+    }
+
+    let accessors=gltf.at("accessors").array();
+    for accessor in accessors.iter() {
+        let view=&buffer_views[accessor.at("bufferView").index()];
+        let components=match accessor.at("type").string() {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            "MAT4" => 16,
+            other => panic!("Unknown type {}", other),
+        };
+        let size=match accessor.at("componentType").index() {
+            5123 => 2,
+            _ => 4,
+        };
+
+        assert_eq!(accessor.at("count").index()*components*size, view.at("byteLength").index());
+    }
+
+    let nodes_count=gltf.len("nodes");
+    for node in gltf.at("nodes").array().iter() {
+        for child in node.get("children").map_or(&Vec::new(), |children| children.array()).iter() {
+            assert!(child.index()<nodes_count);
+        }
+
+        match node.get("mesh") {
+            Some( mesh ) => assert!(mesh.index()<gltf.len("meshes")),
+            None => {},
+        }
+
+        match node.get("skin") {
+            Some( skin ) => assert!(skin.index()<gltf.len("skins")),
+            None => {},
+        }
+    }
+
+    for mesh in gltf.get("meshes").map_or(&Vec::new(), |meshes| meshes.array()).iter() {
+        for primitive in mesh.at("primitives").array().iter() {
+            let indices=&accessors[primitive.at("indices").index()];
+            assert_eq!(indices.at("count").index()%3, 0);
+
+            let positions=&accessors[primitive.at("attributes").at("POSITION").index()];
+            assert!(positions.get("min").is_some() && positions.get("max").is_some());
+        }
+    }
+
+    for skin in gltf.get("skins").map_or(&Vec::new(), |skins| skins.array()).iter() {
+        let joints=skin.at("joints").array();
+        assert_eq!(accessors[skin.at("inverseBindMatrices").index()].at("count").index(), joints.len());
+
+        for joint in joints.iter() {
+            assert!(joint.index()<nodes_count);
+        }
+    }
+
+    for animation in gltf.get("animations").map_or(&Vec::new(), |animations| animations.array()).iter() {
+        let samplers=animation.at("samplers").array();
+
+        for channel in animation.at("channels").array().iter() {
+            assert!(channel.at("target").at("node").index()<nodes_count);
+
+            let sampler=&samplers[channel.at("sampler").index()];
+            let input=&accessors[sampler.at("input").index()];
+            let output=&accessors[sampler.at("output").index()];
+            assert_eq!(input.at("count").index(), output.at("count").index());
+        }
+    }
+}
+
+#[test]
+fn export_glb(){
+    let document=Document::parse(&Path::new("scene.dae")).unwrap();
+
+    let mut glb=Vec::new();
+    document.write_glb(&mut glb).unwrap();
+
+    let (gltf,bin)=read_glb(&glb);
+    check_references(&gltf, bin.len());
+    assert_eq!(gltf.at("buffers").array()[0].at("byteLength").index(), bin.len());
+    assert!(gltf.at("buffers").array()[0].get("uri").is_none());
+
+    assert_eq!(gltf.len("scenes"), 1);
+    assert_eq!(gltf.len("meshes"), 3);
+    assert_eq!(gltf.len("skins"), 1);
+    assert_eq!(gltf.len("cameras"), 1);
+    assert!(gltf.len("animations")>0);
+
+    //xfov 49.13° with aspect ratio 16:9 is yfov 28.84°
+    let perspective=gltf.at("cameras").array()[0].at("perspective");
+    assert!((perspective.at("yfov").number()-0.5033).abs()<0.001, "{:?}", perspective);
+    assert!((perspective.at("aspectRatio").number()-1.7778).abs()<0.001);
+
+    let bones_count:usize=document.skeletons.values().map(|skeleton| skeleton.bones_array.len()).sum();
+    assert!(gltf.len("nodes")>=bones_count+document.skeletons.len()+4);
+}
+
+#[test]
+fn export_gltf_with_materials(){
+    let document=Document::parse(&Path::new("tests/data/face.dae")).unwrap();
+
+    let mut json=Vec::new();
+    let mut bin=Vec::new();
+    document.write_gltf(&mut json, &mut bin, "face.bin").unwrap();
+
+    let gltf:Value=serde_json::from_slice(&json).unwrap();
+    check_references(&gltf, bin.len());
+
+    let buffer=&gltf.at("buffers").array()[0];
+    assert_eq!(buffer.at("uri").string(), "face.bin");
+    assert_eq!(buffer.at("byteLength").index(), bin.len());
+
+    let material=&gltf.at("materials").array()[0];
+    assert_eq!(material.at("name").string(), "Skin");
+    assert_eq!(material.at("alphaMode").string(), "BLEND");
+
+    let pbr=material.at("pbrMetallicRoughness");
+    let base_color:Vec<f64>=pbr.at("baseColorFactor").array().iter().map(|value| value.number()).collect();
+    assert_eq!(base_color, vec![0.8, 0.6, 0.5, 0.25]);
+    assert_eq!(pbr.at("metallicFactor").number(), 0.0);
+    assert!((pbr.at("roughnessFactor").number()-(2.0f64/52.0).sqrt()).abs()<0.0001);
+
+    //skinned and morphed nodes use own meshes of base geometry
+    assert_eq!(gltf.len("meshes"), 2);
+    let skinned=gltf.at("meshes").array().iter().find(|mesh| mesh.at("primitives").array()[0].at("attributes").get("JOINTS_0").is_some()).unwrap();
+    assert_eq!(skinned.at("primitives").array()[0].at("material").index(), 0);
+
+    let skin=&gltf.at("skins").array()[0];
+    let joint_names:Vec<&str>=skin.at("joints").array().iter().map(|joint| gltf.at("nodes").array()[joint.index()].at("name").string()).collect();
+    assert!(joint_names.contains(&"Root"));
+}
+
+#[test]
+fn export_clips(){
+    let document=Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+
+    let mut glb=Vec::new();
+    document.write_glb(&mut glb).unwrap();
+
+    let (gltf,bin)=read_glb(&glb);
+    check_references(&gltf, bin.len());
+
+    let names:Vec<&str>=gltf.at("animations").array().iter().map(|animation| animation.at("name").string()).collect();
+    assert!(names.contains(&"Walk"));
+
+    for animation in gltf.at("animations").array().iter() {
+        for sampler in animation.at("samplers").array().iter() {
+            let input=&gltf.at("accessors").array()[sampler.at("input").index()];
+            assert!(input.at("min").array()[0].number()>=0.0);
+        }
+    }
+
+    //translations of clips are same at keys and between them
+    let mut checked=0;
+
+    for clip in document.clips.values() {
+        let decoded=clip.decode().unwrap();
+        let animation=gltf.at("animations").array().iter().find(|animation| animation.at("name").string()==clip.name).unwrap();
+        let samplers=animation.at("samplers").array();
+
+        for channel in animation.at("channels").array().iter() {
+            let node=&gltf.at("nodes").array()[channel.at("target").at("node").index()];
+
+            if channel.at("target").at("path").string()!="translation" {
+                continue;
+            }
+
+            let source=decoded.channels.iter().find(|source| source.target==format!("{}/translate", node.at("name").string())).unwrap();
+
+            let sampler=&samplers[channel.at("sampler").index()];
+            let times=read_floats(&gltf, &bin, sampler.at("input").index());
+            let values=read_floats(&gltf, &bin, sampler.at("output").index());
+            let step=sampler.at("interpolation").string()=="STEP";
+
+            for key in 0..source.times.len() {
+                let middle=if key+1<source.times.len() { (source.times[key]+source.times[key+1])/2.0 } else { source.times[key] };
+
+                for time in [source.times[key], middle].iter() {
+                    let expected=match source.sample(*time, SampleMode::Clamp) {
+                        ChannelValue::Vec3( value ) => value,
+                        _ => panic!("Translation channel is expected"),
+                    };
+
+                    let value=sample(&times, &values, step, *time);
+                    assert!((0..3).all(|i| (value[i]-expected[i]).abs()<0.0001), "{} at {}: {:?} != {:?}", clip.name, time, value, expected);
+                }
+            }
+
+            checked+=1;
+        }
+    }
+
+    assert_eq!(checked, document.clips.len());
+}
+
+#[test]
+fn export_shared_skin(){
+    let document=Document::parse(&Path::new("tests/data/arm.dae")).unwrap();
+
+    let mut glb=Vec::new();
+    document.write_glb(&mut glb).unwrap();
+
+    let (gltf,bin)=read_glb(&glb);
+    check_references(&gltf, bin.len());
+
+    //both nodes use one skin, so inverse bind matrices are written once
+    assert_eq!(gltf.len("skins"), 1);
+
+    let skins:Vec<usize>=gltf.at("nodes").array().iter().filter_map(|node| node.get("skin")).map(|skin| skin.index()).collect();
+    assert_eq!(skins, vec![0, 0]);
+
+    let matrices=gltf.at("accessors").array().iter().filter(|accessor| accessor.at("type").string()=="MAT4").count();
+    assert_eq!(matrices, 1);
+
+    //bind pose is rest pose, so inverse bind matrices undo world matrices of joints
+    let skin=&gltf.at("skins").array()[0];
+    let inverse_bind_matrices=read_floats(&gltf, &bin, skin.at("inverseBindMatrices").index());
+    let world_matrices=world_matrices(&gltf);
+
+    for (joint_index,joint) in skin.at("joints").array().iter().enumerate() {
+        let matrix=multiply(&world_matrices[joint.index()], &inverse_bind_matrices[joint_index*16..(joint_index+1)*16]);
+
+        for i in 0..16 {
+            let identity=if i%5==0 { 1.0 } else { 0.0 };
+            assert!((matrix[i]-identity).abs()<0.0001, "{:?}", matrix);
+        }
+    }
+
+    //positions are written as they are in document
+    let mesh=&gltf.at("meshes").array()[0];
+    let accessor=mesh.at("primitives").array()[0].at("attributes").at("POSITION").index();
+    let mut positions:Vec<Vec<f32>>=read_floats(&gltf, &bin, accessor).chunks(3).map(|position| position.to_vec()).collect();
+    positions.sort_by(|a,b| a.partial_cmp(b).unwrap());
+    positions.dedup();
+
+    let expected=vec![[0.0, 1.5, 0.0], [0.15, 1.6, 0.0], [0.3, 1.5, 0.0], [0.45, 1.6, 0.0], [0.6, 1.5, 0.0], [0.75, 1.6, 0.0], [0.9, 1.5, 0.0]];
+    assert_eq!(positions, expected.iter().map(|position| position.to_vec()).collect::<Vec<Vec<f32>>>());
+
+    let bounds=&gltf.at("accessors").array()[accessor];
+    let min:Vec<f64>=bounds.at("min").array().iter().map(|value| value.number()).collect();
+    let max:Vec<f64>=bounds.at("max").array().iter().map(|value| value.number()).collect();
+    assert!((min[0]-0.0).abs()<0.0001 && (min[1]-1.5).abs()<0.0001);
+    assert!((max[0]-0.9).abs()<0.0001 && (max[1]-1.6).abs()<0.0001);
+}
+
+#[test]
+fn export_skinned_normals(){
+    //bind shape matrix shears cube along X by Y, so faces of top and bottom keep normals (0,1,0) and (0,-1,0)
+    let text=std::fs::read_to_string("scene.dae").unwrap()
+        .replace("<bind_shape_matrix>1 0 0 -2.083929", "<bind_shape_matrix>1 1 0 -2.083929");
+
+    let mut path=PathBuf::from(std::env::temp_dir());
+    path.push("collada_gltf_sheared.dae");
+    std::fs::write(&path, text).unwrap();
+
+    let document=Document::parse(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut glb=Vec::new();
+    document.write_glb(&mut glb).unwrap();
+
+    let (gltf,bin)=read_glb(&glb);
+    let mesh=gltf.at("meshes").array().iter().find(|mesh| mesh.at("name").string()=="Cube.001").unwrap();
+    let primitive=&mesh.at("primitives").array()[0];
+    assert!(primitive.at("attributes").get("JOINTS_0").is_some());
+
+    let positions=read_floats(&gltf, &bin, primitive.at("attributes").at("POSITION").index());
+    let normals=read_floats(&gltf, &bin, primitive.at("attributes").at("NORMAL").index());
+    let indices=read_indices(&gltf, &bin, primitive.at("indices").index());
+    let vector=|list:&[f32], index:usize| [list[index*3], list[index*3+1], list[index*3+2]];
+
+    //cube is flat shaded, so normals of corners are perpendicular to sides of triangle
+    for triangle in indices.chunks(3) {
+        let p=[vector(&positions, triangle[0]), vector(&positions, triangle[1]), vector(&positions, triangle[2])];
+
+        for corner in triangle.iter() {
+            let n=vector(&normals, *corner);
+            assert!(((n[0]*n[0] + n[1]*n[1] + n[2]*n[2]).sqrt()-1.0).abs()<0.0001, "{:?}", n);
+
+            for side in [(0,1),(1,2)].iter() {
+                let e=[p[side.1][0]-p[side.0][0], p[side.1][1]-p[side.0][1], p[side.1][2]-p[side.0][2]];
+                assert!((n[0]*e[0] + n[1]*e[1] + n[2]*e[2]).abs()<0.0001, "normal {:?}, side {:?}", n, e);
+            }
+        }
+    }
+}
+
+#[test]
+fn export_warnings_and_errors(){
+    //Lamp of animation is not node of scene
+    let document=Document::parse(&Path::new("tests/data/armature.dae")).unwrap();
+
+    let mut glb=Vec::new();
+    let warnings=document.write_glb(&mut glb).unwrap();
+    assert!(warnings.iter().any(|warning| warning.contains("Lamp")), "{:?}", warnings);
+
+    //json has no NaN
+    let mut document=Document::parse(&Path::new("scene.dae")).unwrap();
+
+    {
+        let scene=Arc::get_mut(document.scenes.get_mut("Scene").unwrap()).unwrap();
+        let node=scene.geometries.values_mut().next().unwrap();
+        node.location.position.x=std::f32::NAN;
+    }
+
+    assert!(document.write_glb(&mut Vec::new()).is_err());
+}
@@ -0,0 +1,24 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::LightKind;
+
+#[test]
+fn skip_unsupported_lights(){
+    let document=collada::Document::parse(&Path::new("tests/data/lights.dae")).unwrap();
+
+    assert_eq!(document.lights.len(), 1);
+    let light=document.lights.get("Sun-light").unwrap();
+    assert_eq!(light.kind, LightKind::Directional);
+    assert_eq!(light.color, [1.0, 0.9, 0.8]);
+
+    let scene=document.scenes.get("Scene").unwrap();
+    assert_eq!(scene.lights.len(), 1);
+    assert!(scene.lights.contains_key("Sun"));
+
+    //two lights and two nodes, that use them, are skipped
+    assert_eq!(document.warnings.len(), 4);
+    assert!(document.warnings.iter().any(|warning| warning.contains("Panel-light") && warning.contains("area")));
+    assert!(document.warnings.iter().any(|warning| warning.contains("Dark-light") && warning.contains("color")));
+}
@@ -0,0 +1,67 @@
+extern crate collada;
+
+use std::path::Path;
+
+#[test]
+fn merge_same_armature(){
+    let mut document=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+    let other=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+
+    let added=document.merge_animations(&other).unwrap();
+    assert_eq!(added, vec![String::from("Hips-anim_2")]);
+    assert_eq!(document.animations.len(), 2);
+
+    let animation=document.animations.get("Hips-anim_2").unwrap();
+    assert_eq!(animation.id, Some(String::from("Hips-anim_2")));
+
+    for sampler in animation.all_samplers() {
+        assert_eq!(sampler.skeleton_id, Some(String::from("Rig")));
+    }
+
+    assert_eq!(document.clips.len(), 4);
+    let walk=document.clips.get("walk_2").unwrap();
+    assert_eq!(walk.name, "Walk");
+    assert_eq!(walk.animations[0].id, Some(String::from("Hips-anim_2")));
+
+    let mut document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let other=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+
+    let added=document.merge_animations(&other).unwrap();
+    assert_eq!(added.len(), 8);
+    assert_eq!(document.animations.len(), 16);
+}
+
+#[test]
+fn report_mismatched_bones(){
+    let mut document=collada::Document::parse(&Path::new("tests/data/tall.dae")).unwrap();
+    let other=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+
+    assert!(document.merge_animations(&other).is_err());
+    assert_eq!(document.animations.len(), 0);
+
+    let mut document=collada::Document::parse(&Path::new("tests/data/rig_flat.dae")).unwrap();
+
+    match document.merge_animations(&other) {
+        Err( collada::Error::Other( message ) ) => assert!(message.contains("bones with other parent [Spine]"), "{}", message),
+        _ => panic!("Spine has other parent"),
+    }
+    assert_eq!(document.animations.len(), 0);
+}
+
+#[test]
+fn merge_renamed_armature(){
+    let mut document=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+    let other=collada::Document::parse(&Path::new("tests/data/armature.dae")).unwrap();
+    let warnings_count=document.warnings.len();
+
+    let added=document.merge_animations(&other).unwrap();
+    assert_eq!(added, vec![String::from("Jump-anim")]);
+
+    //skeleton "Armature" has been matched with "Rig" by root bone
+    let samplers=document.animations["Jump-anim"].all_samplers();
+    let bound:Vec<(&str,Option<&str>)>=samplers.iter().map(|sampler| (sampler.bone_id.as_str(), sampler.skeleton_id.as_ref().map(|id| id.as_str()))).collect();
+    assert_eq!(bound, vec![("Hips", Some("Rig")), ("Lamp", None)]);
+
+    assert_eq!(document.warnings.len(), warnings_count+1);
+    assert!(document.warnings.last().unwrap().contains("Lamp"));
+}
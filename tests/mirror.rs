@@ -0,0 +1,70 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::{Axis,ChannelValue,ClipRange,MirrorPattern};
+
+#[test]
+fn pair_bones(){
+    let pattern=MirrorPattern::blender();
+    assert_eq!(pattern.mirror_name("Hand.l"), Some(String::from("Hand.r")));
+    assert_eq!(pattern.mirror_name("Hand_r_2"), Some(String::from("Hand_l_2")));
+    assert_eq!(pattern.mirror_name("Hand_rest"), None);
+    assert_eq!(pattern.mirror_name("Arm_Lower"), None);
+
+    let words=MirrorPattern::new("Left","Right");
+    assert_eq!(words.mirror_name("LeftArm"), Some(String::from("RightArm")));
+    assert_eq!(words.mirror_name("ArmRightUpper"), Some(String::from("ArmLeftUpper")));
+    assert_eq!(words.mirror_name("Arm_Left"), Some(String::from("Arm_Right")));
+    assert_eq!(words.mirror_name("Leftover"), None);
+
+    let prefixes=MirrorPattern::new("L_","R_");
+    assert_eq!(prefixes.mirror_name("L_Hand"), Some(String::from("R_Hand")));
+    assert_eq!(prefixes.mirror_name("Arm_R_2"), Some(String::from("Arm_L_2")));
+    assert_eq!(prefixes.mirror_name("ARMR_Hand"), None);
+
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let map=document.skeletons.get("Guy").unwrap().mirror_map(&pattern);
+
+    assert_eq!(map.pairs.len(), 4);
+    assert_eq!(map.get("Hand_r").unwrap(), "Hand_l");
+    assert_eq!(map.get("Hand_l_2").unwrap(), "Hand_r_2");
+    assert_eq!(map.get("Neck"), None);
+}
+
+#[test]
+fn mirror_clip(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let map=document.skeletons.get("Guy").unwrap().mirror_map(&MirrorPattern::blender());
+
+    let range=ClipRange{ name:String::from("all"), start:0.0, end:1.0 };
+    let clip=document.split_animations(&[range]).unwrap().pop().unwrap();
+    let mirrored=clip.mirror(&map, Axis::X).unwrap();
+
+    assert_eq!(mirrored.channels.len(), clip.channels.len());
+
+    for (channel,mirrored) in clip.channels.iter().zip(mirrored.channels.iter()) {
+        let expected=match channel.target.as_str() {
+            "Hand_r/transform" => "Hand_l/transform",
+            "Hand_l/transform" => "Hand_r/transform",
+            "Hand_r_2/transform" => "Hand_l_2/transform",
+            "Hand_l_2/transform" => "Hand_r_2/transform",
+            target => target,
+        };
+        assert_eq!(mirrored.target, expected);
+
+        match (channel.values.get(0), mirrored.values.get(0)) {
+            (ChannelValue::Location( a ), ChannelValue::Location( b )) => {
+                assert_eq!(b.position.x, -a.position.x);
+                assert_eq!(b.position.y, a.position.y);
+                assert_eq!((b.rotation.x, b.rotation.y, b.rotation.z, b.rotation.w), (a.rotation.x, -a.rotation.y, -a.rotation.z, a.rotation.w));
+            },
+            _ => panic!("Location channel is expected"),
+        }
+    }
+
+    let twice=mirrored.mirror(&map, Axis::X).unwrap();
+    for (channel,twice) in clip.channels.iter().zip(twice.channels.iter()) {
+        assert_eq!(channel.target, twice.target);
+    }
+}
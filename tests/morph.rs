@@ -0,0 +1,38 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::{Controller,MorphMethod};
+
+#[test]
+fn morph_and_skin_on_morph(){
+    let document=collada::Document::parse(&Path::new("tests/data/morph.dae")).unwrap();
+
+    let morph=document.morphs.get("Face-morph").unwrap();
+    assert_eq!(morph.geometry_id, "Base-mesh");
+    assert_eq!(morph.method, MorphMethod::Relative);
+    assert_eq!(morph.targets, vec![String::from("Smile-mesh")]);
+    assert_eq!(morph.weights, vec![0.25]);
+
+    let skin=document.skins.get("Face-skin").unwrap();
+    assert_eq!(skin.geometry_id, "Base-mesh");
+    assert_eq!(skin.morph.as_ref().unwrap().id, "Face-morph");
+
+    let scene=document.scenes.get("Scene").unwrap();
+
+    let face=scene.geometries.get("Face").unwrap();
+    assert_eq!(face.joined.id, "Base-mesh");
+    match face.controller {
+        Controller::Skin(ref skin, ref skeleton) => {
+            assert_eq!(skin.id, "Face-skin");
+            assert_eq!(skeleton.id, "Rig");
+        },
+        _ => panic!("Face should be skinned"),
+    }
+
+    let face_morph=scene.geometries.get("FaceMorph").unwrap();
+    match face_morph.controller {
+        Controller::Morph(ref morph) => assert_eq!(morph.id, "Face-morph"),
+        _ => panic!("FaceMorph should be morphed"),
+    }
+}
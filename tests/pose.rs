@@ -0,0 +1,67 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::{ClipRange,Location,Pose,SampleMode};
+
+fn assert_close(a:&Location, b:&Location){
+    assert!((a.position.x-b.position.x).abs()<0.0001 && (a.position.y-b.position.y).abs()<0.0001 && (a.position.z-b.position.z).abs()<0.0001);
+    assert!((a.scale.x-b.scale.x).abs()<0.0001 && (a.scale.y-b.scale.y).abs()<0.0001 && (a.scale.z-b.scale.z).abs()<0.0001);
+    assert!(a.rotation.dot(&b.rotation).abs()>0.9999);
+}
+
+#[test]
+fn blend_and_add_poses(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let skeleton=document.skeletons.get("Guy").unwrap();
+    let animations:Vec<_>=document.animations.values().cloned().collect();
+
+    let range=ClipRange{ name:String::from("all"), start:0.0, end:1.0 };
+    let clip=document.split_animations(&[range]).unwrap().pop().unwrap();
+
+    let rest=Pose::rest(skeleton);
+    let posed=clip.pose_at(skeleton, 0.5, SampleMode::Clamp).unwrap();
+    let from_animations=skeleton.pose_at(&animations, 0.5).unwrap();
+
+    for (a,b) in posed.locations.iter().zip(from_animations.locations.iter()) {
+        assert_close(a, b);
+    }
+
+    let start=Pose::blend(&rest, &posed, 0.0).unwrap();
+    let end=Pose::blend(&rest, &posed, 1.0).unwrap();
+
+    for i in 0..rest.locations.len() {
+        assert_close(&start.locations[i], &rest.locations[i]);
+        assert_close(&end.locations[i], &posed.locations[i]);
+    }
+
+    let delta=Pose::difference(&posed, &rest).unwrap();
+    let added=Pose::add(&rest, &delta).unwrap();
+    let unchanged=Pose::add(&posed, &Pose::identity(skeleton.bones_array.len())).unwrap();
+
+    for i in 0..rest.locations.len() {
+        assert_close(&added.locations[i], &posed.locations[i]);
+        assert_close(&unchanged.locations[i], &posed.locations[i]);
+    }
+
+    assert!(Pose::blend(&rest, &Pose::identity(1), 0.5).is_err());
+}
+
+#[test]
+fn matrix_palette_in_rest_pose(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let skin=document.skins.get("Guy_Cube-skin").unwrap();
+    let skeleton=document.skeletons.get("Guy").unwrap();
+
+    let joints=skin.bind(skeleton).unwrap();
+    let palette=Pose::rest(skeleton).matrix_palette(skeleton, &joints).unwrap();
+
+    assert_eq!(palette.len(), joints.len());
+
+    for matrix in palette.iter() {
+        for (i,value) in matrix.mat.iter().enumerate() {
+            let identity=if i%5==0 { 1.0 } else { 0.0 };
+            assert!((value-identity).abs()<0.0001, "{:?}", matrix.mat);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::{Channel,ChannelValue,ChannelValues,ClipRange,Interpolation,KeyError,SampleMode};
+
+fn location_error(a:&Channel, b:&Channel, time:f32) -> KeyError {
+    KeyError::between(&a.sample(time, SampleMode::Clamp), &b.sample(time, SampleMode::Clamp))
+}
+
+#[test]
+fn reduce_baked_keys(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let end=document.animations.values().map(|animation| animation.channels().unwrap()[0].end_time()).fold(0.0, f32::max);
+    let range=ClipRange{ name:String::from("all"), start:0.0, end:end };
+    let clip=document.split_animations(&[range]).unwrap().pop().unwrap();
+
+    let tolerance=KeyError{ position:0.001, rotation:0.001, scale:0.001, value:0.001 };
+    let (reduced,reduction)=clip.reduce(&tolerance).unwrap();
+
+    assert_eq!(reduction.keys_before, clip.channels.iter().map(|channel| channel.times.len()).sum::<usize>());
+    assert!(reduction.keys_after<reduction.keys_before);
+    assert!(reduction.compression_ratio()>1.0);
+    assert!(reduction.max_error.is_within(&tolerance));
+
+    for (original,reduced) in clip.channels.iter().zip(reduced.channels.iter()) {
+        assert_eq!(original.times[0], reduced.times[0]);
+        assert_eq!(original.times.last(), reduced.times.last());
+
+        for time in original.times.iter() {
+            assert!(location_error(original, reduced, *time).is_within(&tolerance));
+        }
+    }
+}
+
+#[test]
+fn resample_channel(){
+    let channel=Channel{
+        target:String::from("Node/weight"),
+        times:vec![0.0, 0.5, 1.0, 1.25],
+        values:ChannelValues::Float(vec![0.0, 1.0, 2.0, 2.0]),
+        interpolation:vec![Interpolation::Linear, Interpolation::Linear, Interpolation::Step, Interpolation::Linear],
+        in_tangents:Vec::new(),
+        out_tangents:Vec::new(),
+    };
+
+    let resampled=channel.resample(4.0).unwrap();
+    assert_eq!(resampled.times, vec![0.0, 0.25, 0.5, 0.75, 1.0, 1.25]);
+    assert_eq!(resampled.interpolation[4], Interpolation::Step);
+    match resampled.values {
+        ChannelValues::Float(ref values) => assert_eq!(values, &vec![0.0, 0.5, 1.0, 1.5, 2.0, 2.0]),
+        _ => panic!("float channel must stay float"),
+    }
+
+    //keys on a line collapse to ends, step keeps its key
+    let (reduced,reduction)=resampled.reduce(&KeyError::uniform(0.0001)).unwrap();
+    assert_eq!(reduced.times, vec![0.0, 1.0, 1.25]);
+    assert_eq!((reduction.keys_before, reduction.keys_after), (6, 3));
+    assert_eq!(reduction.compression_ratio(), 2.0);
+
+    match reduced.sample(0.75, SampleMode::Clamp) {
+        ChannelValue::Float(value) => assert_eq!(value, 1.5),
+        _ => panic!("float channel must stay float"),
+    }
+
+    assert!(channel.resample(0.0).is_err());
+}
+
+#[test]
+fn reduce_short_channels(){
+    let empty=Channel{
+        target:String::from("Node/weight"),
+        times:Vec::new(),
+        values:ChannelValues::Float(Vec::new()),
+        interpolation:Vec::new(),
+        in_tangents:Vec::new(),
+        out_tangents:Vec::new(),
+    };
+
+    let (reduced,reduction)=empty.reduce(&KeyError::uniform(0.1)).unwrap();
+    assert_eq!(reduced.times.len(), 0);
+    assert_eq!((reduction.keys_before, reduction.keys_after), (0, 0));
+    assert_eq!(reduction.compression_ratio(), 1.0);
+
+    let single=Channel{
+        times:vec![0.5],
+        values:ChannelValues::Float(vec![2.0]),
+        interpolation:vec![Interpolation::Linear],
+        ..empty
+    };
+
+    let (reduced,reduction)=single.reduce(&KeyError::uniform(0.1)).unwrap();
+    assert_eq!(reduced.times, vec![0.5]);
+    assert_eq!((reduction.keys_before, reduction.keys_after), (1, 1));
+    match reduced.values {
+        ChannelValues::Float(ref values) => assert_eq!(values, &vec![2.0]),
+        _ => panic!("float channel must stay float"),
+    }
+}
@@ -0,0 +1,142 @@
+extern crate collada;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use collada::{Channel,ChannelValue,ChannelValues,Clip,ClipRange,Interpolation,Tangent};
+
+#[test]
+fn retarget_to_other_rig(){
+    let rig=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+    let tall=collada::Document::parse(&Path::new("tests/data/tall.dae")).unwrap();
+    let (from,to)=(rig.skeletons.get("Rig").unwrap(), tall.skeletons.get("Tall").unwrap());
+
+    assert_eq!(from.leg_length().unwrap(), 1.0);
+    assert_eq!(to.leg_length().unwrap(), 2.0);
+
+    let mut map=HashMap::new();
+    map.insert(String::from("Hips"), String::from("Pelvis"));
+    map.insert(String::from("Spine"), String::from("Chest"));
+
+    let range=ClipRange{ name:String::from("all"), start:0.0, end:2.0 };
+    let clip=rig.split_animations(&[range]).unwrap().pop().unwrap();
+    let retargeted=collada::retarget(&clip, from, to, &map).unwrap();
+
+    let targets:Vec<&str>=retargeted.channels.iter().map(|channel| channel.target.as_str()).collect();
+    assert_eq!(targets, vec!["Tall_Pelvis/translate", "Tall_Pelvis/rotateY.ANGLE", "Tall_Chest/rotateX.ANGLE"]);
+
+    match retargeted.channels[0].values {
+        ChannelValues::Vec3( ref values ) => assert_eq!(values, &vec![[0.0, 2.0, 0.0], [0.0, 4.0, 0.0], [4.0, 4.0, 0.0]]),
+        _ => panic!("Translation channel is expected"),
+    }
+
+    match (&retargeted.channels[1].values, &clip.channels[1].values) {
+        (&ChannelValues::Float( ref a ), &ChannelValues::Float( ref b )) => assert_eq!(a, b),
+        _ => panic!("Angle channel is expected"),
+    }
+}
+
+#[test]
+fn retarget_to_same_rig(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let skeleton=document.skeletons.get("Guy").unwrap();
+
+    let range=ClipRange{ name:String::from("all"), start:0.0, end:1.0 };
+    let clip=document.split_animations(&[range]).unwrap().pop().unwrap();
+    let retargeted=collada::retarget(&clip, skeleton, skeleton, &HashMap::new()).unwrap();
+
+    assert_eq!(retargeted.channels.len(), clip.channels.len());
+
+    for (channel,retargeted) in clip.channels.iter().zip(retargeted.channels.iter()) {
+        assert_eq!(channel.target, retargeted.target);
+
+        for key in 0..channel.times.len() {
+            match (channel.values.get(key), retargeted.values.get(key)) {
+                (ChannelValue::Location( a ), ChannelValue::Location( b )) => {
+                    assert!((a.position.x-b.position.x).abs()<0.0001);
+                    assert!(a.rotation.dot(&b.rotation).abs()>0.9999);
+                    assert!((a.scale.y-b.scale.y).abs()<0.0001);
+                },
+                _ => panic!("Location channel is expected"),
+            }
+        }
+    }
+}
+
+#[test]
+fn retarget_angles_and_tangents(){
+    let rig=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+    let turned=collada::Document::parse(&Path::new("tests/data/turned.dae")).unwrap();
+
+    //taller rig, that is turned about Y, its spine is bent about Z
+    let (from,to)=(rig.skeletons.get("Rig").unwrap(), turned.skeletons.get("Turned").unwrap());
+
+    let hermite=|value:ChannelValue| Tangent{ time:None, value:value };
+
+    let channels=vec![
+        Channel{
+            target:String::from("Hips/translate"),
+            times:vec![0.0, 1.0],
+            values:ChannelValues::Vec3(vec![[0.0, 1.0, 0.0], [1.0, 1.0, 0.0]]),
+            interpolation:vec![Interpolation::Hermite, Interpolation::Hermite],
+            in_tangents:vec![hermite(ChannelValue::Vec3([1.0, 0.0, 0.0])), hermite(ChannelValue::Vec3([1.0, 0.0, 0.0]))],
+            out_tangents:vec![hermite(ChannelValue::Vec3([1.0, 0.0, 0.0])), hermite(ChannelValue::Vec3([1.0, 0.0, 0.0]))],
+        },
+        Channel{
+            target:String::from("Hips/rotateY.ANGLE"),
+            times:vec![0.0, 1.0],
+            values:ChannelValues::Float(vec![0.0, 45.0]),
+            interpolation:vec![Interpolation::Bezier, Interpolation::Bezier],
+            in_tangents:vec![Tangent{ time:Some(-0.3), value:ChannelValue::Float(0.0) }, Tangent{ time:Some(0.7), value:ChannelValue::Float(45.0) }],
+            out_tangents:vec![Tangent{ time:Some(0.3), value:ChannelValue::Float(0.0) }, Tangent{ time:Some(1.3), value:ChannelValue::Float(45.0) }],
+        },
+    ];
+
+    let clip=Clip{ name:String::from("walk"), duration:1.0, channels:channels };
+    let retargeted=collada::retarget(&clip, from, to, &HashMap::new()).unwrap();
+
+    //values are offsets from rest pose, HERMITE tangents are only scaled
+    let translation=&retargeted.channels[0];
+    assert_eq!(translation.target, "Turned_Hips/translate");
+
+    match translation.values {
+        ChannelValues::Vec3( ref values ) => assert_eq!(values, &vec![[0.0, 2.0, 0.0], [2.0, 2.0, 0.0]]),
+        _ => panic!("Translation channel is expected"),
+    }
+
+    match translation.out_tangents[0].value {
+        ChannelValue::Vec3( tangent ) => assert_eq!(tangent, [2.0, 0.0, 0.0]),
+        _ => panic!("Vec3 tangent is expected"),
+    }
+
+    //angle turns by rest angle of target bone, BEZIER control points too
+    let rotation=&retargeted.channels[1];
+
+    match rotation.values {
+        ChannelValues::Float( ref values ) => {
+            assert!((values[0]-90.0).abs()<0.01 && (values[1]-135.0).abs()<0.01, "{:?}", values);
+        },
+        _ => panic!("Angle channel is expected"),
+    }
+
+    match (rotation.out_tangents[0].time, &rotation.out_tangents[0].value) {
+        (Some( time ), &ChannelValue::Float( angle )) => assert!(time==0.3 && (angle-90.0).abs()<0.01),
+        _ => panic!("Float tangent is expected"),
+    }
+
+    //spine is bent about other axis, so angle about X can not be moved
+    let bend=Clip{
+        name:String::from("bend"),
+        duration:1.0,
+        channels:vec![Channel{
+            target:String::from("Spine/rotateX.ANGLE"),
+            times:vec![0.0, 1.0],
+            values:ChannelValues::Float(vec![0.0, 30.0]),
+            interpolation:vec![Interpolation::Linear, Interpolation::Linear],
+            in_tangents:Vec::new(),
+            out_tangents:Vec::new(),
+        }],
+    };
+
+    assert!(collada::retarget(&bend, from, to, &HashMap::new()).is_err());
+}
@@ -0,0 +1,111 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::{Channel,ChannelValues,Clip,ClipRange,Interpolation,Location,Position,Quaternion,RootMotionAxes,Scale};
+
+#[test]
+fn extract_from_matrices(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let range=ClipRange{ name:String::from("all"), start:0.0, end:1.0 };
+    let original=document.split_animations(&[range]).unwrap().pop().unwrap();
+
+    let mut clip=original.clone();
+    let motion=clip.extract_root_motion("Position", RootMotionAxes::horizontal()).unwrap();
+
+    let channel_index=clip.channels.iter().position(|channel| channel.target=="Position/transform").unwrap();
+    let (before,after)=match (&original.channels[channel_index].values, &clip.channels[channel_index].values) {
+        (&ChannelValues::Location( ref before ), &ChannelValues::Location( ref after )) => (before, after),
+        _ => panic!("Location channel is expected"),
+    };
+
+    assert_eq!(motion.bone_id, "Position");
+    assert_eq!(motion.deltas.len(), before.len());
+
+    for (key_before,key_after) in before.iter().zip(after.iter()) {
+        assert_eq!(key_after.position.x, before[0].position.x);
+        assert_eq!(key_after.position.z, before[0].position.z);
+        assert_eq!(key_after.position.y, key_before.position.y);
+    }
+
+    let total=motion.total();
+    let last=before.len()-1;
+    assert!((total.position.x-(before[last].position.x-before[0].position.x)).abs()<0.0001);
+    assert!((total.position.z-(before[last].position.z-before[0].position.z)).abs()<0.0001);
+    assert_eq!(total.position.y, 0.0);
+
+    assert!(clip.extract_root_motion("Unknown", RootMotionAxes::horizontal()).is_err());
+}
+
+#[test]
+fn extract_from_translations(){
+    let document=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+    let mut clip=document.clips["idle"].decode().unwrap();
+    let motion=clip.extract_root_motion("Hips", RootMotionAxes::horizontal()).unwrap();
+
+    assert_eq!(motion.times, vec![0.0, 1.0]);
+    assert_eq!(motion.deltas[1].position.x, 2.0);
+    assert_eq!(motion.deltas[1].position.y, 0.0);
+
+    match clip.channels[0].values {
+        ChannelValues::Vec3( ref values ) => assert_eq!(values, &vec![[0.0, 2.0, 0.0], [0.0, 2.0, 0.0]]),
+        _ => panic!("Translation channel is expected"),
+    }
+}
+
+#[test]
+fn extract_yaw(){
+    let half=(45.0f32).to_radians();
+    let yaw=Quaternion::new(0.0, half.sin(), 0.0, half.cos());
+    let pitch=Quaternion::new((15.0f32).to_radians().sin(), 0.0, 0.0, (15.0f32).to_radians().cos());
+
+    //root turns by 90 degrees around Y and leans forward by 30 degrees
+    let locations=vec![
+        Location::new(Position::new(0.0, 1.0, 0.0), Scale::new(1.0, 1.0, 1.0), Quaternion::identity()),
+        Location::new(Position::new(1.0, 1.0, 2.0), Scale::new(1.0, 1.0, 1.0), yaw.clone()*pitch.clone()),
+    ];
+
+    let mut clip=Clip{
+        name:String::from("turn"),
+        duration:1.0,
+        channels:vec![
+            Channel{
+                target:String::from("Root/transform"),
+                times:vec![0.0, 1.0],
+                values:ChannelValues::Location(locations),
+                interpolation:vec![Interpolation::Linear, Interpolation::Linear],
+                in_tangents:Vec::new(),
+                out_tangents:Vec::new(),
+            }
+        ],
+    };
+
+    let mut without_yaw=clip.clone();
+    let motion=clip.extract_root_motion("Root", RootMotionAxes::horizontal()).unwrap();
+
+    assert!(motion.deltas[0].rotation.dot(&Quaternion::identity()).abs()>0.9999);
+    assert!(motion.deltas[1].rotation.dot(&yaw).abs()>0.9999);
+
+    let total=motion.total();
+    assert!(total.rotation.dot(&yaw).abs()>0.9999);
+    assert!((total.position.x-1.0).abs()<0.0001 && (total.position.z-2.0).abs()<0.0001);
+    assert_eq!(total.position.y, 0.0);
+
+    //root keeps its lean, but not its turn and horizontal motion
+    match clip.channels[0].values {
+        ChannelValues::Location( ref locations ) => {
+            assert!(locations[1].rotation.dot(&pitch).abs()>0.9999);
+            assert_eq!((locations[1].position.x, locations[1].position.y, locations[1].position.z), (0.0, 1.0, 0.0));
+        },
+        _ => panic!("Location channel is expected"),
+    }
+
+    let axes=RootMotionAxes{ yaw:false, ..RootMotionAxes::horizontal() };
+    let motion=without_yaw.extract_root_motion("Root", axes).unwrap();
+    assert!(motion.total().rotation.dot(&Quaternion::identity()).abs()>0.9999);
+
+    match without_yaw.channels[0].values {
+        ChannelValues::Location( ref locations ) => assert!(locations[1].rotation.dot(&(yaw*pitch)).abs()>0.9999),
+        _ => panic!("Location channel is expected"),
+    }
+}
@@ -0,0 +1,18 @@
+extern crate collada;
+
+use std::path::Path;
+
+#[test]
+fn node_without_scale(){
+    let document=collada::Document::parse(&Path::new("tests/data/centimeters.dae")).unwrap();
+
+    //node has only <translate>, so it is not scaled
+    let node=document.scenes.get("Scene").unwrap().cameras.get("Camera").unwrap();
+    let scale=&node.location.scale;
+    assert_eq!((scale.x, scale.y, scale.z), (1.0, 1.0, 1.0));
+
+    let matrix=node.location.to_matrix();
+    assert_eq!(matrix.mat[0], 1.0);
+    assert_eq!(matrix.mat[5], 1.0);
+    assert_eq!(matrix.mat[10], 1.0);
+}
@@ -0,0 +1,83 @@
+extern crate collada;
+
+use std::path::Path;
+
+#[test]
+fn hierarchy_navigation(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let skeleton=document.skeletons.get("Guy").unwrap();
+
+    assert_eq!(skeleton.roots(), vec![0]);
+    assert_eq!(skeleton.bones_array[1].children, vec![2,4,6]);
+    assert_eq!(skeleton.parent_chain(7), Some(vec![6,1,0]));
+    assert_eq!(skeleton.parent_chain(0), Some(vec![]));
+    assert_eq!(skeleton.parent_chain(8), None);
+
+    let depth_first:Vec<&str>=skeleton.depth_first().map(|bone| bone.id.as_str()).collect();
+    assert_eq!(depth_first, vec!["Position","Torse","Hand_r","Hand_r_2","Hand_l","Hand_l_2","Neck","Head"]);
+
+    let breadth_first:Vec<&str>=skeleton.breadth_first().map(|bone| bone.id.as_str()).collect();
+    assert_eq!(breadth_first, vec!["Position","Torse","Hand_r","Hand_l","Neck","Hand_r_2","Hand_l_2","Head"]);
+
+    assert_eq!(skeleton.get_bone_by_name("Hand.r.2").unwrap().index, 3);
+    assert_eq!(skeleton.get_bone_by_sid("Hand_r_2").unwrap().index, 3);
+    assert_eq!(skeleton.get_bone_by_id("Hand_r_2").unwrap().index, 3);
+}
+
+#[test]
+fn several_roots(){
+    let document=collada::Document::parse(&Path::new("tests/data/face.dae")).unwrap();
+    let skeleton=document.skeletons.get("Rig").unwrap();
+
+    assert_eq!(skeleton.roots(), vec![0,2]);
+    assert_eq!(skeleton.bones_array[0].children, vec![1]);
+    assert_eq!(skeleton.bones_array[2].id, "IK_Target");
+    assert_eq!(skeleton.breadth_first().count(), 3);
+}
+
+#[test]
+fn prune_unused_bones(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let skeleton=document.skeletons.get("Guy").unwrap();
+    let skin=document.skins.get("Guy_Cube-skin").unwrap();
+
+    let (pruned,remap)=skeleton.prune_unused(&[skin]).unwrap();
+
+    let ids:Vec<&str>=pruned.bones_array.iter().map(|bone| bone.id.as_str()).collect();
+    assert_eq!(ids, vec!["Position","Torse","Hand_l","Hand_l_2"]);
+    assert_eq!(remap, vec![Some(0),Some(1),None,None,Some(2),Some(3),None,None]);
+    assert_eq!(pruned.bones_array[1].children, vec![2]);
+    assert_eq!(pruned.bones_array[3].parent, Some(2));
+
+    let joints=skin.bind(&pruned).unwrap();
+    let bone_indices:Vec<Option<usize>>=joints.iter().map(|joint| joint.bone_index).collect();
+    let joint_names=skin.get_joint_names().unwrap();
+
+    for (joint_name,bone_index) in joint_names.iter().zip(bone_indices.iter()) {
+        match *bone_index {
+            Some( bone_index ) => assert_eq!(&pruned.bones_array[bone_index].sid, joint_name),
+            None => assert!(pruned.get_bone_by_sid(joint_name).is_none()),
+        }
+    }
+}
+
+#[test]
+fn prune_unweighted_joint(){
+    let document=collada::Document::parse(&Path::new("tests/data/arm.dae")).unwrap();
+    let skeleton=document.skeletons.get("Rig").unwrap();
+    let skin=document.skins.get("Arm-skin").unwrap();
+
+    //IK_Pole is joint of skin without weights, Finger is not joint of skin
+    let (pruned,_)=skeleton.prune_unused(&[skin]).unwrap();
+
+    let ids:Vec<&str>=pruned.bones_array.iter().map(|bone| bone.id.as_str()).collect();
+    assert_eq!(ids, vec!["Shoulder","UpperArm","LowerArm","Hand"]);
+
+    let joints=skin.bind(&pruned).unwrap();
+    let bone_indices:Vec<Option<usize>>=joints.iter().map(|joint| joint.bone_index).collect();
+    assert_eq!(bone_indices, vec![Some(0),Some(1),Some(2),Some(3),None]);
+
+    let pose=collada::Pose::rest(&pruned);
+    let palette=pose.matrix_palette(&pruned, &joints).unwrap();
+    assert_eq!(palette[4].mat, collada::Matrix::identity().mat);
+}
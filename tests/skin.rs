@@ -0,0 +1,202 @@
+extern crate collada;
+
+use std::path::Path;
+
+#[test]
+fn bind_skin_to_skeleton(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+
+    let skin=document.skins.get("Guy_Cube-skin").unwrap();
+    let skeleton=document.skeletons.get("Guy").unwrap();
+
+    let joints=skin.bind(skeleton).unwrap();
+    assert_eq!(joints.len(), 8);
+
+    let joint_names=skin.get_joint_names().unwrap();
+    for (joint,joint_name) in joints.iter().zip(joint_names.iter()) {
+        assert_eq!(&skeleton.bones_array[joint.bone_index.unwrap()].sid, joint_name);
+    }
+}
+
+#[test]
+fn fixed_width_influences(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let skin=document.skins.get("Guy_Cube-skin").unwrap();
+
+    let influences=skin.influences::<4>().unwrap();
+    assert_eq!(influences.joints.len(), skin.bones_count_per_vertex.len());
+    assert_eq!(influences.truncated_vertices_count, 0);
+    assert_eq!(influences.max_weight_error, 0.0);
+
+    for (vertex,weights) in influences.weights.iter().enumerate() {
+        assert_eq!(weights[0], 1.0);
+        assert_eq!(weights[1], 0.0);
+        assert_eq!(influences.joints[vertex][0], 5);
+    }
+
+    let influences=skin.influences::<1>().unwrap();
+    assert_eq!(influences.truncated_vertices_count, 0);
+}
+
+#[test]
+fn truncated_influences(){
+    let document=collada::Document::parse(&Path::new("tests/data/arm.dae")).unwrap();
+    let skin=document.skins.get("Arm-skin").unwrap();
+
+    //third vertex has weights 0.6, 0.3 and 0.1
+    let influences=skin.influences::<2>().unwrap();
+    assert_eq!(influences.truncated_vertices_count, 1);
+    assert!((influences.max_weight_error-0.1).abs()<0.0001);
+
+    assert_eq!(influences.joints[2], [1,2]);
+    assert!((influences.weights[2][0]-0.6/0.9).abs()<0.0001);
+    assert!((influences.weights[2][1]-0.3/0.9).abs()<0.0001);
+
+    assert_eq!(influences.joints[0], [0,0]);
+    assert_eq!(influences.weights[0], [1.0,0.0]);
+}
+
+#[test]
+fn skin_node_bound_to_skeleton(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+
+    let node=document.scenes.get("Scene").unwrap().geometries.get("Cube").unwrap();
+    match node.controller {
+        collada::Controller::Skin(ref skin, ref skeleton) => {
+            assert_eq!(skin.id, "Guy_Cube-skin");
+            assert_eq!(skeleton.id, "Guy");
+        },
+        _ => panic!("Cube should be skinned"),
+    }
+
+    for (_,animation) in document.animations.iter() {
+        assert_eq!(animation.samplers[0].skeleton_id, Some(String::from("Guy")));
+    }
+}
+
+#[test]
+fn skin_node_without_skeleton_root(){
+    //both rigs contain joint "Pole", so skeleton of skin can not be chosen without <skeleton>
+    match collada::Document::parse(&Path::new("tests/data/twins.dae")) {
+        Err( collada::Error::Other(message) ) => assert!(message.contains("RigA, RigB"), "{}", message),
+        Err( error ) => panic!("{}", error),
+        Ok( _ ) => panic!("skin with ambiguous skeleton should not be bound"),
+    }
+}
+
+#[test]
+fn skins_by_geometry(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+
+    let skins=document.skins_by_geometry.get("Cube_001-mesh").unwrap();
+    assert_eq!(skins.len(), 1);
+    assert_eq!(skins[0].id, "Guy_Cube-skin");
+}
+
+#[test]
+fn deform_in_rest_pose(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let skin=document.skins.get("Guy_Cube-skin").unwrap();
+    let skeleton=document.skeletons.get("Guy").unwrap();
+    let geometry=document.geometries.get(&skin.geometry_id).unwrap();
+
+    let pose=collada::Pose::rest(skeleton);
+    let linear=skin.deform(geometry, skeleton, &pose, collada::SkinningMethod::Linear).unwrap();
+    let dual_quaternion=skin.deform(geometry, skeleton, &pose, collada::SkinningMethod::DualQuaternion).unwrap();
+
+    let bind_shape_matrix=&skin.bind_shape_matrix;
+    let mesh=&geometry.meshes[0];
+    let vertices=mesh.vertex_indices.get("VERTEX").unwrap();
+
+    let layer=|name:&str| match *vertices.source.layers.get(name).unwrap() {
+        collada::SourceLayer::F32(ref data) => data.clone(),
+        _ => panic!("we expect only f32"),
+    };
+    let (x,y,z)=(layer("X"),layer("Y"),layer("Z"));
+
+    assert_eq!(linear[0].positions.len(), vertices.indices.len());
+    assert_eq!(linear[0].normals.len(), vertices.indices.len());
+
+    for (i,&vertex_index) in vertices.indices.iter().enumerate() {
+        let expected=bind_shape_matrix.transform_point([x[vertex_index], y[vertex_index], z[vertex_index]]);
+
+        for k in 0..3 {
+            assert!((linear[0].positions[i][k]-expected[k]).abs()<0.0001, "{:?} {:?}", linear[0].positions[i], expected);
+            assert!((dual_quaternion[0].positions[i][k]-expected[k]).abs()<0.0001, "{:?} {:?}", dual_quaternion[0].positions[i], expected);
+        }
+    }
+}
+
+#[test]
+fn decompose_half_turns(){
+    //rotations by 180 degrees have negative trace
+    let half_turns=[
+        [1.0,0.0,0.0, 0.0,-1.0,0.0, 0.0,0.0,-1.0],
+        [-1.0,0.0,0.0, 0.0,1.0,0.0, 0.0,0.0,-1.0],
+        [-1.0,0.0,0.0, 0.0,-1.0,0.0, 0.0,0.0,1.0],
+        [0.0,1.0,0.0, 1.0,0.0,0.0, 0.0,0.0,-1.0],
+    ];
+
+    for rotation in half_turns.iter() {
+        let mut mat=[0.0;16];
+        mat[15]=1.0;
+
+        for row in 0..3 {
+            for col in 0..3 {
+                mat[row*4+col]=rotation[row*3+col];
+            }
+        }
+
+        let matrix=collada::Matrix::from(mat);
+        let location=matrix.decompose();
+
+        for v in [[1.0,0.0,0.0],[0.0,1.0,0.0],[0.0,0.0,1.0],[0.3,-0.5,0.8]].iter() {
+            let expected=matrix.transform_vector(*v);
+            let rotated=location.rotation.rotate(*v);
+
+            for i in 0..3 {
+                assert!((expected[i]-rotated[i]).abs()<0.0001, "{:?} != {:?}", rotated, expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn partition_by_joints_count(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let skin=document.skins.get("Guy_Cube-skin").unwrap();
+    let geometry=document.geometries.get(&skin.geometry_id).unwrap();
+
+    let partitions=skin.partition(geometry, 1).unwrap();
+    assert_eq!(partitions.len(), 1);
+    assert_eq!(partitions[0].joints, vec![5]);
+    assert_eq!(partitions[0].mesh.polygons.len(), geometry.meshes[0].polygons.len());
+
+    let mesh=&geometry.meshes[0];
+    let submesh=mesh.subset(&[1]);
+    assert_eq!(submesh.polygons[0].first_vertex_index, 0);
+    assert_eq!(submesh.polygons[0].vertices_count, mesh.polygons[1].vertices_count);
+
+    let first=mesh.polygons[1].first_vertex_index;
+    assert_eq!(submesh.vertex_indices["VERTEX"].indices[..], mesh.vertex_indices["VERTEX"].indices[first..first+mesh.polygons[1].vertices_count]);
+}
+
+#[test]
+fn partition_split_by_joints_count(){
+    let document=collada::Document::parse(&Path::new("tests/data/arm.dae")).unwrap();
+    let skin=document.skins.get("Arm-skin").unwrap();
+    let geometry=document.geometries.get(&skin.geometry_id).unwrap();
+
+    //first triangle is deformed by Shoulder, UpperArm and LowerArm, second by UpperArm and LowerArm, third by LowerArm and Hand
+    let partitions=skin.partition(geometry, 3).unwrap();
+    assert_eq!(partitions.len(), 2);
+
+    assert_eq!(partitions[0].joints, vec![0,1,2]);
+    assert_eq!(partitions[0].mesh.polygons.len(), 2);
+
+    assert_eq!(partitions[1].joints, vec![2,3]);
+    assert_eq!(partitions[1].mesh.polygons.len(), 1);
+    assert_eq!(partitions[1].mesh.vertex_indices["VERTEX"].indices, vec![6,7,8]);
+
+    assert!(skin.partition(geometry, 2).is_err());
+}
@@ -0,0 +1,66 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::{Selector,Target,TargetAddress};
+
+#[test]
+fn parse_addresses(){
+    let address=TargetAddress::parse("Cube/rotationZ.ANGLE").unwrap();
+    assert_eq!(address.id, "Cube");
+    assert_eq!(address.sids, vec![String::from("rotationZ")]);
+    assert_eq!(address.selector, Selector::Member(String::from("ANGLE")));
+
+    let address=TargetAddress::parse("Hand_l/transform(3)(2)").unwrap();
+    assert_eq!(address.sids, vec![String::from("transform")]);
+    assert_eq!(address.selector, Selector::Index2(3,2));
+
+    let address=TargetAddress::parse("Face-morph/Face-morph-weights(1)").unwrap();
+    assert_eq!(address.selector, Selector::Index(1));
+
+    let address=TargetAddress::parse("Lamp.001/point/color").unwrap();
+    assert_eq!(address.id, "Lamp.001");
+    assert_eq!(address.sids.len(), 2);
+    assert_eq!(address.selector, Selector::All);
+
+    assert!(TargetAddress::parse("/transform").is_err());
+    assert!(TargetAddress::parse("Cube//location").is_err());
+    assert!(TargetAddress::parse("Cube/transform(a)").is_err());
+}
+
+#[test]
+fn resolve_targets(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let resolve=|address:&str| TargetAddress::parse(address).unwrap().resolve(&document);
+
+    for (_,animation) in document.animations.iter() {
+        match animation.samplers[0].address.resolve(&document).unwrap() {
+            Target::Bone{skeleton_id, bone_index, transform_sid, selector} => {
+                assert_eq!(skeleton_id, "Guy");
+                assert_eq!(document.skeletons.get("Guy").unwrap().bones_array[bone_index].id, animation.samplers[0].bone_id);
+                assert_eq!(transform_sid, "transform");
+                assert_eq!(selector, Selector::All);
+            },
+            _ => panic!("channels of scene.dae animate bones"),
+        }
+    }
+
+    assert_eq!(resolve("Camera-camera/xfov").unwrap(), Target::Camera{camera_id:String::from("Camera-camera"), parameter:String::from("xfov"), selector:Selector::All});
+    assert_eq!(resolve("Lamp-light/color.R").unwrap(), Target::Light{light_id:String::from("Lamp-light"), parameter:String::from("color"), selector:Selector::Member(String::from("R"))});
+    assert_eq!(resolve("Lamp/location.X").unwrap(), Target::NodeTransform{node_id:String::from("Lamp"), transform_sid:String::from("location"), selector:Selector::Member(String::from("X"))});
+    assert!(resolve("Missing/location").is_err());
+    assert!(resolve("Lamp/transform").is_err());
+    assert!(resolve("Position/location").is_err());
+
+    //"." is element, that contains animation
+    let relative=TargetAddress::parse("./rotationZ.ANGLE").unwrap();
+    assert!(relative.resolve(&document).is_err());
+    assert_eq!(relative.resolve_from(&document, "Lamp").unwrap(), Target::NodeTransform{node_id:String::from("Lamp"), transform_sid:String::from("rotationZ"), selector:Selector::Member(String::from("ANGLE"))});
+
+    let document=collada::Document::parse(&Path::new("tests/data/face.dae")).unwrap();
+    let resolve=|address:&str| TargetAddress::parse(address).unwrap().resolve(&document);
+
+    assert_eq!(resolve("Face-morph/Face-morph-weights(0)").unwrap(), Target::MorphWeight{morph_id:String::from("Face-morph"), index:0});
+    assert!(resolve("Face-morph/Face-morph-weights(1)").is_err());
+    assert_eq!(resolve("Skin-effect/diffuse").unwrap(), Target::Material{material_id:String::from("Skin-material"), parameter:String::from("diffuse"), selector:Selector::All});
+}
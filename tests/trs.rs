@@ -0,0 +1,108 @@
+extern crate collada;
+
+use std::path::Path;
+
+use collada::{Channel,ChannelValue,ChannelValues,Interpolation,SampleMode,Tangent,TrackValues,CURVE_FPS};
+
+fn assert_close(a:&[f32;3], b:&[f32;3]){
+    for i in 0..3 {
+        assert!((a[i]-b[i]).abs()<0.0001, "{:?} != {:?}", a, b);
+    }
+}
+
+#[test]
+fn split_matrix_tracks(){
+    let document=collada::Document::parse(&Path::new("scene.dae")).unwrap();
+    let mut tracks_count=0;
+
+    for animation in document.animations.values() {
+        let channel=&animation.channels().unwrap()[0];
+        let tracks=animation.to_trs_tracks().unwrap();
+        tracks_count+=tracks.len();
+
+        for track in tracks.iter() {
+            //value of key in the middle is value of channel at its time
+            let key=track.times.len()/2;
+            let location=match channel.sample(track.times[key], SampleMode::Clamp) {
+                ChannelValue::Location( location ) => location,
+                _ => panic!("Matrix channel must be sampled into location"),
+            };
+
+            match track.values {
+                TrackValues::Translation( ref values ) =>
+                    assert_close(&values[key], &[location.position.x, location.position.y, location.position.z]),
+                TrackValues::Rotation( ref values ) =>
+                    assert!(values[key].dot(&location.rotation).abs()>0.9999),
+                TrackValues::Scale( ref values ) =>
+                    assert_close(&values[key], &[location.scale.x, location.scale.y, location.scale.z]),
+            }
+
+            assert!(!track.is_constant());
+            assert_eq!(track.times.len(), track.values.get_length());
+            assert_eq!(track.times.len(), track.interpolation.len());
+
+            match track.values {
+                TrackValues::Rotation( ref rotations ) =>
+                    for pair in rotations.windows(2) {
+                        assert!(pair[0].dot(&pair[1])>=0.0);
+                    },
+                _ => {},
+            }
+        }
+    }
+
+    assert!(tracks_count>0);
+}
+
+#[test]
+fn bake_curves(){
+    let tangent=|value:[f32;3]| Tangent{ time:None, value:ChannelValue::Vec3(value) };
+
+    //ease in and out from 0 to 1
+    let channel=Channel{
+        target:String::from("Hips/translate"),
+        times:vec![0.0, 1.0],
+        values:ChannelValues::Vec3(vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]]),
+        interpolation:vec![Interpolation::Bezier, Interpolation::Bezier],
+        in_tangents:vec![tangent([0.0, 0.0, 0.0]), tangent([1.0, 0.0, 0.0])],
+        out_tangents:vec![tangent([0.0, 0.0, 0.0]), tangent([1.0, 0.0, 0.0])],
+    };
+
+    let tracks=channel.to_trs_tracks().unwrap();
+    assert_eq!(tracks.len(), 1);
+
+    let track=&tracks[0];
+    assert_eq!(track.times.len(), CURVE_FPS as usize+1);
+    assert!(track.interpolation.iter().all(|interpolation| *interpolation==Interpolation::Linear));
+
+    //curve is slow at the start, so baked key is below line
+    let key=3;
+    let expected=match channel.sample(track.times[key], SampleMode::Clamp) {
+        ChannelValue::Vec3( value ) => value,
+        _ => panic!("Translation must be sampled into vec3"),
+    };
+
+    match track.values {
+        TrackValues::Translation( ref values ) => {
+            assert_close(&values[key], &expected);
+            //3s²(1-s)+s³ at s=0.1
+            assert!((values[key][0]-0.028).abs()<0.0001);
+        },
+        _ => panic!("Translation track is expected"),
+    }
+}
+
+#[test]
+fn vec3_tracks(){
+    let document=collada::Document::parse(&Path::new("tests/data/rig.dae")).unwrap();
+    let tracks=document.animations["Hips-anim"].to_trs_tracks().unwrap();
+
+    assert_eq!(tracks.len(), 1);
+    assert_eq!(tracks[0].node_id, "Hips");
+    assert_eq!(tracks[0].times, vec![0.0, 1.0, 2.0]);
+
+    match tracks[0].values {
+        TrackValues::Translation( ref values ) => assert_eq!(values[2], [2.0, 2.0, 0.0]),
+        _ => panic!("Translation track is expected"),
+    }
+}
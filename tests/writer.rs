@@ -0,0 +1,253 @@
+extern crate collada;
+
+use std::path::{Path,PathBuf};
+use std::fs::File;
+use std::sync::Arc;
+
+use collada::{Animation,ChannelValue,ChannelValues,Controller,Document,Matrix,SourceLayer,Tangent,WriteOptions};
+
+fn rewrite(file_name:&str, options:WriteOptions) -> Document {
+    let document=Document::parse(&Path::new(file_name)).unwrap();
+
+    let name=Path::new(file_name).file_stem().unwrap().to_str().unwrap().to_string();
+    let mut path=PathBuf::from(std::env::temp_dir());
+    path.push(format!("collada_writer_{}_{}.dae", name, options.triangles));
+
+    {
+        let mut file=File::create(&path).unwrap();
+        document.write(&mut file, options).unwrap();
+    }
+
+    let written=Document::parse(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    written
+}
+
+fn assert_close(a:f32, b:f32){
+    assert!((a-b).abs()<0.001, "{} != {}", a, b);
+}
+
+fn assert_same_values(a:&ChannelValues, b:&ChannelValues){
+    assert_eq!(a.get_length(), b.get_length());
+
+    match (a,b) {
+        (&ChannelValues::Float(ref a), &ChannelValues::Float(ref b)) =>
+            for (a,b) in a.iter().zip(b.iter()) { assert_close(*a, *b); },
+        (&ChannelValues::Vec3(ref a), &ChannelValues::Vec3(ref b)) =>
+            for (a,b) in a.iter().zip(b.iter()) { for i in 0..3 { assert_close(a[i], b[i]); } },
+        (&ChannelValues::Location(ref a), &ChannelValues::Location(ref b)) =>
+            for (a,b) in a.iter().zip(b.iter()) {
+                let (a,b)=(a.to_matrix(), b.to_matrix());
+                for i in 0..16 { assert_close(a.mat[i], b.mat[i]); }
+            },
+        _ => panic!("Values have different types"),
+    }
+}
+
+fn assert_same_matrices(a:&Matrix, b:&Matrix){
+    for i in 0..16 { assert_close(a.mat[i], b.mat[i]); }
+}
+
+fn assert_same_tangents(a:&[Tangent], b:&[Tangent]){
+    assert_eq!(a.len(), b.len());
+
+    for (a,b) in a.iter().zip(b.iter()) {
+        assert_eq!(a.time, b.time);
+
+        match (&a.value, &b.value) {
+            (&ChannelValue::Float( a ), &ChannelValue::Float( b )) => assert_close(a, b),
+            (&ChannelValue::Vec3( a ), &ChannelValue::Vec3( b )) => for i in 0..3 { assert_close(a[i], b[i]); },
+            _ => panic!("Tangents have different types"),
+        }
+    }
+}
+
+fn assert_same_documents(document:&Document, written:&Document){
+    let mut ids:Vec<&String>=document.geometries.keys().collect();
+    ids.sort();
+    let mut written_ids:Vec<&String>=written.geometries.keys().collect();
+    written_ids.sort();
+    assert_eq!(ids, written_ids);
+
+    for (id,geometry) in document.geometries.iter() {
+        let other=written.geometries.get(id).unwrap();
+        assert_eq!(geometry.meshes.len(), other.meshes.len());
+
+        for (mesh,other) in geometry.meshes.iter().zip(other.meshes.iter()) {
+            assert_eq!(mesh.material, other.material);
+            assert_eq!(mesh.short_vertex_format, other.short_vertex_format);
+            assert_eq!(mesh.polygons.len(), other.polygons.len());
+
+            for (polygon,other) in mesh.polygons.iter().zip(other.polygons.iter()) {
+                assert_eq!(polygon.first_vertex_index, other.first_vertex_index);
+                assert_eq!(polygon.vertices_count, other.vertices_count);
+            }
+
+            for (&(ref semantic,ref source),&(_,ref other)) in mesh.sources.iter().zip(other.sources.iter()) {
+                for (name,layer) in source.layers.iter() {
+                    match (layer, other.layers.get(name).unwrap()) {
+                        (&SourceLayer::F32(ref a), &SourceLayer::F32(ref b)) =>
+                            for (a,b) in a.iter().zip(b.iter()) { assert_close(*a, *b); },
+                        _ => panic!("Layer {} of {} has other type", name, semantic),
+                    }
+                }
+            }
+
+            for (semantic,indices) in mesh.vertex_indices.iter() {
+                assert_eq!(indices.indices, other.vertex_indices.get(semantic).unwrap().indices);
+            }
+        }
+    }
+
+    assert_eq!(document.cameras.len(), written.cameras.len());
+    for (id,camera) in document.cameras.iter() {
+        let other=written.cameras.get(id).unwrap();
+        assert_eq!(camera.perspective.x_fov.is_some(), other.perspective.x_fov.is_some());
+        assert_close(camera.perspective.vertical_fov().unwrap_or(0.0), other.perspective.vertical_fov().unwrap_or(0.0));
+        assert_close(camera.perspective.aspect().unwrap_or(0.0), other.perspective.aspect().unwrap_or(0.0));
+        assert_close(camera.perspective.z_far, other.perspective.z_far);
+    }
+
+    assert_eq!(document.skeletons.len(), written.skeletons.len());
+    for (id,skeleton) in document.skeletons.iter() {
+        let other=written.skeletons.get(id).unwrap();
+        assert_eq!(skeleton.bones_array.len(), other.bones_array.len());
+
+        for (bone,other) in skeleton.bones_array.iter().zip(other.bones_array.iter()) {
+            assert_eq!(bone.id, other.id);
+            assert_eq!(bone.name, other.name);
+            assert_eq!(bone.parent, other.parent);
+
+            assert_same_matrices(&bone.matrix, &other.matrix);
+        }
+    }
+
+    assert_eq!(document.skins.len(), written.skins.len());
+    for (id,skin) in document.skins.iter() {
+        let other=written.skins.get(id).unwrap();
+        assert_eq!(skin.geometry_id, other.geometry_id);
+        assert_eq!(skin.bones_count_per_vertex.len(), other.bones_count_per_vertex.len());
+        assert_eq!(skin.morph.is_some(), other.morph.is_some());
+        assert_eq!(skin.get_joint_names().unwrap(), other.get_joint_names().unwrap());
+        assert_same_matrices(&skin.bind_shape_matrix, &other.bind_shape_matrix);
+
+        assert_eq!(skin.inverse_bind_matrices.len(), other.inverse_bind_matrices.len());
+        for (a,b) in skin.inverse_bind_matrices.iter().zip(other.inverse_bind_matrices.iter()) {
+            assert_same_matrices(a, b);
+        }
+
+        let (influences,other_influences)=(skin.influences::<4>().unwrap(), other.influences::<4>().unwrap());
+        assert_eq!(influences.joints, other_influences.joints);
+        assert_eq!(influences.weights.len(), other_influences.weights.len());
+
+        for (a,b) in influences.weights.iter().zip(other_influences.weights.iter()) {
+            for i in 0..4 { assert_close(a[i], b[i]); }
+        }
+    }
+
+    for (id,morph) in document.morphs.iter() {
+        let other=written.morphs.get(id).unwrap();
+        assert_eq!(morph.method, other.method);
+        assert_eq!(morph.targets, other.targets);
+        assert_eq!(morph.weights, other.weights);
+    }
+
+    assert_eq!(document.effects.len(), written.effects.len());
+    for (id,effect) in document.effects.iter() {
+        let other=written.effects.get(id).unwrap();
+        assert_eq!(effect.shading, other.shading);
+        assert_eq!(effect.diffuse, other.diffuse);
+        assert_eq!(effect.shininess, other.shininess);
+        assert_eq!(effect.opacity, other.opacity);
+    }
+
+    //channels of parts of nodes, like `translate`, are baked into matrix channels of nodes
+    assert_eq!(document.animations.len(), written.animations.len());
+    for (id,animation) in document.animations.iter() {
+        let channels=animation.channels().unwrap();
+        let other=written.animations.get(id).unwrap().channels().unwrap();
+        let is_baked=|target:&str| channels.iter().any(|channel| channel.target.starts_with(&format!("{}/", target.split('/').next().unwrap())) && !channel.target.ends_with("/transform"));
+
+        for channel in channels.iter().filter(|channel| !is_baked(&channel.target)) {
+            let other=other.iter().find(|other| other.target==channel.target).unwrap();
+
+            assert_eq!(channel.times, other.times);
+            assert_eq!(channel.interpolation, other.interpolation);
+            assert_same_values(&channel.values, &other.values);
+            assert_same_tangents(&channel.in_tangents, &other.in_tangents);
+            assert_same_tangents(&channel.out_tangents, &other.out_tangents);
+        }
+    }
+
+    //baked channels give same poses at keys
+    let animations:Vec<Arc<Animation>>=document.animations.values().cloned().collect();
+    let written_animations:Vec<Arc<Animation>>=written.animations.values().cloned().collect();
+
+    let mut times:Vec<f32>=animations.iter().flat_map(|animation| animation.channels().unwrap()).flat_map(|channel| channel.times).collect();
+    times.push(0.0);
+
+    for (id,skeleton) in document.skeletons.iter() {
+        let other=written.skeletons.get(id).unwrap();
+
+        for time in times.iter() {
+            let (pose,other_pose)=(skeleton.pose_at(&animations, *time).unwrap(), other.pose_at(&written_animations, *time).unwrap());
+
+            for (a,b) in pose.locations.iter().zip(other_pose.locations.iter()) {
+                assert_same_matrices(&a.to_matrix(), &b.to_matrix());
+            }
+        }
+    }
+
+    assert_eq!(document.clips.len(), written.clips.len());
+    for (id,clip) in document.clips.iter() {
+        let other=written.clips.get(id).unwrap();
+        assert_eq!(clip.name, other.name);
+        assert_eq!(clip.start, other.start);
+        assert_eq!(clip.end, other.end);
+        assert_eq!(clip.animations.len(), other.animations.len());
+    }
+}
+
+#[test]
+fn write_and_read_back(){
+    for file_name in ["scene.dae", "tests/data/face.dae", "tests/data/rig.dae", "tests/data/centimeters.dae"].iter() {
+        let document=Document::parse(&Path::new(file_name)).unwrap();
+        let written=rewrite(file_name, WriteOptions::default());
+
+        assert_same_documents(&document, &written);
+    }
+
+    //channels of translate and rotateY point at sid, that written nodes have
+    let written=rewrite("tests/data/rig.dae", WriteOptions::default());
+    let mut targets:Vec<String>=written.animations["Hips-anim"].channels().unwrap().into_iter().map(|channel| channel.target).collect();
+    targets.sort();
+    assert_eq!(targets, vec!["Hips/transform", "Spine/transform"]);
+
+    let written=rewrite("tests/data/face.dae", WriteOptions::default());
+    let scene=written.scenes.get("Scene").unwrap();
+
+    match scene.geometries.get("Face").unwrap().controller {
+        Controller::Skin(ref skin, ref skeleton) => {
+            assert_eq!(skin.id, "Face-skin");
+            assert_eq!(skeleton.id, "Rig");
+        },
+        _ => panic!("Face should be skinned"),
+    }
+}
+
+#[test]
+fn write_triangles(){
+    let options=WriteOptions{ triangles:true, indent:false };
+
+    let document=Document::parse(&Path::new("scene.dae")).unwrap();
+    let written=rewrite("scene.dae", options);
+
+    assert_same_documents(&document, &written);
+
+    let mut text=Vec::new();
+    document.write(&mut text, options).unwrap();
+    let text=String::from_utf8(text).unwrap();
+
+    assert!(text.contains("<triangles"));
+}